@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE flow ADD COLUMN fee numeric(20,8) NOT NULL DEFAULT 0
+;
@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE flow ADD COLUMN order_id integer
+;
@@ -0,0 +1,12 @@
+-- Add migration script here
+DROP TABLE IF EXISTS funding_rate
+;
+CREATE TABLE funding_rate
+(
+    symbol integer NOT NULL,
+    time timestamp with time zone NOT NULL,
+    rate numeric(20,8) NOT NULL,
+    mark_price numeric(20,8),
+    CONSTRAINT funding_rate_pkey PRIMARY KEY (symbol, time)
+)
+;
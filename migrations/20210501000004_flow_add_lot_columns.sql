@@ -0,0 +1,7 @@
+-- Add migration script here
+ALTER TABLE flow ADD COLUMN entry_price numeric(20,8)
+;
+ALTER TABLE flow ADD COLUMN realized_pnl numeric(20,8)
+;
+ALTER TABLE flow ADD COLUMN holding_seconds bigint
+;
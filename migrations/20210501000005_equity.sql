@@ -0,0 +1,12 @@
+-- Add migration script here
+DROP TABLE IF EXISTS equity
+;
+CREATE TABLE equity
+(
+    position integer NOT NULL,
+    time timestamp with time zone NOT NULL,
+    equity numeric(20,8) NOT NULL,
+    exposed boolean NOT NULL,
+    CONSTRAINT equity_pkey PRIMARY KEY (position, time)
+)
+;
@@ -0,0 +1,11 @@
+-- Add migration script here
+DROP TABLE IF EXISTS back_test_selection
+;
+CREATE TABLE back_test_selection
+(
+    position integer NOT NULL,
+    candles_selection text NOT NULL,
+    pair_symbol integer,
+    CONSTRAINT back_test_selection_pkey PRIMARY KEY (position)
+)
+;
@@ -1,11 +1,14 @@
 use crate::config::{definition::ConfigDefinition, selection::Selection};
+use crate::repository::trade_agg_repository::TradeAggRepository;
 use crate::services::provider::candles_provider::CandlesProvider;
 use crate::services::provider::candles_provider_buffer::CandlesProviderBuffer;
 use crate::services::provider::candles_provider_buffer_singleton::CandlesProviderBufferSingleton;
 use crate::services::provider::candles_provider_selection::CandlesProviderSelection;
-use crate::services::script::script_back_test::run_script;
+use crate::services::script::script_back_test::{plot_script, run_script, BackTestSettings};
+use crate::services::script::script_optimize::{optimize_script, Objective, ParamRange, Search};
+use crate::services::tec_plotter::chart_pattern_plotter::ChartPatternPlotter;
 use crate::services::technicals::top_bottom_tec::TopBottomTec;
-use crate::services::trading::top_bottom_triangle::top_bottom_triangle;
+use crate::services::trading::chart_pattern::{chart_patterns, PATTERN_TOLERANCE};
 use crate::utils::date_utils::datetime_to_filename;
 use crate::Exchange;
 use crate::Streamer;
@@ -13,7 +16,6 @@ use crate::{
     repository::candle_repository::CandleRepository,
     services::tec_plotter::plot_selection::plot_selection,
 };
-use chrono::Duration;
 use log::info;
 use sqlx::PgPool;
 use std::sync::{Arc, RwLock};
@@ -23,13 +25,20 @@ pub struct Application {
     pub definition: ConfigDefinition,
     pub selection: Selection,
     pub candles_provider: CandlesProviderBuffer,
+    pub trade_agg_repository: TradeAggRepository,
 }
 
 impl Application {
-    pub fn new(repository: CandleRepository, exchange: Exchange, selection: Selection) -> Self {
+    pub fn new(
+        repository: CandleRepository,
+        trade_agg_repository: TradeAggRepository,
+        exchange: Exchange,
+        selection: Selection,
+    ) -> Self {
         let candles_provider_singleton = CandlesProviderBufferSingleton::new(repository, exchange);
         Application {
             candles_provider: CandlesProviderBuffer::new(candles_provider_singleton),
+            trade_agg_repository,
             selection,
             definition: ConfigDefinition::new(),
         }
@@ -47,18 +56,62 @@ impl Application {
         self.selection = selection;
     }
 
-    pub fn run_script_test(&mut self, pool: Arc<RwLock<PgPool>>, file: &str) -> eyre::Result<()> {
-        run_script(pool, self, file)?;
+    /// Candles of the pair symbol over the selection
+    pub fn pair_candles_provider(&self) -> Option<Box<dyn CandlesProvider>> {
+        self.selection.pair_symbol_opt.map(|symbol| {
+            let mut candles_selection = self.selection.candles_selection;
+            candles_selection.symbol_minutes.symbol = symbol;
+            Box::new(CandlesProviderSelection::new(
+                self.candles_provider.clone(),
+                candles_selection,
+            )) as Box<dyn CandlesProvider>
+        })
+    }
+
+    pub fn run_script_test(
+        &mut self,
+        pool: Arc<RwLock<PgPool>>,
+        file: &str,
+        settings: BackTestSettings,
+        plot_equity: bool,
+    ) -> eyre::Result<()> {
+        run_script(pool, self, file, settings, plot_equity)?;
         Ok(())
     }
 
-    pub fn plot_triangles(&mut self) -> eyre::Result<()> {
+    pub fn optimize_script_test(
+        &mut self,
+        pool: Arc<RwLock<PgPool>>,
+        file: &str,
+        settings: BackTestSettings,
+        ranges: &[ParamRange],
+        search: Search,
+        objective: Objective,
+    ) -> eyre::Result<()> {
+        optimize_script(pool, self, file, settings, ranges, search, objective)?;
+        Ok(())
+    }
+
+    pub fn plot_script_test(
+        &mut self,
+        pool: Arc<RwLock<PgPool>>,
+        file: &str,
+        plot_equity: bool,
+    ) -> eyre::Result<()> {
+        plot_script(pool, self, file, plot_equity)
+    }
+
+    pub fn plot_patterns(&mut self) -> eyre::Result<()> {
         let selection = self.selection.clone();
         let candles_selection = selection.candles_selection;
         let candles_provider_selection =
             CandlesProviderSelection::new(self.candles_provider.clone(), candles_selection);
         let candles_provider = Box::new(candles_provider_selection);
-        plot_triangles(selection, candles_provider)
+        plot_patterns(
+            selection,
+            candles_provider,
+            self.trade_agg_repository.clone(),
+        )
     }
 
     pub fn run_stream(&mut self) -> eyre::Result<()> {
@@ -73,34 +126,51 @@ impl Application {
             selection.candles_selection,
         );
         let candles_provider = Box::new(candles_provider_selection);
-        plot_selection(selection, candles_provider, Vec::new())
+        plot_selection(
+            selection,
+            candles_provider,
+            Some(self.trade_agg_repository.clone()),
+            self.pair_candles_provider(),
+            Vec::new(),
+        )
     }
 }
 
-pub fn plot_triangles(
-    selection: Selection,
+pub fn plot_patterns(
+    mut selection: Selection,
     mut candles_provider: Box<dyn CandlesProvider>,
+    trade_agg_repository: TradeAggRepository,
 ) -> eyre::Result<()> {
     let candles = candles_provider.candles()?;
     let topbottom_tac = TopBottomTec::new(&candles, candles.len(), 7);
     let top_bottoms = topbottom_tac.top_bottoms()?;
 
-    let top_bottoms = top_bottoms.iter().collect::<Vec<_>>();
-    let top_bottoms_ref = top_bottoms.as_slice();
-
-    let minutes = selection.candles_selection.symbol_minutes.minutes;
-
-    let triangles = top_bottom_triangle(top_bottoms_ref, minutes);
-    triangles.iter().for_each(|triangle| {
-        let mut selection_par = selection.clone();
-        let open_time = triangle.open(minutes);
-        let margin = Duration::minutes(minutes as i64 * 100);
-        selection_par.candles_selection.start_time = open_time - margin;
-        selection_par.candles_selection.end_time = open_time + margin;
-        selection_par.image_name = format!("out/triangle_{}.png", datetime_to_filename(&open_time));
-        info!("Plotting triangle {}", selection_par.image_name);
-
-        plot_selection(selection_par, candles_provider.clone_provider(), Vec::new()).unwrap();
+    let patterns = chart_patterns(&top_bottoms, PATTERN_TOLERANCE);
+    patterns.iter().for_each(|pattern| {
+        info!(
+            "Pattern {} {} - {} breakout {:.2} quality {:.2}",
+            pattern.pattern_type,
+            pattern.start_time,
+            pattern.end_time,
+            pattern.breakout,
+            pattern.quality
+        )
     });
-    Ok(())
+
+    selection.image_name = format!(
+        "out/patterns_{}.png",
+        datetime_to_filename(&selection.candles_selection.start_time)
+    );
+    info!(
+        "Plotting {} patterns {}",
+        patterns.len(),
+        selection.image_name
+    );
+    plot_selection(
+        selection,
+        candles_provider,
+        Some(trade_agg_repository),
+        None,
+        vec![Box::new(ChartPatternPlotter::new(&patterns))],
+    )
 }
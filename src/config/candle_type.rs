@@ -0,0 +1,82 @@
+use eyre::bail;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Transformation applied to the exchange candles
+#[derive(Serialize, Deserialize, Hash, Eq, PartialEq, PartialOrd, Debug, Clone, Copy, Default)]
+pub enum CandleType {
+    Raw,
+    #[default]
+    HeikinAshi,
+    /// Renko bricks of fixed size
+    Renko(Decimal),
+    /// Renko bricks sized by the average true range of the first `period` candles of the
+    /// selection, fixed afterwards
+    RenkoAtr(usize),
+    /// Bars of fixed high low range
+    RangeBar(Decimal),
+    /// Line break reversing after the extreme of the last `lines` lines
+    LineBreak(usize),
+}
+
+impl CandleType {
+    /// Candles with one candle per timeframe, bricks have irregular timestamps
+    pub fn is_time_based(&self) -> bool {
+        matches!(self, CandleType::Raw | CandleType::HeikinAshi)
+    }
+}
+
+/// Parses `raw`, `ha`, `renko:<size>`, `renko_atr:<period>`, `range:<size>` or
+/// `line_break:<lines>`
+impl FromStr for CandleType {
+    type Err = eyre::Error;
+
+    fn from_str(value: &str) -> eyre::Result<Self> {
+        let (name, param) = match value.split_once(':') {
+            Some((name, param)) => (name, Some(param)),
+            None => (value, None),
+        };
+        let candle_type = match (name, param) {
+            ("raw", None) => CandleType::Raw,
+            ("ha", None) => CandleType::HeikinAshi,
+            ("renko", Some(size)) => CandleType::Renko(Decimal::from_str(size)?),
+            ("renko_atr", Some(period)) => CandleType::RenkoAtr(period.parse()?),
+            ("range", Some(size)) => CandleType::RangeBar(Decimal::from_str(size)?),
+            ("line_break", Some(lines)) => CandleType::LineBreak(lines.parse()?),
+            ("line_break", None) => CandleType::LineBreak(3),
+            _ => bail!("Unknown candle type {}", value),
+        };
+        match candle_type {
+            CandleType::Renko(size) | CandleType::RangeBar(size) if size <= dec!(0) => {
+                bail!("Candle type {} needs a positive size", value)
+            }
+            CandleType::RenkoAtr(0) | CandleType::LineBreak(0) => {
+                bail!("Candle type {} needs a positive count", value)
+            }
+            _ => Ok(candle_type),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn candle_type_from_str_test() {
+        assert_eq!(CandleType::from_str("ha").unwrap(), CandleType::HeikinAshi);
+        assert_eq!(
+            CandleType::from_str("renko:12.5").unwrap(),
+            CandleType::Renko(dec!(12.5))
+        );
+        assert_eq!(
+            CandleType::from_str("line_break").unwrap(),
+            CandleType::LineBreak(3)
+        );
+        assert!(CandleType::from_str("renko").is_err());
+        assert!(CandleType::from_str("range:0").is_err());
+        assert!(!CandleType::RenkoAtr(14).is_time_based());
+    }
+}
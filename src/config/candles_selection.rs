@@ -1,15 +1,45 @@
+use super::candle_type::CandleType;
 use super::symbol_minutes::SymbolMinutes;
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Hash, Eq, PartialEq, PartialOrd, Debug, Clone, Copy)]
+#[serde(from = "StoredCandlesSelection")]
 pub struct CandlesSelection {
     pub symbol_minutes: SymbolMinutes,
     #[serde(with = "my_date_format")]
     pub start_time: DateTime<Utc>,
     #[serde(with = "my_date_format")]
     pub end_time: DateTime<Utc>,
-    pub heikin_ashi: bool,
+    pub candle_type: CandleType,
+}
+
+/// Selection as stored, selections stored before candle types have a `heikin_ashi` flag instead
+#[derive(Deserialize)]
+struct StoredCandlesSelection {
+    symbol_minutes: SymbolMinutes,
+    #[serde(with = "my_date_format")]
+    start_time: DateTime<Utc>,
+    #[serde(with = "my_date_format")]
+    end_time: DateTime<Utc>,
+    candle_type: Option<CandleType>,
+    heikin_ashi: Option<bool>,
+}
+
+impl From<StoredCandlesSelection> for CandlesSelection {
+    fn from(stored: StoredCandlesSelection) -> Self {
+        let candle_type = match (stored.candle_type, stored.heikin_ashi) {
+            (Some(candle_type), _) => candle_type,
+            (None, Some(false)) => CandleType::Raw,
+            (None, _) => CandleType::HeikinAshi,
+        };
+        Self {
+            symbol_minutes: stored.symbol_minutes,
+            start_time: stored.start_time,
+            end_time: stored.end_time,
+            candle_type,
+        }
+    }
 }
 
 impl CandlesSelection {
@@ -21,7 +51,7 @@ impl CandlesSelection {
             symbol_minutes: SymbolMinutes::new(symbol, minutes),
             start_time,
             end_time,
-            heikin_ashi: true,
+            candle_type: CandleType::default(),
         }
     }
 
@@ -35,7 +65,7 @@ impl CandlesSelection {
             symbol_minutes: SymbolMinutes::new(symbol, minutes),
             start_time,
             end_time,
-            heikin_ashi: true,
+            candle_type: CandleType::default(),
         }
     }
 
@@ -129,3 +159,39 @@ mod my_date_format {
 //         Ok(result)
 //     }
 // }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn stored_candles_selection_test() {
+        let json = |field: &str| {
+            format!(
+                r#"{{"symbol_minutes":{{"symbol":1,"minutes":15}},"start_time":"2020-11-01 00:00:00","end_time":"2020-12-01 00:00:00"{}}}"#,
+                field
+            )
+        };
+        let candle_type = |field: &str| {
+            serde_json::from_str::<CandlesSelection>(&json(field))
+                .unwrap()
+                .candle_type
+        };
+        assert_eq!(
+            candle_type(r#","heikin_ashi":true"#),
+            CandleType::HeikinAshi
+        );
+        assert_eq!(candle_type(r#","heikin_ashi":false"#), CandleType::Raw);
+        assert_eq!(candle_type(""), CandleType::HeikinAshi);
+
+        let selection = CandlesSelection {
+            candle_type: CandleType::LineBreak(3),
+            ..serde_json::from_str(&json("")).unwrap()
+        };
+        let stored = serde_json::to_string(&selection).unwrap();
+        assert_eq!(
+            serde_json::from_str::<CandlesSelection>(&stored).unwrap(),
+            selection
+        );
+    }
+}
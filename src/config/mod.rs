@@ -1,3 +1,4 @@
+pub mod candle_type;
 pub mod candles_selection;
 pub mod definition;
 pub mod selection;
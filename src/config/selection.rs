@@ -7,6 +7,9 @@ pub struct Selection {
     pub tacs: HashMap<String, TacDefinition>,
     pub candles_selection: CandlesSelection,
     pub image_name: String,
+    /// Other symbol of the pair technicals
+    #[serde(default)]
+    pub pair_symbol_opt: Option<i32>,
 }
 
 impl Selection {
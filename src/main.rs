@@ -13,15 +13,32 @@ use crate::app::Application;
 use crate::repository::candle_repository::CandleRepository;
 use crate::repository::pool_factory::create_pool;
 use crate::repository::symbol_repository::SymbolRepository;
+use crate::repository::trade_agg_repository::TradeAggRepository;
 use crate::services::candles_checker::CandlesChecker;
+use crate::services::funding_rates_checker::FundingRatesChecker;
+use crate::services::script::script_back_test::BackTestSettings;
+use crate::services::script::script_optimize::{Objective, ParamRange, Search};
 use crate::services::streamer::Streamer;
+use crate::services::technicals::divergence_tec::DivergenceTec;
 use crate::services::technicals::ema_tec::EmaTec;
+use crate::services::technicals::ind_registry::REGISTRY;
 use crate::services::technicals::macd_tec::MacdTec;
+use crate::services::technicals::pair_tec::{PairRatioTec, PairSpreadTec};
+use crate::services::technicals::support_resistance_tec::SupportResistanceTec;
+use crate::services::technicals::volume_profile_tec::VolumeProfileTec;
+use crate::services::technicals::volume_tec::VolumeTec;
+use crate::services::technicals::vwap_tec::VwapTec;
 use crate::services::trade_aggs_checker::TradeAggsChecker;
+use crate::services::trading::cost_model::{CostModel, Slippage};
+use crate::services::trading::execution::Execution;
+use crate::services::trading::fill_source::FillSource;
+use crate::services::trading::lots::LotMode;
+use crate::services::trading::margin::{Margin, MarginMode};
 use crate::utils::date_utils::str_to_datetime;
-use config::{candles_selection::CandlesSelection, selection::Selection};
+use config::{candle_type::CandleType, candles_selection::CandlesSelection, selection::Selection};
 use eyre::Result;
 use log::{info, Level, LevelFilter};
+use rust_decimal::Decimal;
 use services::provider::trade_history_provider::TradeHistoryProvider;
 use services::{
     exchange::Exchange,
@@ -55,6 +72,12 @@ struct Args {
     /// End date time
     #[structopt(short, long, default_value = "2020-12-01 00:00:00")]
     end_time: String,
+    /// Candle type (raw, ha, renko:<size>, renko_atr:<period>, range:<size>, line_break:<lines>)
+    #[structopt(short, long = "candles", default_value = "ha")]
+    candle_type: CandleType,
+    /// Other symbol of pair technicals (e.g. BTCUSDT)
+    #[structopt(short, long)]
+    pair: Option<String>,
     #[structopt(subcommand)]
     command: Commands,
 }
@@ -66,10 +89,13 @@ enum Commands {
     Candle(Candle),
     /// Trade commands
     Trade(Trade),
+    /// Funding rate commands
+    Funding(Funding),
     /// Plot graph
     Plot {},
-    /// Triangle
-    Triangle {},
+    /// Plot chart patterns
+    #[structopt(alias = "triangle")]
+    Patterns {},
     /// Interactive stream
     Stream {},
     /// Run script trader bot back test
@@ -77,9 +103,100 @@ enum Commands {
         /// Rhai script file
         #[structopt(short, long)]
         file: String,
+        #[structopt(flatten)]
+        options: BackTestOptions,
+        /// Plot equity and drawdown panels
+        #[structopt(long)]
+        equity: bool,
+    },
+    /// Optimize script parameters over parallel back tests, results in out/optimize.csv
+    ScriptOptimize {
+        /// Rhai script file
+        #[structopt(short, long)]
+        file: String,
+        /// Script parameter values (<name>=<start>:<end>[:<step>])
+        #[structopt(long = "param", required = true)]
+        params: Vec<ParamRange>,
+        /// Parameter sets (grid, random:<runs>)
+        #[structopt(long, default_value = "grid")]
+        search: Search,
+        /// Score ranking the runs (net_return, sharpe, return_max_drawdown:<fraction>)
+        #[structopt(long, default_value = "net_return")]
+        objective: Objective,
+        #[structopt(flatten)]
+        options: BackTestOptions,
+    },
+    /// Plot the last script back test again
+    ScriptPlot {
+        /// Rhai script file
+        #[structopt(short, long)]
+        file: String,
+        /// Plot equity and drawdown panels
+        #[structopt(long)]
+        equity: bool,
     },
 }
 
+// Without doc comment, it would replace the about of the commands flattening it
+#[derive(Debug, StructOpt)]
+struct BackTestOptions {
+    /// Maker fee rate (e.g. 0.001 on Binance spot)
+    #[structopt(long, default_value = "0")]
+    maker_fee: Decimal,
+    /// Taker fee rate (e.g. 0.001 on Binance spot)
+    #[structopt(long, default_value = "0")]
+    taker_fee: Decimal,
+    /// Fees paid with BNB at a discount
+    #[structopt(long)]
+    bnb: bool,
+    /// Slippage (none, fixed:<rate>, volatility:<factor>, volume:<factor>)
+    #[structopt(long, default_value = "none")]
+    slippage: Slippage,
+    /// Minimum order total (e.g. 10 on Binance spot)
+    #[structopt(long, default_value = "0")]
+    min_notional: Decimal,
+    /// Prices filling pending orders inside each candle (bar, <minutes>m, trades)
+    #[structopt(long, default_value = "bar")]
+    fills: FillSource,
+    /// Fill of the operations (close, next_open, next_open:<latency seconds>)
+    #[structopt(long, default_value = "close")]
+    execution: Execution,
+    /// Futures leverage allowing short positions, spot without it
+    #[structopt(long)]
+    leverage: Option<Decimal>,
+    /// Futures margin mode (isolated, cross)
+    #[structopt(long, default_value = "cross")]
+    margin: MarginMode,
+    /// Futures maintenance margin rate
+    #[structopt(long, default_value = "0.004")]
+    maintenance: Decimal,
+    /// Lots closed by the exits (fifo, average)
+    #[structopt(long, default_value = "average")]
+    lots: LotMode,
+}
+
+impl BackTestOptions {
+    fn settings(&self) -> BackTestSettings {
+        let cost_model = CostModel::new(
+            self.maker_fee,
+            self.taker_fee,
+            self.bnb,
+            self.slippage,
+            self.min_notional,
+        );
+        let margin_opt = self
+            .leverage
+            .map(|leverage| Margin::new(leverage, self.margin, self.maintenance));
+        BackTestSettings::new(
+            cost_model,
+            self.fills,
+            self.execution,
+            margin_opt,
+            self.lots,
+        )
+    }
+}
+
 #[derive(Debug, StructOpt)]
 #[structopt(about = "Candles commands")]
 enum Candle {
@@ -110,19 +227,39 @@ enum Trade {
     Check {},
 }
 
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Funding rate commands")]
+enum Funding {
+    /// Import funding rates from exchange
+    Import {},
+    /// Check funding rates record integrity
+    Check {},
+}
+
 pub fn selection_default(candles_selection: CandlesSelection) -> Selection {
     let mut tacs = HashMap::new();
     for tac in vec![
         RsiTec::definition(),
         MacdTec::definition(),
         EmaTec::definition(),
+        VwapTec::definition(),
+        VolumeProfileTec::definition(),
+        VolumeTec::definition(),
+        SupportResistanceTec::definition(),
+        DivergenceTec::definition(),
+        PairRatioTec::definition(),
+        PairSpreadTec::definition(),
     ] {
         tacs.insert(tac.name.clone(), tac);
     }
+    for definition in REGISTRY.iter().filter(|d| d.plot_style.is_some()) {
+        tacs.insert(definition.name.to_string(), definition.tac_definition());
+    }
     Selection {
         tacs,
         candles_selection,
         image_name: "out/stock.png".to_string(),
+        pair_symbol_opt: None,
     }
 }
 
@@ -136,12 +273,14 @@ fn create_exchange(repository_symbol: SymbolRepository) -> Result<Exchange> {
 
 fn candles_selection_from_arg(repository_symbol: SymbolRepository, opt: &Args) -> CandlesSelection {
     let symbol = repository_symbol.symbol_by_pair(&opt.symbol).unwrap().id;
-    CandlesSelection::from(
+    let mut candles_selection = CandlesSelection::from(
         symbol,
         opt.minutes as i32,
         str_to_datetime(&opt.start_time),
         str_to_datetime(&opt.end_time),
-    )
+    );
+    candles_selection.candle_type = opt.candle_type;
+    candles_selection
 }
 
 fn create_app(
@@ -151,7 +290,8 @@ fn create_app(
 ) -> Result<Application> {
     let selection = selection_default(candles_selection);
     Ok(Application::new(
-        create_repository_candle(pool),
+        create_repository_candle(pool.clone()),
+        TradeAggRepository::new(pool),
         create_exchange(repository_symbol)?,
         selection,
     ))
@@ -196,6 +336,10 @@ async fn main(args: Args) -> color_eyre::eyre::Result<()> {
     let candles_selection = candles_selection_from_arg(repository_symbol.clone(), &args);
 
     let mut app = create_app(pool.clone(), repository_symbol.clone(), candles_selection)?;
+    app.selection.pair_symbol_opt = args
+        .pair
+        .as_ref()
+        .map(|pair| repository_symbol.symbol_by_pair(pair).unwrap().id);
 
     match args.command {
         Commands::Candle(candle) => match candle {
@@ -228,10 +372,24 @@ async fn main(args: Args) -> color_eyre::eyre::Result<()> {
             let mut streamer = Streamer::new(&mut app);
             streamer.run()?;
         }
-        Commands::Triangle {} => {
-            app.plot_triangles()?;
+        Commands::Patterns {} => {
+            app.plot_patterns()?;
         }
-        Commands::ScriptBackTest { file } => app.run_script_test(pool, &file)?,
+        Commands::ScriptBackTest {
+            file,
+            options,
+            equity,
+        } => app.run_script_test(pool, &file, options.settings(), equity)?,
+        Commands::ScriptOptimize {
+            file,
+            params,
+            search,
+            objective,
+            options,
+        } => {
+            app.optimize_script_test(pool, &file, options.settings(), &params, search, objective)?
+        }
+        Commands::ScriptPlot { file, equity } => app.plot_script_test(pool, &file, equity)?,
         Commands::Trade(trade) => match trade {
             Trade::Sync {} => {
                 TradeHistoryProvider::new(pool, create_exchange(repository_symbol)?).sync()?
@@ -240,6 +398,10 @@ async fn main(args: Args) -> color_eyre::eyre::Result<()> {
             Trade::Import {} => TradeAggsChecker::new(pool, candles_selection).import()?,
             Trade::Check {} => TradeAggsChecker::new(pool, candles_selection).check()?,
         },
+        Commands::Funding(funding) => match funding {
+            Funding::Import {} => FundingRatesChecker::new(pool, candles_selection).import()?,
+            Funding::Check {} => FundingRatesChecker::new(pool, candles_selection).check()?,
+        },
     };
     info!("Exiting program, elapsed {:?}", start.elapsed());
     Ok(())
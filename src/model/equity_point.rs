@@ -0,0 +1,21 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+/// Position value at a candle close
+#[derive(Clone, Copy, Debug)]
+pub struct EquityPoint {
+    pub time: DateTime<Utc>,
+    pub equity: Decimal,
+    /// Position open at the close
+    pub exposed: bool,
+}
+
+impl EquityPoint {
+    pub fn new(time: DateTime<Utc>, equity: Decimal, exposed: bool) -> Self {
+        Self {
+            time,
+            equity,
+            exposed,
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 
+#[derive(Clone)]
 pub struct Flow {
     pub id: i32,
     pub position: i32,
@@ -9,8 +10,14 @@ pub struct Flow {
     pub price: Decimal,
     pub quantity: Decimal,
     pub total: Decimal,
+    pub fee: Decimal,
     pub real_balance_fiat_old: Decimal,
     pub real_balance_fiat_new: Decimal,
     pub gain_perc: Decimal,
     pub log: Option<String>,
+    pub order_id: Option<i32>,
+    /// Average entry price of the lots closed by the flow
+    pub entry_price: Option<Decimal>,
+    pub realized_pnl: Option<Decimal>,
+    pub holding_seconds: Option<i64>,
 }
@@ -0,0 +1,28 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+/// Funding paid by longs to shorts, or received when negative, at the time
+#[derive(sqlx::FromRow, Clone, Copy, Debug, PartialEq)]
+pub struct FundingRate {
+    pub symbol: i32,
+    pub time: DateTime<Utc>,
+    pub rate: Decimal,
+    /// Price of the position value, older records don't have it
+    pub mark_price: Option<Decimal>,
+}
+
+impl FundingRate {
+    pub fn new(
+        symbol: i32,
+        time: DateTime<Utc>,
+        rate: Decimal,
+        mark_price: Option<Decimal>,
+    ) -> Self {
+        Self {
+            symbol,
+            time,
+            rate,
+            mark_price,
+        }
+    }
+}
@@ -1,10 +1,13 @@
 pub mod candle;
+pub mod equity_point;
 pub mod flow;
+pub mod funding_rate;
 pub mod low_high_price;
 pub mod open_close_price;
 pub mod open_close_range;
 pub mod open_close_time;
 pub mod operation;
+pub mod order;
 pub mod position;
 pub mod price;
 pub mod quantity;
@@ -5,14 +5,43 @@ use super::side::Side;
 #[derive(Debug, Clone, PartialEq, Copy)]
 pub enum Operation {
     Buy(Quantity),
+    /// Sells up to the long position
     Sell(Quantity),
+    /// Sells opening or increasing a short position
+    Short(Quantity),
+    /// Buys up to the short position
+    Cover(Quantity),
 }
 
 impl Operation {
     pub fn to_side(&self) -> Side {
+        if self.is_buy() {
+            Side::Bought
+        } else {
+            Side::Sold
+        }
+    }
+
+    pub fn is_buy(&self) -> bool {
+        matches!(self, Operation::Buy(_) | Operation::Cover(_))
+    }
+
+    pub fn quantity(&self) -> Quantity {
+        match self {
+            Operation::Buy(quantity)
+            | Operation::Sell(quantity)
+            | Operation::Short(quantity)
+            | Operation::Cover(quantity) => *quantity,
+        }
+    }
+
+    /// Same operation of another quantity
+    pub fn with_quantity(&self, quantity: Quantity) -> Self {
         match self {
-            Operation::Buy(_) => Side::Bought,
-            Operation::Sell(_) => Side::Sold,
+            Operation::Buy(_) => Operation::Buy(quantity),
+            Operation::Sell(_) => Operation::Sell(quantity),
+            Operation::Short(_) => Operation::Short(quantity),
+            Operation::Cover(_) => Operation::Cover(quantity),
         }
     }
 }
@@ -0,0 +1,85 @@
+use super::operation::Operation;
+use super::price::Price;
+use super::quantity::Quantity;
+use chrono::{DateTime, Duration, Utc};
+use eyre::bail;
+
+/// Price condition of a pending order
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OrderKind {
+    /// Fills at the price or better
+    Limit(Price),
+    /// Fills at market once the price is reached
+    Stop(Price),
+    /// Becomes a limit order once the stop is reached
+    StopLimit { stop: Price, limit: Price },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimeInForce {
+    /// Good till cancelled
+    Gtc,
+    /// Immediate or cancel, only the next candle can fill it
+    Ioc,
+    /// Good till the time
+    Gtt(DateTime<Utc>),
+}
+
+impl TimeInForce {
+    /// Parses `gtc`, `ioc` or `gtt:<minutes>` from now
+    pub fn parse(value: &str, now: DateTime<Utc>) -> eyre::Result<Self> {
+        Ok(match value.split_once(':') {
+            None if value == "gtc" => TimeInForce::Gtc,
+            None if value == "ioc" => TimeInForce::Ioc,
+            Some(("gtt", minutes)) => TimeInForce::Gtt(now + Duration::minutes(minutes.parse()?)),
+            _ => bail!("Unknown time in force {}", value),
+        })
+    }
+}
+
+/// Order resting in the book until filled, cancelled or expired
+#[derive(Clone, Debug)]
+pub struct Order {
+    pub id: i32,
+    pub operation: Operation,
+    pub kind: OrderKind,
+    pub time_in_force: TimeInForce,
+    /// Placement time, only later candles can fill it
+    pub time: DateTime<Utc>,
+    pub description_opt: Option<String>,
+}
+
+impl Order {
+    pub fn new(
+        id: i32,
+        operation: Operation,
+        kind: OrderKind,
+        time_in_force: TimeInForce,
+        time: DateTime<Utc>,
+        description_opt: Option<String>,
+    ) -> Self {
+        Self {
+            id,
+            operation,
+            kind,
+            time_in_force,
+            time,
+            description_opt,
+        }
+    }
+}
+
+/// Change of the order book requested by a trend provider
+#[derive(Clone, Debug)]
+pub enum OrderAction {
+    Place(Order),
+    Cancel(i32),
+    CancelAll,
+    /// Cancels the order and places a copy with a new id, quantity and price
+    Replace {
+        id: i32,
+        new_id: i32,
+        quantity: Quantity,
+        price: Price,
+    },
+}
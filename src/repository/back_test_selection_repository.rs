@@ -0,0 +1,66 @@
+use crate::config::candles_selection::CandlesSelection;
+use sqlx::PgPool;
+use std::sync::{Arc, RwLock};
+
+/// Candles selection of the back test of a position, to plot it again over the same candles
+#[derive(Clone)]
+pub struct BackTestSelectionRepository {
+    pool: Arc<RwLock<PgPool>>,
+}
+
+impl BackTestSelectionRepository {
+    pub fn new(pool: Arc<RwLock<PgPool>>) -> Self {
+        Self { pool }
+    }
+
+    pub fn delete_selection_from_position(&self, position: i32) {
+        let pool = self.pool.read().unwrap();
+        let future = sqlx::query!(
+            "DELETE FROM back_test_selection WHERE position = $1",
+            position
+        )
+        .execute(&*pool);
+        async_std::task::block_on(future).unwrap();
+    }
+
+    /// Candles selection and pair symbol of the position back test
+    pub fn read_selection_by_position(
+        &self,
+        position: i32,
+    ) -> eyre::Result<Option<(CandlesSelection, Option<i32>)>> {
+        let pool = self.pool.read().unwrap();
+        let future = sqlx::query!(
+            "SELECT candles_selection, pair_symbol FROM back_test_selection WHERE position = $1",
+            position
+        )
+        .fetch_optional(&*pool);
+        let result = async_std::task::block_on(future)?;
+        result
+            .map(|row| {
+                Ok((
+                    serde_json::from_str(&row.candles_selection)?,
+                    row.pair_symbol,
+                ))
+            })
+            .transpose()
+    }
+
+    pub fn insert_selection(
+        &self,
+        position: i32,
+        candles_selection: &CandlesSelection,
+        pair_symbol_opt: Option<i32>,
+    ) -> eyre::Result<()> {
+        let pool = self.pool.read().unwrap();
+        let future = sqlx::query!(
+            "INSERT INTO back_test_selection (position, candles_selection, pair_symbol) \
+                VALUES ( $1, $2, $3 )",
+            position,
+            serde_json::to_string(candles_selection)?,
+            pair_symbol_opt,
+        )
+        .execute(&*pool);
+        async_std::task::block_on(future)?;
+        Ok(())
+    }
+}
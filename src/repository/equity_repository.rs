@@ -0,0 +1,52 @@
+use crate::model::equity_point::EquityPoint;
+use sqlx::PgPool;
+use std::sync::{Arc, RwLock};
+
+#[derive(Clone)]
+pub struct EquityRepository {
+    pool: Arc<RwLock<PgPool>>,
+}
+
+impl EquityRepository {
+    pub fn new(pool: Arc<RwLock<PgPool>>) -> Self {
+        Self { pool }
+    }
+
+    pub fn delete_equity_from_position(&self, position: i32) {
+        let pool = self.pool.read().unwrap();
+        let future =
+            sqlx::query!("DELETE FROM equity WHERE position = $1", position).execute(&*pool);
+        async_std::task::block_on(future).unwrap();
+    }
+
+    pub fn read_equity_by_position(&self, position: i32) -> eyre::Result<Vec<EquityPoint>> {
+        let pool = self.pool.read().unwrap();
+        let future = sqlx::query_as!(
+            EquityPoint,
+            "SELECT time, equity, exposed FROM equity WHERE position = $1 ORDER BY time",
+            position
+        )
+        .fetch_all(&*pool);
+        let result = async_std::task::block_on(future)?;
+        Ok(result)
+    }
+
+    /// Insert the equity series of the position in a single statement
+    pub fn insert_equity(&self, position: i32, equity: &[EquityPoint]) -> eyre::Result<()> {
+        let times = equity.iter().map(|p| p.time).collect::<Vec<_>>();
+        let values = equity.iter().map(|p| p.equity).collect::<Vec<_>>();
+        let exposed = equity.iter().map(|p| p.exposed).collect::<Vec<_>>();
+        let pool = self.pool.read().unwrap();
+        let future = sqlx::query!(
+            "INSERT INTO equity (position, time, equity, exposed) \
+                SELECT $1, * FROM UNNEST($2::timestamptz[], $3::numeric[], $4::bool[])",
+            position,
+            &times,
+            &values,
+            &exposed,
+        )
+        .execute(&*pool);
+        async_std::task::block_on(future)?;
+        Ok(())
+    }
+}
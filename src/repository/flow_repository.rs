@@ -33,6 +33,18 @@ impl FlowRepository {
         async_std::task::block_on(future).unwrap();
     }
 
+    pub fn read_flows_by_position(&self, position: i32) -> eyre::Result<Vec<Flow>> {
+        let pool = self.pool.read().unwrap();
+        let future = sqlx::query_as!(
+            Flow,
+            "SELECT * FROM flow WHERE position = $1 ORDER BY id",
+            position
+        )
+        .fetch_all(&*pool);
+        let result = async_std::task::block_on(future)?;
+        Ok(result)
+    }
+
     /// Insert flow
     pub fn insert_flow(&self, flow: &mut Flow) -> eyre::Result<i32> {
         flow.id = self.last_flow_id() + 1;
@@ -47,12 +59,17 @@ impl FlowRepository {
                 price, \
                 quantity, \
                 total, \
+                fee, \
                 real_balance_fiat_old, \
                 real_balance_fiat_new, \
                 gain_perc,
-                log
+                log,
+                order_id,
+                entry_price,
+                realized_pnl,
+                holding_seconds
                 ) \
-                VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11 ) \
+                VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16 ) \
                 RETURNING id \
             ",
             flow.id,
@@ -62,10 +79,15 @@ impl FlowRepository {
             flow.price,
             flow.quantity,
             flow.total,
+            flow.fee,
             flow.real_balance_fiat_old,
             flow.real_balance_fiat_new,
             flow.gain_perc,
             flow.log,
+            flow.order_id,
+            flow.entry_price,
+            flow.realized_pnl,
+            flow.holding_seconds,
         )
         .fetch_one(&*pool);
         let rec = async_std::task::block_on(future)?;
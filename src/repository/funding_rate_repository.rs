@@ -0,0 +1,62 @@
+use crate::model::funding_rate::FundingRate;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::sync::{Arc, RwLock};
+
+#[derive(Clone)]
+pub struct FundingRateRepository {
+    pool: Arc<RwLock<PgPool>>,
+}
+
+impl FundingRateRepository {
+    pub fn new(pool: Arc<RwLock<PgPool>>) -> Self {
+        Self { pool }
+    }
+
+    pub fn last_funding_rate_time(&self, symbol: i32) -> eyre::Result<Option<DateTime<Utc>>> {
+        let pool = self.pool.read().unwrap();
+        let future = sqlx::query_scalar!(
+            "SELECT MAX(time) FROM funding_rate WHERE symbol = $1",
+            symbol
+        )
+        .fetch_one(&*pool);
+        let result = async_std::task::block_on(future)?;
+        Ok(result)
+    }
+
+    pub fn read_funding_rates_by_time(
+        &self,
+        symbol: i32,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> eyre::Result<Vec<FundingRate>> {
+        let pool = self.pool.read().unwrap();
+        let future = sqlx::query_as!(
+            FundingRate,
+            "SELECT * FROM funding_rate WHERE symbol = $1 AND time BETWEEN $2 AND $3 ORDER BY time",
+            symbol,
+            start_time,
+            end_time
+        )
+        .fetch_all(&*pool);
+        let result = async_std::task::block_on(future)?;
+        Ok(result)
+    }
+
+    /// Insert funding rate, already imported times are kept
+    pub fn insert_funding_rate(&self, funding_rate: &FundingRate) -> eyre::Result<()> {
+        let pool = self.pool.read().unwrap();
+        let future = sqlx::query!(
+            "INSERT INTO funding_rate (symbol, time, rate, mark_price) \
+                VALUES ( $1, $2, $3, $4 ) \
+                ON CONFLICT DO NOTHING",
+            funding_rate.symbol,
+            funding_rate.time,
+            funding_rate.rate,
+            funding_rate.mark_price,
+        )
+        .execute(&*pool);
+        async_std::task::block_on(future)?;
+        Ok(())
+    }
+}
@@ -1,5 +1,8 @@
+pub mod back_test_selection_repository;
 pub mod candle_repository;
+pub mod equity_repository;
 pub mod flow_repository;
+pub mod funding_rate_repository;
 pub mod pool_factory;
 pub mod position_repository;
 pub mod symbol_repository;
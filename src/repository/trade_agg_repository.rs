@@ -5,9 +5,11 @@ use colored::Colorize;
 use eyre::bail;
 use ifmt::iformat;
 use log::error;
+use rust_decimal::Decimal;
 use sqlx::PgPool;
 use std::sync::{Arc, RwLock};
 
+#[derive(Clone)]
 pub struct TradeAggRepository {
     pool: Arc<RwLock<PgPool>>,
 }
@@ -58,6 +60,58 @@ impl TradeAggRepository {
         Ok(result)
     }
 
+    pub fn read_trades_agg_by_symbol_time(
+        &self,
+        symbol: i32,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> eyre::Result<Vec<TradeAgg>> {
+        let pool = self.pool.read().unwrap();
+        let future = sqlx::query_as!(
+            TradeAgg,
+            "SELECT * FROM trade_agg WHERE symbol = $1 AND time BETWEEN $2 AND $3 ORDER BY time",
+            symbol,
+            start_time,
+            end_time
+        )
+        .fetch_all(&*pool);
+        let result = async_std::task::block_on(future)?;
+        Ok(result)
+    }
+
+    /// Traded quantity grouped by price bins of `bin_size` starting at `price_min`, returning
+    /// the bin index (clamped to `bins`) and the quantity sum
+    pub fn volume_at_price(
+        &self,
+        symbol: i32,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        price_min: Decimal,
+        bin_size: Decimal,
+        bins: i32,
+    ) -> eyre::Result<Vec<(i32, Decimal)>> {
+        let pool = self.pool.read().unwrap();
+        let future = sqlx::query!(
+            "SELECT \
+                LEAST(GREATEST(FLOOR((price - $4) / $5), 0), $6::INTEGER - 1)::INTEGER \
+                    AS \"bin!\", \
+                SUM(quantity) AS \"quantity!\" \
+            FROM trade_agg \
+            WHERE symbol = $1 AND time BETWEEN $2 AND $3 \
+            GROUP BY 1 \
+            ORDER BY 1",
+            symbol,
+            start_time,
+            end_time,
+            price_min,
+            bin_size,
+            bins
+        )
+        .fetch_all(&*pool);
+        let result = async_std::task::block_on(future)?;
+        Ok(result.into_iter().map(|r| (r.bin, r.quantity)).collect())
+    }
+
     /// Insert trades
     pub fn insert_trades_agg(&self, trades: &[TradeAgg]) -> eyre::Result<()> {
         // Insert trade calling method insert_trade, that returns Result<id>
@@ -4,7 +4,7 @@ use crate::utils::date_utils::timestamp_to_datetime;
 use crate::utils::dec_utils::fdec;
 use crate::{
     config::symbol_minutes::SymbolMinutes,
-    model::{candle::Candle, trade_agg::TradeAgg},
+    model::{candle::Candle, funding_rate::FundingRate, trade_agg::TradeAgg},
     repository::symbol_repository::SymbolRepository,
 };
 use binance::{
@@ -16,7 +16,22 @@ use eyre::{bail, Result};
 use ifmt::iformat;
 use log::error;
 use log::{log, Level};
+use rust_decimal::Decimal;
+use serde::Deserialize;
 use std::env;
+use std::str::FromStr;
+
+const FUTURES_API: &str = "https://fapi.binance.com";
+
+/// Funding rate history record of the futures API
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FundingRateRecord {
+    funding_time: u64,
+    funding_rate: String,
+    #[serde(default)]
+    mark_price: String,
+}
 
 pub struct Exchange {
     api_key: String,
@@ -35,6 +50,11 @@ impl Exchange {
         })
     }
 
+    /// Identifier of the symbol pair (e.g. BTCUSDT)
+    pub fn symbol_id(&self, pair: &str) -> Option<i32> {
+        self.repository_symbol.symbol_by_pair(pair).map(|s| s.id)
+    }
+
     pub fn futures_market(&self) -> FuturesMarket {
         Binance::new(Some(self.api_key.clone()), Some(self.secret_key.clone()))
     }
@@ -88,6 +108,40 @@ impl Exchange {
         Ok(trade_histories)
     }
 
+    /// Funding rates from the start time, up to 1000 records
+    pub fn funding_rates(
+        &self,
+        symbol: i32,
+        start_time: DateTime<Utc>,
+    ) -> eyre::Result<Vec<FundingRate>> {
+        let symbol_s = self.repository_symbol.symbol_by_id(symbol).unwrap().symbol;
+        let start_time = datetime_to_timestamp(&start_time).to_string();
+
+        // https://binance-docs.github.io/apidocs/futures/en/#get-funding-rate-history
+        let records: Vec<FundingRateRecord> = reqwest::blocking::Client::new()
+            .get(&format!("{}/fapi/v1/fundingRate", FUTURES_API))
+            .query(&[
+                ("symbol", symbol_s.as_str()),
+                ("startTime", start_time.as_str()),
+                ("limit", "1000"),
+            ])
+            .send()?
+            .error_for_status()?
+            .json()?;
+
+        records
+            .iter()
+            .map(|r| {
+                Ok(FundingRate::new(
+                    symbol,
+                    timestamp_to_datetime(&r.funding_time),
+                    Decimal::from_str(&r.funding_rate)?,
+                    Decimal::from_str(&r.mark_price).ok(),
+                ))
+            })
+            .collect()
+    }
+
     pub fn candles(
         &self,
         symbol_minutes: &SymbolMinutes,
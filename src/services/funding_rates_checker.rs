@@ -0,0 +1,153 @@
+use super::exchange::Exchange;
+use crate::model::funding_rate::FundingRate;
+use crate::repository::funding_rate_repository::FundingRateRepository;
+use crate::repository::symbol_repository::SymbolRepository;
+use crate::CandlesSelection;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Utc;
+use log::info;
+use log::warn;
+use log::Level;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+/// Funding is paid every 8 hours
+pub const FUNDING_INTERVAL_HOURS: i64 = 8;
+
+pub struct FundingRatesChecker {
+    pool: Arc<RwLock<PgPool>>,
+    candles_selection: CandlesSelection,
+}
+
+impl FundingRatesChecker {
+    pub fn new(pool: Arc<RwLock<PgPool>>, candles_selection: CandlesSelection) -> Self {
+        Self {
+            pool,
+            candles_selection,
+        }
+    }
+
+    pub fn check(&self) -> eyre::Result<()> {
+        let repository = FundingRateRepository::new(self.pool.clone());
+        let symbol = self.candles_selection.symbol_minutes.symbol;
+        let start_time = self.candles_selection.start_time;
+        let end_time = self.candles_selection.end_time;
+
+        let funding_rates = repository.read_funding_rates_by_time(symbol, start_time, end_time)?;
+
+        // Some funding times are a few seconds late
+        let max_min = Duration::hours(FUNDING_INTERVAL_HOURS) + Duration::minutes(1);
+
+        let missing_ranges =
+            missing_ranges_funding_rates(start_time, end_time, &funding_rates, max_min);
+
+        if missing_ranges.is_empty() {
+            return Ok(());
+        }
+        warn!("Missing ranges from {} {}:", start_time, end_time);
+        for missing_range in missing_ranges.iter() {
+            warn!("{} {}", missing_range.0, missing_range.1);
+        }
+
+        Ok(())
+    }
+
+    /// Import funding rates from the last imported or the selection start until now
+    pub fn import(&self) -> eyre::Result<()> {
+        let repository = FundingRateRepository::new(self.pool.clone());
+        let symbol = self.candles_selection.symbol_minutes.symbol;
+
+        let exchange: Exchange =
+            Exchange::new(SymbolRepository::new(self.pool.clone()), Level::Debug)?;
+
+        let mut start_time = repository
+            .last_funding_rate_time(symbol)?
+            .map(|time| time + Duration::seconds(1))
+            .unwrap_or(self.candles_selection.start_time);
+
+        while start_time < Utc::now() {
+            info!("Retrieving from exchange {}...", start_time);
+            let funding_rates = exchange.funding_rates(symbol, start_time)?;
+            info!("Retrieved from exchange {} records", funding_rates.len());
+
+            let last_time = match funding_rates.last() {
+                Some(funding_rate) => funding_rate.time,
+                None => {
+                    info!("No more funding rates from exchange");
+                    break;
+                }
+            };
+
+            info!("Inserting funding rates {}...", funding_rates.len());
+            for funding_rate in funding_rates.iter() {
+                if let Err(e) = repository.insert_funding_rate(funding_rate) {
+                    warn!(
+                        "Error {} on insert funding rate time {}",
+                        e, funding_rate.time
+                    );
+                }
+            }
+            info!("Inserted funding rates");
+
+            start_time = last_time + Duration::seconds(1);
+        }
+
+        Ok(())
+    }
+}
+
+pub fn missing_ranges_funding_rates(
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    funding_rates: &[FundingRate],
+    max_min: Duration,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut last_time = start_time;
+    let mut missing = Vec::new();
+    for funding_rate in funding_rates.iter() {
+        if funding_rate.time - last_time >= max_min {
+            missing.push((last_time, funding_rate.time));
+        }
+        last_time = funding_rate.time;
+    }
+    if end_time - last_time >= max_min {
+        missing.push((last_time, end_time));
+    }
+    missing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::date_utils::str_to_datetime;
+    use pretty_assertions::assert_eq;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn missing_ranges_funding_rates_test() {
+        let funding_rates = [
+            "2021-04-11 00:00:00",
+            "2021-04-11 08:00:01",
+            "2021-04-12 00:00:00",
+        ]
+        .iter()
+        .map(|time| FundingRate::new(1, str_to_datetime(time), dec!(0.0001), None))
+        .collect::<Vec<_>>();
+
+        let start_time = str_to_datetime("2021-04-11 00:00:00");
+        let end_time = str_to_datetime("2021-04-12 06:00:00");
+        let max_min = Duration::hours(FUNDING_INTERVAL_HOURS) + Duration::minutes(1);
+        let missing_ranges =
+            missing_ranges_funding_rates(start_time, end_time, &funding_rates, max_min);
+
+        assert_eq!(
+            missing_ranges,
+            vec![(
+                str_to_datetime("2021-04-11 08:00:01"),
+                str_to_datetime("2021-04-12 00:00:00"),
+            )]
+        );
+    }
+}
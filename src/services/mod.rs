@@ -1,5 +1,6 @@
 pub mod candles_checker;
 pub mod exchange;
+pub mod funding_rates_checker;
 pub mod provider;
 pub mod script;
 pub mod streamer;
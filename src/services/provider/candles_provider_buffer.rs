@@ -23,6 +23,17 @@ impl CandlesProviderBuffer {
     pub fn set_candles_selection(&mut self, candles_selection: CandlesSelection) {
         self.candles_selection_opt = Some(candles_selection);
     }
+
+    pub fn candles_selection(&self) -> Option<CandlesSelection> {
+        self.candles_selection_opt
+    }
+
+    pub fn symbol_id(&self, pair: &str) -> Option<i32> {
+        self.candles_provider_singleton
+            .read()
+            .unwrap()
+            .symbol_id(pair)
+    }
 }
 
 impl CandlesProvider for CandlesProviderBuffer {
@@ -33,6 +44,16 @@ impl CandlesProvider for CandlesProviderBuffer {
             .cloned()
             .ok_or_else(|| -> eyre::Error { eyre!("candles_selection not defined!") })?;
 
+        // Buffered candles only need the read lock, so parallel back tests don't wait on each other
+        if let Some(candles) = self
+            .candles_provider_singleton
+            .read()
+            .unwrap()
+            .buffered_candles(candles_selection)?
+        {
+            return Ok(candles);
+        }
+
         let m = &*self.candles_provider_singleton;
 
         let mut c = m.write().unwrap();
@@ -1,4 +1,5 @@
 use super::candles_buffer::CandlesBuffer;
+use crate::config::candle_type::CandleType;
 use crate::config::candles_selection::CandlesSelection;
 use crate::config::symbol_minutes::SymbolMinutes;
 use crate::model::candle::Candle;
@@ -6,6 +7,7 @@ use crate::model::open_close_range::OpenCloseRange;
 use crate::repository::candle_repository::CandleRepository;
 use crate::services::exchange::Exchange;
 use crate::services::provider::candles_range::candles_to_ranges_missing;
+use crate::services::technicals::brick_candles;
 use crate::services::technicals::heikin_ashi;
 use chrono::prelude::*;
 use chrono::Duration;
@@ -21,7 +23,8 @@ use std::time::Instant;
 pub struct CandlesProviderBufferSingleton {
     exchange: Exchange,
     candle_repository: CandleRepository,
-    buffer: HashMap<SymbolMinutes, CandlesBuffer>,
+    /// Raw and Heikin-Ashi candles are buffered apart, bricks are made from raw candles
+    buffer: HashMap<(SymbolMinutes, bool), CandlesBuffer>,
 }
 
 impl CandlesProviderBufferSingleton {
@@ -34,6 +37,32 @@ impl CandlesProviderBufferSingleton {
         Arc::new(RwLock::new(candles_provider_singleton))
     }
 
+    pub fn symbol_id(&self, pair: &str) -> Option<i32> {
+        self.exchange.symbol_id(pair)
+    }
+
+    /// Candles of the selection when the buffer already holds them, without loading any
+    pub fn buffered_candles(
+        &self,
+        candles_selection: CandlesSelection,
+    ) -> eyre::Result<Option<Vec<Candle>>> {
+        let heikin_ashi = candles_selection.candle_type == CandleType::HeikinAshi;
+        let candles_btree = match self
+            .buffer
+            .get(&(candles_selection.symbol_minutes, heikin_ashi))
+        {
+            Some(candles_btree) => candles_btree,
+            None => return Ok(None),
+        };
+        if !candles_btree
+            .missing_ranges(&candles_selection.start_time, &buffer_end_time())?
+            .is_empty()
+        {
+            return Ok(None);
+        }
+        Ok(Some(buffer_candles(candles_btree, &candles_selection)))
+    }
+
     pub fn candles(&mut self, candles_selection: CandlesSelection) -> eyre::Result<Vec<Candle>> {
         let start = Instant::now();
         debug!("Initializing import...");
@@ -83,11 +112,12 @@ impl CandlesProviderBufferSingleton {
             .with_nanosecond(0)
             .unwrap();
 
-        let end_time = Utc.ymd(2020, 12, 31).and_hms(23, 59, 59);
+        let end_time = buffer_end_time();
 
         // let end_time = candles_selection.end_time;
 
         let symbol_minutes = candles_selection.symbol_minutes;
+        let heikin_ashi = candles_selection.candle_type == CandleType::HeikinAshi;
 
         let candles_btree = loop {
             // Get candles from buffer
@@ -98,7 +128,7 @@ impl CandlesProviderBufferSingleton {
 
             let candles_btree = self
                 .buffer
-                .entry(symbol_minutes)
+                .entry((symbol_minutes, heikin_ashi))
                 .or_insert_with(|| CandlesBuffer::new(symbol_minutes.minutes));
 
             debug!("Candles buffer count: {}", candles_btree.len());
@@ -170,7 +200,7 @@ impl CandlesProviderBufferSingleton {
                     candles_repo.iter().collect::<Vec<_>>().as_slice(),
                 )?;
 
-                candles_to_buf(candles_selection.heikin_ashi, candles_repo, candles_btree)?;
+                candles_to_buf(heikin_ashi, candles_repo, candles_btree)?;
 
                 debug!(
                     "Repository ranges missing count: {}",
@@ -200,20 +230,12 @@ impl CandlesProviderBufferSingleton {
                         .insert_candles(&mut candles_exchange)?;
 
                     // Insert candles on buffer
-                    candles_to_buf(
-                        candles_selection.heikin_ashi,
-                        candles_exchange,
-                        candles_btree,
-                    )?;
+                    candles_to_buf(heikin_ashi, candles_exchange, candles_btree)?;
                 }
             }
         };
 
-        let candles = candles_btree
-            .candles_from_range(candles_selection.start_time, candles_selection.end_time)
-            .into_iter()
-            .cloned()
-            .collect::<Vec<_>>();
+        let candles = buffer_candles(candles_btree, &candles_selection);
 
         // .range((
         //     Included(candles_selection.start_time),
@@ -241,6 +263,31 @@ impl CandlesProviderBufferSingleton {
     }
 }
 
+/// End of the candles kept in the buffer
+fn buffer_end_time() -> DateTime<Utc> {
+    Utc.ymd(2020, 12, 31).and_hms(23, 59, 59)
+}
+
+/// Buffered candles of the selection range made of its candle type
+fn buffer_candles(
+    candles_btree: &CandlesBuffer,
+    candles_selection: &CandlesSelection,
+) -> Vec<Candle> {
+    let candles = candles_btree
+        .candles_from_range(candles_selection.start_time, candles_selection.end_time)
+        .into_iter()
+        .cloned()
+        .collect::<Vec<_>>();
+
+    match candles_selection.candle_type {
+        CandleType::Raw | CandleType::HeikinAshi => candles,
+        CandleType::Renko(brick_size) => brick_candles::renko(&candles, brick_size),
+        CandleType::RenkoAtr(period) => brick_candles::renko_atr(&candles, period),
+        CandleType::RangeBar(range) => brick_candles::range_bars(&candles, range),
+        CandleType::LineBreak(lines) => brick_candles::line_break(&candles, lines),
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
@@ -1,6 +1,8 @@
 pub mod position_register;
 pub mod script_back_test;
 pub mod script_fns;
+pub mod script_indicator;
+pub mod script_optimize;
 pub mod script_state;
 pub mod script_state_singleton;
 pub mod script_trend_provider;
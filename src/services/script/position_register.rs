@@ -1,83 +1,434 @@
+use crate::model::candle::Candle;
+use crate::model::funding_rate::FundingRate;
 use crate::model::position::Position;
+use crate::model::price::Price;
 use crate::model::quantity::Quantity;
+use crate::services::trading::cost_model::{CostModel, Fill};
+use crate::services::trading::lots::{LotMode, Lots};
+use crate::services::trading::margin::Margin;
+use crate::services::trading::order_book::OrderBook;
 use crate::services::trading::trade_operation::TradeOperation;
+use crate::utils::dec_utils::percent;
 use crate::{model::operation::Operation, services::trading::flow_register::FlowRegister};
 use log::warn;
-use rust_decimal::prelude::Zero;
 use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::fmt::Display;
 
 #[derive(Clone)]
 pub struct PositionRegister {
     pub position: Position,
     pub flow_register: FlowRegister,
+    pub cost_model: CostModel,
+    pub order_book: OrderBook,
+    /// Futures margin, spot positions without it
+    pub margin_opt: Option<Margin>,
+    /// Open lots of the position
+    pub lots: Lots,
+    /// Candles checked by the back test
+    pub bar: i64,
+    pub initial_balance_fiat: Decimal,
+    /// Fees paid in fiat
+    pub fees: Decimal,
+    /// Fiat lost by the fill prices against the operation prices
+    pub slippage: Decimal,
+    /// Funding paid in fiat, negative when received
+    pub funding: Decimal,
+    /// Last funding rate reached by the back test
+    pub funding_rate_opt: Option<Decimal>,
 }
 
 impl PositionRegister {
-    pub fn new(position: Position, flow_register: FlowRegister) -> Self {
+    pub fn new(
+        position: Position,
+        flow_register: FlowRegister,
+        cost_model: CostModel,
+        margin_opt: Option<Margin>,
+        lot_mode: LotMode,
+    ) -> Self {
         Self {
+            initial_balance_fiat: position.real_balance_fiat,
             position,
             flow_register,
+            cost_model,
+            order_book: OrderBook::new(),
+            margin_opt,
+            lots: Lots::new(lot_mode),
+            bar: 0,
+            fees: dec!(0),
+            slippage: dec!(0),
+            funding: dec!(0),
+            funding_rate_opt: None,
         }
     }
 
-    pub fn register(&mut self, trade_operation: &TradeOperation) -> eyre::Result<()> {
-        self.flow_register.set_position_old(&self.position);
+    /// Gain after fees and slippage
+    pub fn net_gain(&self) -> Decimal {
+        self.position.real_balance_fiat - self.initial_balance_fiat
+    }
 
-        {
-            match trade_operation.operation {
-                // I have USD and must buy coin
-                Operation::Buy(mut quantity_asset) => {
-                    let mut buy_total = quantity_asset.0 * trade_operation.price.0;
-                    let diff = self.position.balance_fiat - buy_total;
-
-                    if diff < Decimal::zero() {
-                        warn!("Fixing quantity to buy!");
-                        quantity_asset =
-                            Quantity(self.position.balance_fiat / trade_operation.price.0);
-                        buy_total = quantity_asset.0 * trade_operation.price.0;
-                    }
-
-                    self.position.balance_asset += quantity_asset.0;
-                    self.position.balance_fiat -= buy_total;
-                }
+    /// Gain as if every operation filled at its price without fees and funding
+    pub fn gross_gain(&self) -> Decimal {
+        self.net_gain() + self.fees + self.slippage + self.funding
+    }
 
-                // I have coin and must sell to gain USD
-                Operation::Sell(mut quantity_asset) => {
-                    let mut sell_total = quantity_asset.0 * trade_operation.price.0;
+    pub fn position_side(&self) -> PositionSide {
+        match self.position.balance_asset {
+            asset if asset > dec!(0) => PositionSide::Long,
+            asset if asset < dec!(0) => PositionSide::Short,
+            _ => PositionSide::Flat,
+        }
+    }
 
-                    let diff = self.position.balance_asset - quantity_asset.0;
+    /// Gain in percent of the open position at the price from its entry price, positive for a
+    /// short when the price fell, 0 when flat
+    pub fn gain_perc(&self, price: Decimal) -> Decimal {
+        let entry_price = self.lots.entry_price();
+        match self.position_side() {
+            PositionSide::Long if entry_price > dec!(0) => percent(&price, &entry_price),
+            PositionSide::Short if entry_price > dec!(0) => -percent(&price, &entry_price),
+            _ => dec!(0),
+        }
+    }
+
+    /// Price liquidating the futures position
+    pub fn liquidation_price(&self) -> Option<Decimal> {
+        self.margin_opt.and_then(|margin| {
+            margin.liquidation_price(
+                self.position.balance_asset,
+                self.lots.entry_price(),
+                self.position.balance_fiat,
+            )
+        })
+    }
 
-                    if diff < Decimal::zero() {
-                        warn!("Fixing quantity to sell!");
-                        quantity_asset = Quantity(self.position.balance_asset);
-                        sell_total = quantity_asset.0 * trade_operation.price.0;
-                    }
+    /// Pays the funding of the open futures position, longs pay positive rates to shorts, the
+    /// position value uses the mark price or the price without it
+    pub fn apply_funding(
+        &mut self,
+        funding_rate: &FundingRate,
+        price: Decimal,
+    ) -> eyre::Result<()> {
+        self.funding_rate_opt = Some(funding_rate.rate);
+        let asset = self.position.balance_asset;
+        if self.margin_opt.is_none() || asset == dec!(0) {
+            return Ok(());
+        }
+        let mark_price = funding_rate.mark_price.unwrap_or(price);
+        let payment = asset * mark_price * funding_rate.rate;
 
-                    self.position.balance_asset -= quantity_asset.0;
-                    self.position.balance_fiat += sell_total;
+        self.flow_register.set_position_old(&self.position);
+        self.position.balance_fiat -= payment;
+        self.position.real_balance_fiat -= payment;
+        self.funding += payment;
+        self.flow_register
+            .set_funding(&self.position, funding_rate, mark_price, payment)
+    }
+
+    /// Fills the operation in the candle, returns the operation at its fill quantity and price or
+    /// None when rejected
+    pub fn register(
+        &mut self,
+        trade_operation: &TradeOperation,
+        candle: &Candle,
+    ) -> eyre::Result<Option<TradeOperation>> {
+        let cost_model = self.cost_model;
+        self.register_fill(trade_operation, |operation| {
+            cost_model.fill(operation, trade_operation.price, candle)
+        })
+    }
+
+    /// Liquidates the position and fills the pending orders crossed by the path candles inside
+    /// the candle, in time order at the path candle close, returns the registered operations.
+    /// IOC orders not filled by any path candle opened after them expire with the candle.
+    pub fn fill_orders(
+        &mut self,
+        candle: &Candle,
+        path: &[Candle],
+    ) -> eyre::Result<Vec<TradeOperation>> {
+        let mut trade_operations = Vec::new();
+        for step in path {
+            if let Some(liquidation) = self.liquidate(candle, step)? {
+                trade_operations.push(liquidation);
+            }
+            for order_fill in self.order_book.fill(step) {
+                let trade_operation = TradeOperation::from_fill(&order_fill, step.close_time);
+                let cost_model = self.cost_model;
+                let fill_of = |operation: &Operation| {
+                    cost_model.order_fill(operation, order_fill.price, order_fill.is_maker, candle)
+                };
+                if let Some(filled) = self.register_fill(&trade_operation, fill_of)? {
+                    trade_operations.push(filled);
                 }
-            };
+            }
+        }
+        self.order_book.expire_ioc(path.last().unwrap_or(candle));
+        Ok(trade_operations)
+    }
 
-            self.position.price = trade_operation.price.0;
-            self.position.real_balance_fiat =
-                self.position.balance_asset * self.position.price + self.position.balance_fiat;
+    /// Closes the position at the liquidation price, or the open when it gapped past it
+    fn liquidate(
+        &mut self,
+        candle: &Candle,
+        step: &Candle,
+    ) -> eyre::Result<Option<TradeOperation>> {
+        let liquidation_price = match self.liquidation_price() {
+            Some(liquidation_price) => liquidation_price,
+            None => return Ok(None),
+        };
+        let asset = self.position.balance_asset;
+        let (operation, price) = match self.position_side() {
+            PositionSide::Long if step.low <= liquidation_price => (
+                Operation::Sell(Quantity(asset)),
+                step.open.min(liquidation_price),
+            ),
+            PositionSide::Short if step.high >= liquidation_price => (
+                Operation::Cover(Quantity(-asset)),
+                step.open.max(liquidation_price),
+            ),
+            _ => return Ok(None),
+        };
+        warn!(
+            "Liquidated {} at {}",
+            self.position_side(),
+            price.round_dp(8)
+        );
+        let trade_operation = TradeOperation::new(
+            operation,
+            step.close_time,
+            Price(price),
+            Some("Liquidation".to_string()),
+        );
+        let fill = self.cost_model.fill(&operation, Price(price), candle);
+        self.settle(&trade_operation, fill, Quantity(asset.abs()))
+            .map(Some)
+    }
+
+    /// Quantity of the operation the balances allow
+    fn allowed_quantity(&self, operation: &Operation, fill: &Fill) -> Option<Quantity> {
+        let asset = self.position.balance_asset;
+        let requested = operation.quantity().0;
+        let price = fill.price.0;
+        let allowed = match (operation, self.margin_opt) {
+            // I have USD and must buy coin, paying the fee too
+            (Operation::Buy(_), None) => {
+                let cost = price * (dec!(1) + fill.fee_rate);
+                requested.min(self.position.balance_fiat / cost)
+            }
+            // The position after buying can't exceed the leveraged equity
+            (Operation::Buy(_), Some(margin)) => {
+                let max_position = margin.max_position(self.position.real_balance_fiat, price);
+                requested.min(max_position - asset)
+            }
+            // I have coin and must sell to gain USD
+            (Operation::Sell(_), _) => requested.min(asset),
+            (Operation::Short(_), None) => {
+                warn!("Short selling needs margin!");
+                return None;
+            }
+            (Operation::Short(_), Some(margin)) => {
+                let max_position = margin.max_position(self.position.real_balance_fiat, price);
+                requested.min(max_position + asset)
+            }
+            (Operation::Cover(_), _) => requested.min(-asset),
+        };
+        if allowed < requested {
+            warn!(
+                "Fixing quantity of {:?} to {}!",
+                operation,
+                allowed.round_dp(8)
+            );
+        }
+        if allowed > dec!(0) {
+            Some(Quantity(allowed))
+        } else {
+            None
         }
+    }
 
-        self.flow_register
-            .set_position_new(&self.position, trade_operation)?;
-        Ok(())
+    /// Settles the quantity the balances allow, slipping by that quantity rather than the
+    /// requested one, a smaller quantity doesn't slip more so it stays allowed
+    fn register_fill<F>(
+        &mut self,
+        trade_operation: &TradeOperation,
+        fill_of: F,
+    ) -> eyre::Result<Option<TradeOperation>>
+    where
+        F: Fn(&Operation) -> Fill,
+    {
+        let operation = &trade_operation.operation;
+        let quantity_asset = match self.allowed_quantity(operation, &fill_of(operation)) {
+            Some(quantity_asset) => quantity_asset,
+            None => return Ok(None),
+        };
+        let fill = fill_of(&operation.with_quantity(quantity_asset));
+
+        let total = quantity_asset.0 * fill.price.0;
+        if total < self.cost_model.min_notional {
+            warn!(
+                "Rejected operation with total {} under minimum notional {}",
+                total, self.cost_model.min_notional
+            );
+            return Ok(None);
+        }
+        self.settle(trade_operation, fill, quantity_asset).map(Some)
+    }
+
+    /// Moves the balances, returns the operation at its fill quantity and price
+    fn settle(
+        &mut self,
+        trade_operation: &TradeOperation,
+        fill: Fill,
+        quantity_asset: Quantity,
+    ) -> eyre::Result<TradeOperation> {
+        let price = fill.price.0;
+        let total = quantity_asset.0 * price;
+        let fee = total * fill.fee_rate;
+
+        self.flow_register.set_position_old(&self.position);
+
+        let asset_change = if trade_operation.operation.is_buy() {
+            self.position.balance_fiat -= total + fee;
+            quantity_asset.0
+        } else {
+            self.position.balance_fiat += total - fee;
+            -quantity_asset.0
+        };
+        self.position.balance_asset += asset_change;
+        let lot_close_opt = self
+            .lots
+            .fill(asset_change, price, trade_operation.now, self.bar);
+        self.fees += fee;
+        self.slippage += (price - trade_operation.price.0).abs() * quantity_asset.0;
+
+        self.position.price = price;
+        self.position.real_balance_fiat =
+            self.position.balance_asset * self.position.price + self.position.balance_fiat;
+
+        self.flow_register.set_position_new(
+            &self.position,
+            trade_operation,
+            quantity_asset,
+            fill.price,
+            fee,
+            lot_close_opt,
+        )?;
+        Ok(TradeOperation {
+            operation: trade_operation.operation.with_quantity(quantity_asset),
+            price: fill.price,
+            ..trade_operation.clone()
+        })
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Display)]
+pub enum PositionSide {
+    Long,
+    Short,
+    Flat,
+}
+
 #[cfg(test)]
-mod tests {
-    // use crate::repository::flow_repository::FlowRepository;
+pub mod tests {
+    use super::*;
+    use crate::model::order::{Order, OrderAction, OrderKind, TimeInForce};
+    use crate::services::trading::cost_model::Slippage;
+    use crate::services::trading::margin::MarginMode;
+    use crate::utils::candles_utils::tests::{candle_at, timeframe_candle};
+
+    fn margin_register(margin_opt: Option<Margin>) -> PositionRegister {
+        let cost_model = CostModel::new(dec!(0), dec!(0), false, Slippage::None, dec!(0));
+        PositionRegister::new(
+            Position::from_fiat("test", dec!(1000)),
+            FlowRegister::in_memory(),
+            cost_model,
+            margin_opt,
+            LotMode::Average,
+        )
+    }
 
-    // use super::PositionRegister;
+    fn position_register() -> PositionRegister {
+        margin_register(None)
+    }
+
+    #[test]
+    fn ioc_order_path_test() {
+        let bar = candle_at(1, dec!(100), dec!(102), dec!(86), dec!(88), dec!(15));
+        // One minute candles going down a unit each
+        let minutes = (0..15)
+            .map(|i| {
+                let low = dec!(100) - Decimal::from(i);
+                timeframe_candle(1, 15 + i, low + dec!(1), low + dec!(2), low, low, dec!(1))
+            })
+            .collect::<Vec<_>>();
+        let placed = candle_at(0, dec!(100), dec!(100), dec!(100), dec!(100), dec!(1));
+        let ioc = |limit| {
+            OrderAction::Place(Order::new(
+                1,
+                Operation::Buy(Quantity(dec!(1))),
+                OrderKind::Limit(Price(limit)),
+                TimeInForce::Ioc,
+                placed.close_time,
+                None,
+            ))
+        };
+
+        for path in [std::slice::from_ref(&bar), &minutes[..]].iter() {
+            // Filled by a later path candle than the first one
+            let mut register = position_register();
+            register.order_book.apply(ioc(dec!(95)));
+            let filled = register.fill_orders(&bar, path).unwrap();
+            assert_eq!(filled.len(), 1);
+            assert_eq!(filled[0].price, Price(dec!(95)));
+            assert!(register.order_book.orders().is_empty());
+
+            // Not reached inside the bar, expired after it
+            let mut register = position_register();
+            register.order_book.apply(ioc(dec!(80)));
+            assert!(register.fill_orders(&bar, path).unwrap().is_empty());
+            assert!(register.order_book.orders().is_empty());
+        }
+    }
 
-    // fn position_register_test() {
-    // TODO Create FlowRepository Trait and struct FlowRepositoryDB
-    // FlowRepository
-    // PositionRegister::new();
-    //}
+    #[test]
+    fn gain_perc_test() {
+        let now = candle_at(0, dec!(100), dec!(100), dec!(100), dec!(100), dec!(1)).close_time;
+        let fill = Fill {
+            price: Price(dec!(100)),
+            fee_rate: dec!(0),
+        };
+        let margin = Margin::new(dec!(2), MarginMode::Isolated, dec!(0.004));
+        let mut register = margin_register(Some(margin));
+        assert_eq!(register.gain_perc(dec!(90)), dec!(0));
+
+        let buy = TradeOperation::new(Operation::Buy(Quantity(dec!(1))), now, fill.price, None);
+        register.settle(&buy, fill, Quantity(dec!(1))).unwrap();
+        assert_eq!(register.gain_perc(dec!(110)), dec!(10));
+        assert_eq!(register.gain_perc(dec!(90)), dec!(-10));
+
+        let mut register = margin_register(Some(margin));
+        let short = TradeOperation::new(Operation::Short(Quantity(dec!(1))), now, fill.price, None);
+        register.settle(&short, fill, Quantity(dec!(1))).unwrap();
+        assert_eq!(register.gain_perc(dec!(90)), dec!(10));
+        assert_eq!(register.gain_perc(dec!(110)), dec!(-10));
+    }
+
+    #[test]
+    fn volume_slippage_test() {
+        let candle = candle_at(0, dec!(100), dec!(100), dec!(100), dec!(100), dec!(100));
+        let mut register = position_register();
+        register.cost_model.slippage = Slippage::Volume(dec!(1));
+        let buy = TradeOperation::new(
+            Operation::Buy(Quantity(dec!(20))),
+            candle.close_time,
+            Price(dec!(100)),
+            None,
+        );
+
+        // Slipped by the quantity the balance allows, not the 20% of the requested one
+        let filled = register.register(&buy, &candle).unwrap().unwrap();
+        let quantity = filled.operation.quantity().0;
+        assert!(quantity < dec!(10));
+        assert_eq!(filled.price.0, dec!(100) * (dec!(1) + quantity / dec!(100)));
+    }
 }
@@ -1,22 +1,43 @@
+use crate::app::Application;
+use crate::config::candle_type::CandleType;
+use crate::model::candle::Candle;
+use crate::model::equity_point::EquityPoint;
+use crate::model::funding_rate::FundingRate;
 use crate::model::position::Position;
+use crate::repository::back_test_selection_repository::BackTestSelectionRepository;
+use crate::repository::equity_repository::EquityRepository;
 use crate::repository::flow_repository::FlowRepository;
+use crate::repository::funding_rate_repository::FundingRateRepository;
 use crate::repository::position_repository::PositionRepository;
 use crate::services::provider::candles_provider::CandlesProvider;
 use crate::services::script::position_register::PositionRegister;
+use crate::services::script::script_indicator::ScriptIndicatorTec;
 use crate::services::script::script_trend_provider::ScriptTrendProvider;
 use crate::services::script::singleton_engine::EngineSingleton;
+use crate::services::tec_plotter::equity_plotter::{EquityPlotter, EquityTec};
+use crate::services::tec_plotter::line_ind_plotter::LineIndicatorPlotter;
+use crate::services::tec_plotter::lines_area_plotter::LinesAreaPlotter;
 use crate::services::tec_plotter::plot_selection::PlotterSelection;
 use crate::services::tec_plotter::plotter_indicator_context::PlotterIndicatorContext;
 use crate::services::tec_plotter::trading_plotter::TradingPlotter;
-use crate::services::trading::flow_register::FlowRegister;
+use crate::services::technicals::ind_registry::PlotStyle;
+use crate::services::technicals::technical::TecSerieIndicators;
+use crate::services::trading::cost_model::CostModel;
+use crate::services::trading::execution::Execution;
+use crate::services::trading::fill_source::{bar_path, bar_trades, trade_candles, FillSource};
+use crate::services::trading::flow_register::{FlowRegister, FUNDING_LOG};
+use crate::services::trading::lots::LotMode;
+use crate::services::trading::margin::Margin;
+use crate::services::trading::performance::{trade_pnls, PerformanceReport};
 use crate::services::trading::trade_operation::TradeOperation;
+use crate::services::trading::trader::Trader;
 use crate::services::trading::trader_factory::TraderFactory;
 use crate::services::trading::trader_register::TraderRegister;
-use crate::{app::Application, model::price::Price};
 use colored::Colorize;
-use eyre::eyre;
+use eyre::{bail, eyre};
 use ifmt::iformat;
-use log::info;
+use log::{info, warn};
+use plotters::style::RGBColor;
 use rust_decimal_macros::dec;
 use sqlx::PgPool;
 use std::{
@@ -25,7 +46,7 @@ use std::{
     time::Instant,
 };
 
-fn path_to_description<P: AsRef<Path>>(path: P) -> String {
+pub fn path_to_description<P: AsRef<Path>>(path: P) -> String {
     let script_file_path = path.as_ref();
     script_file_path
         .with_extension("")
@@ -35,96 +56,382 @@ fn path_to_description<P: AsRef<Path>>(path: P) -> String {
         .to_string()
 }
 
-/// Run script back test
+/// How the back test fills and accounts the operations
+#[derive(Clone, Copy, Debug)]
+pub struct BackTestSettings {
+    pub cost_model: CostModel,
+    pub fill_source: FillSource,
+    pub execution: Execution,
+    /// Futures margin, spot without it
+    pub margin_opt: Option<Margin>,
+    pub lot_mode: LotMode,
+}
+
+impl BackTestSettings {
+    pub fn new(
+        cost_model: CostModel,
+        fill_source: FillSource,
+        execution: Execution,
+        margin_opt: Option<Margin>,
+        lot_mode: LotMode,
+    ) -> Self {
+        Self {
+            cost_model,
+            fill_source,
+            execution,
+            margin_opt,
+            lot_mode,
+        }
+    }
+}
+
+/// Raw exchange candles of the prices and the selection candles of the indicators
+pub fn selection_candles(app: &mut Application) -> eyre::Result<(Vec<Candle>, Vec<Candle>)> {
+    // Prices come from exchange candles, whatever the candles of indicators
+    let mut prices_selection = app.selection.candles_selection;
+    prices_selection.candle_type = CandleType::Raw;
+    app.candles_provider.set_candles_selection(prices_selection);
+    let prices = app.candles_provider.candles()?;
+
+    // Load candles from selection
+    app.candles_provider
+        .set_candles_selection(app.selection.candles_selection);
+    let candles = app.candles_provider.candles()?;
+    Ok((prices, candles))
+}
+
+/// Lower timeframe candles resolving the order fills inside each price candle
+pub fn lower_candles(app: &mut Application, fill_source: FillSource) -> eyre::Result<Vec<Candle>> {
+    match fill_source {
+        FillSource::Candles(minutes) => {
+            let prices_selection = app.selection.candles_selection;
+            let mut lower_selection = prices_selection;
+            lower_selection.candle_type = CandleType::Raw;
+            lower_selection.symbol_minutes.minutes = minutes;
+            app.candles_provider.set_candles_selection(lower_selection);
+            let lower_candles = app.candles_provider.candles()?;
+            app.candles_provider.set_candles_selection(prices_selection);
+            Ok(lower_candles)
+        }
+        FillSource::Bar | FillSource::Trades => Ok(Vec::new()),
+    }
+}
+
+/// Funding rates over the selection, futures positions pay funding
+pub fn funding_rates(
+    pool: Arc<RwLock<PgPool>>,
+    app: &Application,
+    margin_opt: Option<Margin>,
+) -> eyre::Result<Vec<FundingRate>> {
+    if margin_opt.is_none() {
+        return Ok(Vec::new());
+    }
+    let prices_selection = app.selection.candles_selection;
+    let funding_rates = FundingRateRepository::new(pool).read_funding_rates_by_time(
+        prices_selection.symbol_minutes.symbol,
+        prices_selection.start_time,
+        prices_selection.end_time,
+    )?;
+    if funding_rates.is_empty() {
+        warn!("No funding rates found, import them to pay funding!");
+    }
+    Ok(funding_rates)
+}
+
+/// Runs the installed script over the prices, returns the trader with its trades and equity
+pub fn back_test(
+    app: &Application,
+    prices: &[Candle],
+    lower_candles: &[Candle],
+    position_register: PositionRegister,
+    funding_rates: Vec<FundingRate>,
+    settings: BackTestSettings,
+) -> eyre::Result<Trader<ScriptTrendProvider>> {
+    if prices.is_empty() {
+        bail!("First candle not found!");
+    }
+
+    let mut trader_register = TraderRegister::from(position_register);
+    trader_register.set_funding_rates(funding_rates);
+
+    // Create trader from trend provider
+    let trader_factory = TraderFactory::from(
+        app.selection.candles_selection,
+        // TODO This candles_provider can get the real final end to buffer next candles
+        app.candles_provider.clone(),
+    );
+
+    let script_trend_provider = ScriptTrendProvider::new();
+
+    let mut trader =
+        trader_factory.create_trader(script_trend_provider, trader_register, settings.execution);
+
+    // Trades of the whole selection loaded once, split by candle below
+    let trades = match settings.fill_source {
+        FillSource::Trades => app.trade_agg_repository.read_trades_agg_by_symbol_time(
+            prices[0].symbol,
+            prices[0].open_time,
+            prices[prices.len() - 1].close_time,
+        )?,
+        FillSource::Bar | FillSource::Candles(_) => Vec::new(),
+    };
+
+    // Run trader from candles, this invoke script_trend_provider.trend()
+    for candle in prices.iter() {
+        match settings.fill_source {
+            FillSource::Trades => {
+                let path = trade_candles(candle, bar_trades(candle, &trades));
+                trader.check(candle, bar_path(candle, &path))?;
+            }
+            FillSource::Bar | FillSource::Candles(_) => {
+                trader.check(candle, bar_path(candle, lower_candles))?
+            }
+        }
+    }
+
+    Ok(trader)
+}
+
+/// Run script back test, plotting the equity and drawdown panels when asked
 pub fn run_script<P: AsRef<Path>>(
     pool: Arc<RwLock<PgPool>>,
     app: &mut Application,
     script_file: P,
+    settings: BackTestSettings,
+    plot_equity: bool,
 ) -> eyre::Result<Vec<TradeOperation>> {
+    let BackTestSettings {
+        cost_model,
+        fill_source,
+        margin_opt,
+        lot_mode,
+        ..
+    } = settings;
     let start = Instant::now();
     info!("Initializing back test...");
 
     // Create engine script and register functions
     EngineSingleton::install(&script_file)?;
 
-    // Load candles from selection
-    app.candles_provider
-        .set_candles_selection(app.selection.candles_selection);
-    let candles = app.candles_provider.candles()?;
+    let (prices, candles) = selection_candles(app)?;
+
+    let lower_candles = lower_candles(app, fill_source)?;
+    let funding_rates = funding_rates(pool.clone(), app, margin_opt)?;
 
     let flow_repository = FlowRepository::new(pool.clone());
     let flow_register = FlowRegister::new(flow_repository.clone());
 
-    // Initial position
-    let _first_price = Price(
-        candles
-            .first()
-            .ok_or_else(|| eyre!("First candle not found!"))?
-            .open,
-    );
-
     let position_description = path_to_description(&script_file);
-    let position_repository = PositionRepository::new(pool);
+    let position_repository = PositionRepository::new(pool.clone());
+    let equity_repository = EquityRepository::new(pool.clone());
+    let selection_repository = BackTestSelectionRepository::new(pool);
 
     let position_opt = position_repository.position_by_description(&position_description);
     if let Some(position) = position_opt {
         flow_repository.delete_flows_from_position(position.id);
+        equity_repository.delete_equity_from_position(position.id);
+        selection_repository.delete_selection_from_position(position.id);
         position_repository.delete_position(position.id);
     }
 
+    // Initial position
     let mut position = Position::from_fiat(&position_description, dec!(1000));
     position_repository.insert_position(&mut position)?;
 
-    let position_register = PositionRegister::new(position, flow_register);
-
-    let trader_register = TraderRegister::from(position_register);
-
-    // Create trader from trend provider
-    let trader_factory = TraderFactory::from(
-        app.selection.candles_selection,
-        // TODO This candles_provider can get the real final end to buffer next candles
-        app.candles_provider.clone(),
-    );
+    let position_register =
+        PositionRegister::new(position, flow_register, cost_model, margin_opt, lot_mode);
 
-    let script_trend_provider = ScriptTrendProvider::new();
-
-    let mut trader = trader_factory.create_trader(script_trend_provider, trader_register);
-
-    // Run trader from candles, this invoke script_trend_provider.trend()
-    candles.iter().for_each(|c| {
-        trader.check(c.close_time, Price(c.close)).unwrap();
-    });
+    let trader = back_test(
+        app,
+        &prices,
+        &lower_candles,
+        position_register,
+        funding_rates,
+        settings,
+    )?;
 
     info!(
         "{}",
         iformat!(
-            "Finished back test, total read candles: {candles.len()} elapsed: {start.elapsed():?}"
+            "Finished back test, total read candles: {prices.len()} elapsed: {start.elapsed():?}"
         )
         .bright_cyan()
     );
 
+    {
+        let position_register = trader.position_register();
+        let gross_gain = position_register.gross_gain().round_dp(2);
+        let fees = position_register.fees.round_dp(2);
+        let slippage = position_register.slippage.round_dp(2);
+        let funding = position_register.funding.round_dp(2);
+        let net_gain = position_register.net_gain().round_dp(2);
+        info!(
+            "{}",
+            iformat!(
+                "Gross P&L USD {gross_gain} fees {fees} slippage {slippage} funding {funding} \
+                net P&L USD {net_gain}"
+            )
+            .bright_cyan()
+        );
+    }
+
+    // Performance from the flows and the equity at each price candle
+    let position_id = trader.position_register().position.id;
+    let flows = flow_repository.read_flows_by_position(position_id)?;
+    let report = PerformanceReport::new(trader.equity(), &trade_pnls(&flows), &prices);
+    info!("{}", iformat!("Performance:\n{report}").bright_cyan());
+
+    // Equity and selection to plot again the back test later
+    equity_repository.insert_equity(position_id, trader.equity())?;
+    selection_repository.insert_selection(
+        position_id,
+        &app.selection.candles_selection,
+        app.selection.pair_symbol_opt,
+    )?;
+
     // Get realized trades
     let trades = trader.trades();
 
+    plot_back_test(
+        app,
+        &candles,
+        &trades,
+        plot_equity.then(|| (trader.equity(), &prices[..])),
+    )?;
+
+    // Report next to the image
+    let report_path = Path::new(&app.selection.image_name).with_extension("json");
+    report.save(&report_path)?;
+    info!("Saved performance report {}", report_path.display());
+
+    Ok(trades)
+}
+
+/// Plot the previous back test of the script from its flows and equity
+pub fn plot_script<P: AsRef<Path>>(
+    pool: Arc<RwLock<PgPool>>,
+    app: &mut Application,
+    script_file: P,
+    plot_equity: bool,
+) -> eyre::Result<()> {
+    // Engine evaluates the script indicators
+    EngineSingleton::install(&script_file)?;
+
+    let position_description = path_to_description(&script_file);
+    let position = PositionRepository::new(pool.clone())
+        .position_by_description(&position_description)
+        .ok_or_else(|| {
+            eyre!(
+                "Position {} not found, run its back test!",
+                position_description
+            )
+        })?;
+
+    // Candles of the back test run, whatever the current selection
+    let (candles_selection, pair_symbol_opt) = BackTestSelectionRepository::new(pool.clone())
+        .read_selection_by_position(position.id)?
+        .ok_or_else(|| {
+            eyre!(
+                "Selection of {} not found, run its back test again!",
+                position_description
+            )
+        })?;
+    app.selection.candles_selection = candles_selection;
+    app.selection.pair_symbol_opt = pair_symbol_opt;
+
+    let (prices, candles) = selection_candles(app)?;
+
+    let flows = FlowRepository::new(pool.clone())
+        .read_flows_by_position(position.id)?
+        .into_iter()
+        .filter(|f| f.log.as_deref() != Some(FUNDING_LOG))
+        .collect::<Vec<_>>();
+    let trades = TradeOperation::from_flows(&flows);
+    let equity = EquityRepository::new(pool).read_equity_by_position(position.id)?;
+
+    plot_back_test(
+        app,
+        &candles,
+        &trades,
+        plot_equity.then(|| (&equity[..], &prices[..])),
+    )
+}
+
+/// Plot the trades and script indicators over the candles, with the equity against the prices
+/// in lower panels
+fn plot_back_test(
+    app: &mut Application,
+    candles: &[Candle],
+    trades: &[TradeOperation],
+    equity_opt: Option<(&[EquityPoint], &[Candle])>,
+) -> eyre::Result<()> {
+    // Script indicators over back test candles
+    let engine = EngineSingleton::current();
+    let mut script_upper_series = Vec::new();
+    let mut script_lower_tecs = Vec::new();
+    for definition in engine.script_indicators() {
+        let serie = engine.eval_indicator(&definition.name, candles)?;
+        app.selection
+            .tacs
+            .insert(definition.name.clone(), definition.tac_definition());
+        match definition.plot_style {
+            PlotStyle::Upper => script_upper_series.push(serie),
+            PlotStyle::Lower => script_lower_tecs.push(ScriptIndicatorTec::new(serie)),
+        }
+    }
+
+    // Equity against holding the asset and drawdown panels, flat series can't be plotted
+    let equity_tecs = equity_opt
+        .map(|(equity, prices)| {
+            vec![
+                EquityTec::equity(equity, prices),
+                EquityTec::drawdown(equity),
+            ]
+        })
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|t| !t.is_flat())
+        .collect::<Vec<_>>();
+    for tec in equity_tecs.iter() {
+        app.selection.tacs.insert(tec.name(), tec.tac_definition());
+    }
+
     {
         // Create default plotter selection
         app.selection.image_name = "out/back_test.png".into();
         let mut plotter_selection =
             PlotterSelection::from(app.selection.clone(), app.candles_provider.clone_provider());
+        plotter_selection.set_trade_agg_repository(app.trade_agg_repository.clone());
+        if let Some(pair_candles_provider) = app.pair_candles_provider() {
+            plotter_selection.set_pair_candles_provider(pair_candles_provider);
+        }
 
         // Add plotter for trading marks
-        let trading_plotter = TradingPlotter::new(&trades);
+        let trading_plotter = TradingPlotter::new(trades);
         let plotters = vec![Box::new(trading_plotter) as Box<dyn PlotterIndicatorContext>];
         plotters
             .into_iter()
             .for_each(|p| plotter_selection.push_plotter_ind(p));
 
+        // script indicators = teal
+        let teal = RGBColor(0, 128, 128);
+        script_upper_series.iter().for_each(|s| {
+            plotter_selection.push_plotter_ind(Box::new(LineIndicatorPlotter::new(s, teal)))
+        });
+        script_lower_tecs.iter().for_each(|t| {
+            plotter_selection.push_plotter_lower_ind(Box::new(LinesAreaPlotter::new(t, teal)))
+        });
+        equity_tecs.iter().for_each(|t| {
+            plotter_selection.push_plotter_lower_ind(Box::new(EquityPlotter::new(t)))
+        });
+
         // Plot image
         plotter_selection.plot()?;
     }
 
-    Ok(trades)
+    Ok(())
 }
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,15 +1,34 @@
 use super::{
     script_state_singleton::ScriptStateSingleton, singleton_context::ContextSingleton,
-    singleton_position::PositionRegisterSingleton,
+    singleton_engine::EngineSingleton, singleton_position::PositionRegisterSingleton,
 };
+use crate::config::candle_type::CandleType;
+use crate::model::candle::Candle;
 use crate::model::operation::Operation;
+use crate::model::order::{Order, OrderAction, OrderKind, TimeInForce};
+use crate::model::price::Price;
 use crate::model::quantity::Quantity;
+use crate::services::technicals::ichimoku_tec::{self, IND_CLOUD, IND_TK_CROSS};
+use crate::services::technicals::ind_registry::{definition, REGISTRY};
+use crate::services::technicals::ind_stream::{StreamIndicator, HISTORY_LEN};
 use crate::services::technicals::ind_type::IndicatorType;
+use crate::services::technicals::pair_tec::PairStats;
+use crate::services::technicals::support_resistance_tec::{
+    nearest_resistance, nearest_support, zones, SupportResistanceZone, ZONE_NEIGHBORS,
+    ZONE_TOLERANCE,
+};
+use crate::services::technicals::swing_stream::SwingSerie;
+use crate::services::technicals::top_bottom::{TopBottom, TopBottomType};
+use crate::services::technicals::volume_profile_tec::{VolumeProfileTec, PROFILE_BINS};
+use crate::services::trading::chart_pattern::{
+    last_chart_patterns, PATTERN_SWINGS, PATTERN_TOLERANCE,
+};
 use crate::services::trading::trend::trend_direction::TrendDirection;
 use crate::utils::dec_utils::fdec;
-use crate::utils::dec_utils::percent;
+use chrono::{DateTime, Utc};
 use colored::Colorize;
 use log::info;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map};
 use rust_decimal::{
     prelude::{FromPrimitive, ToPrimitive},
     Decimal,
@@ -25,99 +44,733 @@ pub fn price() -> f64 {
     price_dec().to_f64().unwrap()
 }
 
-pub fn gain_perc() -> f64 {
-    if !is_bought() {
-        return 0.;
-    }
-    let singleton = PositionRegisterSingleton::current();
-    let position_register = singleton.position_opt.as_ref().unwrap();
-    let old = position_register.position.real_balance_fiat_r();
-    let new = price_dec() * position_register.position.balance_asset_r();
-    percent(&new, &old).to_f64().unwrap()
+/// Last `len` closed candles of timeframe `min`, oldest first
+fn candles(min: i64, len: i64) -> Vec<Candle> {
+    let singleton = ContextSingleton::current();
+    let trade_context_provider = singleton.trade_context_provider_opt.as_ref().unwrap();
+    trade_context_provider
+        .candles(min as i32, len.max(0) as usize)
+        .unwrap()
 }
 
-pub fn log(text: String) {
-    info!("{} {}", "[SCRIPT]".bright_yellow(), &text.yellow());
+/// Candle `bars_ago` candles before the last closed one
+fn candle_at(min: i64, bars_ago: i64) -> Option<Candle> {
+    let candles = candles(min, bars_ago.max(0) + 1);
+    (candles.len() as i64 > bars_ago.max(0)).then(|| candles[0])
+}
 
-    let singleton = ScriptStateSingleton::current();
-    let trade_context_provider = singleton.script_state_opt.as_ref().unwrap();
+fn candle_value(min: i64, bars_ago: i64, value: fn(&Candle) -> Decimal) -> f64 {
+    candle_at(min, bars_ago)
+        .map(|c| value(&c).to_f64().unwrap())
+        .unwrap_or(f64::NAN)
+}
 
-    let mut trade_context_provider = trade_context_provider.clone();
-    trade_context_provider.log = Some(text);
-    ScriptStateSingleton::set_current(trade_context_provider);
+fn candle_values(min: i64, len: i64, value: fn(&Candle) -> Decimal) -> Array {
+    to_array(
+        candles(min, len)
+            .iter()
+            .map(|c| value(c).to_f64().unwrap())
+            .collect(),
+    )
+}
+
+/// Open of candle `bars_ago` candles before the last closed one, NaN when not available
+pub fn open(min: i64, bars_ago: i64) -> f64 {
+    candle_value(min, bars_ago, |c| c.open)
+}
+
+pub fn high(min: i64, bars_ago: i64) -> f64 {
+    candle_value(min, bars_ago, |c| c.high)
+}
+
+pub fn low(min: i64, bars_ago: i64) -> f64 {
+    candle_value(min, bars_ago, |c| c.low)
+}
+
+pub fn close(min: i64, bars_ago: i64) -> f64 {
+    candle_value(min, bars_ago, |c| c.close)
+}
+
+pub fn volume(min: i64, bars_ago: i64) -> f64 {
+    candle_value(min, bars_ago, |c| c.volume)
+}
+
+/// Close time timestamp of candle `bars_ago` candles before the last closed one, -1 when not
+/// available
+pub fn candle_time(min: i64, bars_ago: i64) -> i64 {
+    candle_at(min, bars_ago)
+        .map(|c| c.close_time.timestamp())
+        .unwrap_or(-1)
+}
+
+/// Opens of last `len` closed candles, oldest first
+pub fn opens(min: i64, len: i64) -> Array {
+    candle_values(min, len, |c| c.open)
+}
+
+pub fn highs(min: i64, len: i64) -> Array {
+    candle_values(min, len, |c| c.high)
+}
+
+pub fn lows(min: i64, len: i64) -> Array {
+    candle_values(min, len, |c| c.low)
+}
+
+pub fn closes(min: i64, len: i64) -> Array {
+    candle_values(min, len, |c| c.close)
 }
 
-pub fn macd(min: i64, a: i64, b: i64, c: i64) -> f64 {
+pub fn volumes(min: i64, len: i64) -> Array {
+    candle_values(min, len, |c| c.volume)
+}
+
+pub fn candle_times(min: i64, len: i64) -> Array {
+    candles(min, len)
+        .iter()
+        .map(|c| Dynamic::from(c.close_time.timestamp()))
+        .collect()
+}
+
+fn swings(min: i64, neighbors: i64, swing_serie: SwingSerie, len: i64) -> Vec<TopBottom> {
     let singleton = ContextSingleton::current();
     let trade_context_provider = singleton.trade_context_provider_opt.as_ref().unwrap();
     trade_context_provider
-        .value(
+        .swings(
             min as i32,
-            &IndicatorType::Macd(a as usize, b as usize, c as usize),
+            neighbors.max(0) as usize,
+            swing_serie,
+            len.max(0) as usize,
         )
         .unwrap()
 }
 
-pub fn macd_signal(min: i64, a: i64, b: i64, c: i64) -> f64 {
+/// Swings as maps with `time` (swing candle close time timestamp), `price` and `top`
+fn swings_to_array(swings: Vec<TopBottom>) -> Array {
+    swings
+        .into_iter()
+        .map(|s| {
+            let mut map = Map::new();
+            map.insert("time".into(), Dynamic::from(s.close_time.timestamp()));
+            map.insert("price".into(), Dynamic::from(s.price.to_f64().unwrap()));
+            map.insert("top".into(), Dynamic::from(s.type_p == TopBottomType::Top));
+            Dynamic::from(map)
+        })
+        .collect()
+}
+
+/// Last `len` tops confirmed `neighbors` candles later, oldest first
+pub fn tops(min: i64, neighbors: i64, len: i64) -> Array {
+    swings_to_array(swings(min, neighbors, SwingSerie::Tops, len))
+}
+
+pub fn bottoms(min: i64, neighbors: i64, len: i64) -> Array {
+    swings_to_array(swings(min, neighbors, SwingSerie::Bottoms, len))
+}
+
+/// Last `len` alternating tops and bottoms, oldest first
+pub fn zigzag(min: i64, neighbors: i64, len: i64) -> Array {
+    swings_to_array(swings(min, neighbors, SwingSerie::Zigzag, len))
+}
+
+/// If last swing price is greater than the previous one, false with less than two swings
+fn last_swing_greater(min: i64, neighbors: i64, swing_serie: SwingSerie) -> Option<bool> {
+    match &swings(min, neighbors, swing_serie, 2)[..] {
+        [previous, last] => Some(last.price > previous.price),
+        _ => None,
+    }
+}
+
+pub fn higher_high(min: i64, neighbors: i64) -> bool {
+    last_swing_greater(min, neighbors, SwingSerie::Tops) == Some(true)
+}
+
+pub fn lower_high(min: i64, neighbors: i64) -> bool {
+    last_swing_greater(min, neighbors, SwingSerie::Tops) == Some(false)
+}
+
+pub fn higher_low(min: i64, neighbors: i64) -> bool {
+    last_swing_greater(min, neighbors, SwingSerie::Bottoms) == Some(true)
+}
+
+pub fn lower_low(min: i64, neighbors: i64) -> bool {
+    last_swing_greater(min, neighbors, SwingSerie::Bottoms) == Some(false)
+}
+
+/// Chart patterns ending with the last confirmed swing, as maps with `name`, `time` (last
+/// swing close time timestamp), `breakout`, `bullish` and `quality`
+pub fn last_patterns_tolerance(min: i64, neighbors: i64, tolerance: f64) -> Array {
+    let swings = swings(min, neighbors, SwingSerie::Zigzag, PATTERN_SWINGS as i64);
+    last_chart_patterns(&swings, tolerance)
+        .into_iter()
+        .map(|p| {
+            let mut map = Map::new();
+            map.insert("name".into(), Dynamic::from(p.pattern_type.name()));
+            map.insert("time".into(), Dynamic::from(p.end_time.timestamp()));
+            map.insert("breakout".into(), Dynamic::from(p.breakout));
+            map.insert("bullish".into(), Dynamic::from(p.bullish));
+            map.insert("quality".into(), Dynamic::from(p.quality));
+            Dynamic::from(map)
+        })
+        .collect()
+}
+
+pub fn last_patterns(min: i64, neighbors: i64) -> Array {
+    last_patterns_tolerance(min, neighbors, PATTERN_TOLERANCE)
+}
+
+/// If the last confirmed swing completes the named chart pattern
+pub fn is_pattern(min: i64, neighbors: i64, name: &str) -> bool {
+    let swings = swings(min, neighbors, SwingSerie::Zigzag, PATTERN_SWINGS as i64);
+    last_chart_patterns(&swings, PATTERN_TOLERANCE)
+        .iter()
+        .any(|p| p.pattern_type.name() == name)
+}
+
+/// Support/resistance zones of the swings confirmed until now
+fn swing_zones(min: i64, neighbors: i64) -> Vec<SupportResistanceZone> {
+    let mut top_bottoms = swings(min, neighbors, SwingSerie::Tops, HISTORY_LEN as i64);
+    top_bottoms.extend(swings(
+        min,
+        neighbors,
+        SwingSerie::Bottoms,
+        HISTORY_LEN as i64,
+    ));
+    zones(&top_bottoms, ZONE_TOLERANCE, None)
+}
+
+/// Price of the nearest zone under the current price, NaN when there is none
+pub fn nearest_support_neighbors(min: i64, neighbors: i64) -> f64 {
+    nearest_support(&swing_zones(min, neighbors), price())
+        .map(|z| z.price())
+        .unwrap_or(f64::NAN)
+}
+
+/// Price of the nearest zone over the current price, NaN when there is none
+pub fn nearest_resistance_neighbors(min: i64, neighbors: i64) -> f64 {
+    nearest_resistance(&swing_zones(min, neighbors), price())
+        .map(|z| z.price())
+        .unwrap_or(f64::NAN)
+}
+
+pub fn nearest_support_default(min: i64) -> f64 {
+    nearest_support_neighbors(min, ZONE_NEIGHBORS as i64)
+}
+
+pub fn nearest_resistance_default(min: i64) -> f64 {
+    nearest_resistance_neighbors(min, ZONE_NEIGHBORS as i64)
+}
+
+/// Volume profile of the last `len` candles closed until now, each candle volume spread over
+/// its range in `bins` price levels
+fn volume_profile(min: i64, len: i64, bins: i64) -> VolumeProfileTec {
+    VolumeProfileTec::new(&candles(min, len), bins.max(0) as usize)
+}
+
+/// Point of control price, NaN when the candles have no price range
+pub fn poc_bins(min: i64, len: i64, bins: i64) -> f64 {
+    volume_profile(min, len, bins).poc().unwrap_or(f64::NAN)
+}
+
+/// Value area high price, NaN when the candles have no price range
+pub fn value_area_high_bins(min: i64, len: i64, bins: i64) -> f64 {
+    volume_profile(min, len, bins).vah().unwrap_or(f64::NAN)
+}
+
+/// Value area low price, NaN when the candles have no price range
+pub fn value_area_low_bins(min: i64, len: i64, bins: i64) -> f64 {
+    volume_profile(min, len, bins).val().unwrap_or(f64::NAN)
+}
+
+pub fn poc(min: i64, len: i64) -> f64 {
+    poc_bins(min, len, PROFILE_BINS as i64)
+}
+
+pub fn value_area_high(min: i64, len: i64) -> f64 {
+    value_area_high_bins(min, len, PROFILE_BINS as i64)
+}
+
+pub fn value_area_low(min: i64, len: i64) -> f64 {
+    value_area_low_bins(min, len, PROFILE_BINS as i64)
+}
+
+/// Divergences of the last swings as maps with `name`, `bullish`, `hidden`, `time` (last swing
+/// close time timestamp), `price` and `value` (oscillator value at last swing)
+fn divergences(
+    min: i64,
+    neighbors: i64,
+    indicator_type: &IndicatorType,
+) -> Result<Array, Box<EvalAltResult>> {
     let singleton = ContextSingleton::current();
     let trade_context_provider = singleton.trade_context_provider_opt.as_ref().unwrap();
-    trade_context_provider
-        .value(
-            min as i32,
-            &IndicatorType::MacdSignal(a as usize, b as usize, c as usize),
-        )
-        .unwrap()
+    Ok(trade_context_provider
+        .divergences(min as i32, neighbors.max(0) as usize, indicator_type)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|d| {
+            let mut map = Map::new();
+            map.insert("name".into(), Dynamic::from(d.divergence_type.name()));
+            map.insert(
+                "bullish".into(),
+                Dynamic::from(d.divergence_type.is_bullish()),
+            );
+            map.insert(
+                "hidden".into(),
+                Dynamic::from(d.divergence_type.is_hidden()),
+            );
+            map.insert("time".into(), Dynamic::from(d.end_time.timestamp()));
+            map.insert("price".into(), Dynamic::from(d.end_price));
+            map.insert("value".into(), Dynamic::from(d.end_value));
+            Dynamic::from(map)
+        })
+        .collect())
 }
 
-pub fn macd_divergence(min: i64, a: i64, b: i64, c: i64) -> f64 {
+/// Divergences with the main output of the registered indicator `name` computed with `params`
+fn registered_divergences(
+    min: i64,
+    neighbors: i64,
+    name: &str,
+    params: &[i64],
+) -> Result<Array, Box<EvalAltResult>> {
+    let definition =
+        definition(name).ok_or_else(|| format!("Not registered indicator {}!", name))?;
+    if params.len() != definition.params.len() {
+        return Err(format!("Indicator {} has {} params!", name, definition.params.len()).into());
+    }
+    let params = params
+        .iter()
+        .map(|p| (*p).max(0) as usize)
+        .collect::<Vec<_>>();
+    divergences(
+        min,
+        neighbors,
+        &definition.indicator_type(definition.outputs[0].name, &params),
+    )
+}
+
+/// Register `divergences(min, neighbors, name, params…)` for the oscillators streamed from the
+/// registry, e.g. `divergences(15, 7, "rsi", 14)` or `divergences(15, 7, "macd", 12, 26, 9)`
+pub fn register_divergences(engine: &mut Engine) {
+    engine.register_fn(
+        "divergences",
+        |min: i64, neighbors: i64, name: &str, a: i64| {
+            registered_divergences(min, neighbors, name, &[a])
+        },
+    );
+    engine.register_fn(
+        "divergences",
+        |min: i64, neighbors: i64, name: &str, a: i64, b: i64| {
+            registered_divergences(min, neighbors, name, &[a, b])
+        },
+    );
+    engine.register_fn(
+        "divergences",
+        |min: i64, neighbors: i64, name: &str, a: i64, b: i64, c: i64| {
+            registered_divergences(min, neighbors, name, &[a, b, c])
+        },
+    );
+}
+
+/// Last `len` closed candles of timeframe `min` of another symbol (e.g. "BTCUSDT")
+fn symbol_candles(min: i64, pair: &str, len: i64) -> Vec<Candle> {
     let singleton = ContextSingleton::current();
     let trade_context_provider = singleton.trade_context_provider_opt.as_ref().unwrap();
     trade_context_provider
-        .value(
-            min as i32,
-            &IndicatorType::MacdDivergence(a as usize, b as usize, c as usize),
-        )
+        .symbol_candles(pair, min as i32, len.max(0) as usize)
         .unwrap()
 }
 
-pub fn ema(min: i64, a: i64) -> f64 {
+/// Close of another symbol `bars_ago` candles before the last closed one, NaN when missing
+pub fn symbol_close(min: i64, pair: &str, bars_ago: i64) -> f64 {
+    let candles = symbol_candles(min, pair, bars_ago.max(0) + 1);
+    if candles.len() as i64 > bars_ago.max(0) {
+        candles[0].close.to_f64().unwrap()
+    } else {
+        f64::NAN
+    }
+}
+
+pub fn symbol_closes(min: i64, pair: &str, len: i64) -> Array {
+    to_array(
+        symbol_candles(min, pair, len)
+            .iter()
+            .map(|c| c.close.to_f64().unwrap())
+            .collect(),
+    )
+}
+
+fn pair_stats(min: i64, pair: &str, period: i64) -> PairStats {
     let singleton = ContextSingleton::current();
     let trade_context_provider = singleton.trade_context_provider_opt.as_ref().unwrap();
     trade_context_provider
-        .value(min as i32, &IndicatorType::Ema(a as usize))
+        .pair_stats(pair, min as i32, period.max(0) as usize)
         .unwrap()
 }
 
-pub fn sma(min: i64, a: i64) -> f64 {
+/// Last close divided by the last close of another symbol
+pub fn ratio(min: i64, pair: &str) -> f64 {
+    candle_value(min, 0, |c| c.close) / symbol_close(min, pair, 0)
+}
+
+/// Correlation of the returns with another symbol over last `period` candles
+pub fn correlation(min: i64, pair: &str, period: i64) -> f64 {
+    pair_stats(min, pair, period).correlation
+}
+
+/// Returns regression slope on the returns of another symbol over last `period` candles
+pub fn beta(min: i64, pair: &str, period: i64) -> f64 {
+    pair_stats(min, pair, period).beta
+}
+
+/// Z-score of the log price spread with another symbol over last `period` candles
+pub fn spread(min: i64, pair: &str, period: i64) -> f64 {
+    pair_stats(min, pair, period).spread
+}
+
+/// Gain in percent of the open long or short position at the current price from its entry price
+pub fn gain_perc() -> f64 {
+    let singleton = PositionRegisterSingleton::current();
+    let position_register = singleton.position_opt.as_ref().unwrap();
+    position_register.gain_perc(price_dec()).to_f64().unwrap()
+}
+
+/// Optimized parameter rounded to an integer, the default when not optimized
+pub fn param_int(name: &str, default: i64) -> i64 {
+    EngineSingleton::current()
+        .param(name)
+        .map(|value| value.round() as i64)
+        .unwrap_or(default)
+}
+
+pub fn param_float(name: &str, default: f64) -> f64 {
+    EngineSingleton::current().param(name).unwrap_or(default)
+}
+
+pub fn log(text: String) {
+    info!("{} {}", "[SCRIPT]".bright_yellow(), &text.yellow());
+
+    let singleton = ScriptStateSingleton::current();
+    let trade_context_provider = singleton.script_state_opt.as_ref().unwrap();
+
+    let mut trade_context_provider = trade_context_provider.clone();
+    trade_context_provider.log = Some(text);
+    ScriptStateSingleton::set_current(trade_context_provider);
+}
+
+/// If candle close is over the Ichimoku cloud
+pub fn price_above_cloud(min: i64, a: i64, b: i64, c: i64) -> bool {
+    value(min, &ichimoku(IND_CLOUD, a, b, c)) > 0.
+}
+
+/// If candle close is under the Ichimoku cloud
+pub fn price_below_cloud(min: i64, a: i64, b: i64, c: i64) -> bool {
+    value(min, &ichimoku(IND_CLOUD, a, b, c)) < 0.
+}
+
+/// If candle close is between Ichimoku senkou spans
+pub fn price_in_cloud(min: i64, a: i64, b: i64, c: i64) -> bool {
+    value(min, &ichimoku(IND_CLOUD, a, b, c)) == 0.
+}
+
+/// If tenkan crossed above kijun on last candle
+pub fn tk_cross_up(min: i64, a: i64, b: i64, c: i64) -> bool {
+    value(min, &ichimoku(IND_TK_CROSS, a, b, c)) > 0.
+}
+
+/// If tenkan crossed below kijun on last candle
+pub fn tk_cross_down(min: i64, a: i64, b: i64, c: i64) -> bool {
+    value(min, &ichimoku(IND_TK_CROSS, a, b, c)) < 0.
+}
+
+fn ichimoku(output: &'static str, a: i64, b: i64, c: i64) -> IndicatorType {
+    ichimoku_tec::DEFINITION.indicator_type(output, &[a as usize, b as usize, c as usize])
+}
+
+fn value(min: i64, indicator_type: &IndicatorType) -> f64 {
     let singleton = ContextSingleton::current();
     let trade_context_provider = singleton.trade_context_provider_opt.as_ref().unwrap();
     trade_context_provider
-        .value(min as i32, &IndicatorType::Sma(a as usize))
+        .value(min as i32, indicator_type)
         .unwrap()
 }
 
-pub fn rsi(min: i64, a: i64) -> f64 {
+/// Register the functions of each registered output exposed to scripts, called with the
+/// minutes followed by the params:
+/// - `rsi(15, 14)` last value
+/// - `rsi(15, 14, "ha")` last value computed from the given candle type
+/// - `rsi_series(15, 14, 20, "ha")` last 20 values computed from the given candle type
+/// - `rsi_at(15, 14, 1)` value of the previous candle, when streamed
+/// - `rsi_series(15, 14, 20)` last 20 values, when streamed
+///
+/// Streamed indicators keep the last `HISTORY_LEN` (500) values only, `_at` further back is
+/// NaN and `_series` returns at most that many values.
+pub fn register_indicators(engine: &mut Engine) {
+    for definition in REGISTRY.iter() {
+        let default_params = definition.default_params();
+        for output in definition.outputs.iter() {
+            let name = match output.script_opt {
+                Some(name) => name,
+                None => continue,
+            };
+            let at = format!("{}_at", name);
+            let series_name = format!("{}_series", name);
+            let streamed = StreamIndicator::is_streamed(
+                &definition.indicator_type(output.name, &default_params),
+            );
+            let t = move |params: &[i64]| {
+                let params = params.iter().map(|p| *p as usize).collect::<Vec<_>>();
+                definition.indicator_type(output.name, &params)
+            };
+            match definition.params.len() {
+                1 => {
+                    engine.register_fn(name, move |min: i64, a: i64| value(min, &t(&[a])));
+                    engine.register_fn(name, move |min: i64, a: i64, candle_type: &str| {
+                        candles_value(min, candle_type, &t(&[a]))
+                    });
+                    engine.register_fn(
+                        &series_name,
+                        move |min: i64, a: i64, len: i64, candle_type: &str| {
+                            candles_series(min, candle_type, &t(&[a]), len)
+                        },
+                    );
+                    if streamed {
+                        engine.register_fn(&at, move |min: i64, a: i64, bars_ago: i64| {
+                            indicator_at_type(min, &t(&[a]), bars_ago)
+                        });
+                        engine.register_fn(&series_name, move |min: i64, a: i64, len: i64| {
+                            to_array(series(min, &t(&[a]), len))
+                        });
+                    }
+                }
+                2 => {
+                    engine.register_fn(name, move |min: i64, a: i64, b: i64| {
+                        value(min, &t(&[a, b]))
+                    });
+                    engine.register_fn(name, move |min: i64, a: i64, b: i64, candle_type: &str| {
+                        candles_value(min, candle_type, &t(&[a, b]))
+                    });
+                    engine.register_fn(
+                        &series_name,
+                        move |min: i64, a: i64, b: i64, len: i64, candle_type: &str| {
+                            candles_series(min, candle_type, &t(&[a, b]), len)
+                        },
+                    );
+                    if streamed {
+                        engine.register_fn(&at, move |min: i64, a: i64, b: i64, bars_ago: i64| {
+                            indicator_at_type(min, &t(&[a, b]), bars_ago)
+                        });
+                        engine.register_fn(
+                            &series_name,
+                            move |min: i64, a: i64, b: i64, len: i64| {
+                                to_array(series(min, &t(&[a, b]), len))
+                            },
+                        );
+                    }
+                }
+                _ => {
+                    engine.register_fn(name, move |min: i64, a: i64, b: i64, c: i64| {
+                        value(min, &t(&[a, b, c]))
+                    });
+                    engine.register_fn(
+                        name,
+                        move |min: i64, a: i64, b: i64, c: i64, candle_type: &str| {
+                            candles_value(min, candle_type, &t(&[a, b, c]))
+                        },
+                    );
+                    engine.register_fn(
+                        &series_name,
+                        move |min: i64, a: i64, b: i64, c: i64, len: i64, candle_type: &str| {
+                            candles_series(min, candle_type, &t(&[a, b, c]), len)
+                        },
+                    );
+                    if streamed {
+                        engine.register_fn(
+                            &at,
+                            move |min: i64, a: i64, b: i64, c: i64, bars_ago: i64| {
+                                indicator_at_type(min, &t(&[a, b, c]), bars_ago)
+                            },
+                        );
+                        engine.register_fn(
+                            &series_name,
+                            move |min: i64, a: i64, b: i64, c: i64, len: i64| {
+                                to_array(series(min, &t(&[a, b, c]), len))
+                            },
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Value of indicator declared by the script as `fn indicator_<name>(candles)` or
+/// `fn overlay_<name>(candles)`, computed once over the selection candles of timeframe
+pub fn indicator(min: i64, name: &str) -> f64 {
+    value_at(&script_series(min, name, 1), 0)
+}
+
+pub fn indicator_at(min: i64, name: &str, bars_ago: i64) -> f64 {
+    value_at(&script_series(min, name, bars_ago.max(0) + 1), bars_ago)
+}
+
+pub fn indicator_series(min: i64, name: &str, len: i64) -> Array {
+    to_array(script_series(min, name, len))
+}
+
+fn script_series(min: i64, name: &str, len: i64) -> Vec<f64> {
     let singleton = ContextSingleton::current();
     let trade_context_provider = singleton.trade_context_provider_opt.as_ref().unwrap();
     trade_context_provider
-        .value(min as i32, &IndicatorType::Rsi(a as usize))
+        .script_values(min as i32, name, len.max(0) as usize, |candles| {
+            EngineSingleton::current().eval_indicator(name, candles)
+        })
         .unwrap()
 }
 
-pub fn min(min: i64, a: i64) -> f64 {
+/// Values of last `len` candles of timeframe `min`, oldest first
+fn series(min: i64, indicator_type: &IndicatorType, len: i64) -> Vec<f64> {
     let singleton = ContextSingleton::current();
     let trade_context_provider = singleton.trade_context_provider_opt.as_ref().unwrap();
     trade_context_provider
-        .value(min as i32, &IndicatorType::Min(a as usize))
+        .values(min as i32, indicator_type, len.max(0) as usize)
         .unwrap()
 }
 
-pub fn max(min: i64, a: i64) -> f64 {
+/// Value `bars_ago` candles before the last one, NaN while there are not enough candles
+fn value_at(values: &[f64], bars_ago: i64) -> f64 {
+    values
+        .len()
+        .checked_sub(bars_ago.max(0) as usize + 1)
+        .map(|i| values[i])
+        .unwrap_or(f64::NAN)
+}
+
+fn indicator_at_type(min: i64, indicator_type: &IndicatorType, bars_ago: i64) -> f64 {
+    value_at(&series(min, indicator_type, bars_ago.max(0) + 1), bars_ago)
+}
+
+fn to_array(values: Vec<f64>) -> Array {
+    values.into_iter().map(Dynamic::from).collect()
+}
+
+fn to_floats(array: &[Dynamic]) -> Vec<f64> {
+    array
+        .iter()
+        .map(|d| {
+            d.as_float()
+                .or_else(|_| d.as_int().map(|i| i as f64))
+                .unwrap_or(f64::NAN)
+        })
+        .collect()
+}
+
+pub fn set_candle_type(min: i64, candle_type: &str) -> Result<(), Box<EvalAltResult>> {
+    let candle_type = parse_candle_type(candle_type)?;
     let singleton = ContextSingleton::current();
     let trade_context_provider = singleton.trade_context_provider_opt.as_ref().unwrap();
-    trade_context_provider
-        .value(min as i32, &IndicatorType::Max(a as usize))
-        .unwrap()
+    trade_context_provider.set_candle_type(min as i32, candle_type);
+    Ok(())
+}
+
+/// Candle type of a script, a typo raises a script error
+fn parse_candle_type(candle_type: &str) -> Result<CandleType, Box<EvalAltResult>> {
+    candle_type
+        .parse()
+        .map_err(|e: eyre::Error| e.to_string().into())
+}
+
+/// Value computed from the given candles of timeframe `min`
+fn candles_value(
+    min: i64,
+    candle_type: &str,
+    indicator_type: &IndicatorType,
+) -> Result<f64, Box<EvalAltResult>> {
+    let candle_type = parse_candle_type(candle_type)?;
+    let singleton = ContextSingleton::current();
+    let trade_context_provider = singleton.trade_context_provider_opt.as_ref().unwrap();
+    Ok(trade_context_provider
+        .candles_value(min as i32, candle_type, indicator_type)
+        .unwrap())
+}
+
+/// Values of last `len` given candles of timeframe `min`, oldest first
+fn candles_series(
+    min: i64,
+    candle_type: &str,
+    indicator_type: &IndicatorType,
+    len: i64,
+) -> Result<Array, Box<EvalAltResult>> {
+    let candle_type = parse_candle_type(candle_type)?;
+    let singleton = ContextSingleton::current();
+    let trade_context_provider = singleton.trade_context_provider_opt.as_ref().unwrap();
+    Ok(to_array(
+        trade_context_provider
+            .candles_values(min as i32, candle_type, indicator_type, len.max(0) as usize)
+            .unwrap(),
+    ))
+}
+
+/// Last and previous values of a serie
+fn last_two(serie: &[Dynamic]) -> Option<(f64, f64)> {
+    let values = to_floats(serie);
+    match values[..] {
+        [.., previous, last] => Some((previous, last)),
+        _ => None,
+    }
+}
+
+/// If serie `a` crossed above serie `b` on the last candle
+pub fn crosses_above(a: Array, b: Array) -> bool {
+    match (last_two(&a), last_two(&b)) {
+        (Some((a_previous, a_last)), Some((b_previous, b_last))) => {
+            a_previous <= b_previous && a_last > b_last
+        }
+        _ => false,
+    }
+}
+
+/// If serie `a` crossed below serie `b` on the last candle
+pub fn crosses_below(a: Array, b: Array) -> bool {
+    match (last_two(&a), last_two(&b)) {
+        (Some((a_previous, a_last)), Some((b_previous, b_last))) => {
+            a_previous >= b_previous && a_last < b_last
+        }
+        _ => false,
+    }
+}
+
+/// If serie crossed above `level` on the last candle
+pub fn crosses_above_level(serie: Array, level: f64) -> bool {
+    last_two(&serie)
+        .map(|(previous, last)| previous <= level && last > level)
+        .unwrap_or(false)
+}
+
+/// If serie crossed below `level` on the last candle
+pub fn crosses_below_level(serie: Array, level: f64) -> bool {
+    last_two(&serie)
+        .map(|(previous, last)| previous >= level && last < level)
+        .unwrap_or(false)
+}
+
+/// If serie went up on each of the last `n` candles
+pub fn rising(serie: Array, n: i64) -> bool {
+    let values = to_floats(&serie);
+    n > 0
+        && values.len() > n as usize
+        && values[values.len() - n as usize - 1..]
+            .windows(2)
+            .all(|w| w[1] > w[0])
+}
+
+/// If serie went down on each of the last `n` candles
+pub fn falling(serie: Array, n: i64) -> bool {
+    let values = to_floats(&serie);
+    n > 0
+        && values.len() > n as usize
+        && values[values.len() - n as usize - 1..]
+            .windows(2)
+            .all(|w| w[1] < w[0])
 }
 
 /// If I have more assets (equivalent value) than fiat
@@ -149,6 +802,245 @@ pub fn sell(quantity: f64) {
     ScriptStateSingleton::set_current(script_state);
 }
 
+/// Sells opening or increasing a short position, needs futures margin
+pub fn short(quantity: f64) {
+    let singleton = ScriptStateSingleton::current();
+    let script_state = singleton.script_state_opt.as_ref().unwrap();
+    let mut script_state = script_state.clone();
+    script_state.operation_opt = Some(Operation::Short(Quantity(fdec(quantity))));
+    ScriptStateSingleton::set_current(script_state);
+}
+
+/// Buys back up to the short position
+pub fn cover(quantity: f64) {
+    let singleton = ScriptStateSingleton::current();
+    let script_state = singleton.script_state_opt.as_ref().unwrap();
+    let mut script_state = script_state.clone();
+    script_state.operation_opt = Some(Operation::Cover(Quantity(fdec(quantity))));
+    ScriptStateSingleton::set_current(script_state);
+}
+
+/// `long`, `short` or `flat`
+pub fn position_side() -> String {
+    let singleton = PositionRegisterSingleton::current();
+    let position_register = singleton.position_opt.as_ref().unwrap();
+    position_register.position_side().to_string().to_lowercase()
+}
+
+/// Price liquidating the futures position, 0 without it
+pub fn liquidation_price() -> f64 {
+    let singleton = PositionRegisterSingleton::current();
+    let position_register = singleton.position_opt.as_ref().unwrap();
+    position_register
+        .liquidation_price()
+        .and_then(|price| price.to_f64())
+        .unwrap_or(0.)
+}
+
+/// Average entry price of the open lots, 0 when flat
+pub fn entry_price() -> f64 {
+    let singleton = PositionRegisterSingleton::current();
+    let position_register = singleton.position_opt.as_ref().unwrap();
+    position_register.lots.entry_price().to_f64().unwrap()
+}
+
+/// P&L of the open lots at the current price before fees
+pub fn unrealized_pnl() -> f64 {
+    let singleton = PositionRegisterSingleton::current();
+    let position_register = singleton.position_opt.as_ref().unwrap();
+    position_register
+        .lots
+        .unrealized_pnl(price_dec())
+        .to_f64()
+        .unwrap()
+}
+
+/// Candles since the oldest open lot, 0 when flat
+pub fn bars_in_trade() -> i64 {
+    let singleton = PositionRegisterSingleton::current();
+    let position_register = singleton.position_opt.as_ref().unwrap();
+    position_register.lots.bars_in_trade(position_register.bar)
+}
+
+/// Last funding rate of the futures position, 0 without it
+pub fn funding_rate() -> f64 {
+    let singleton = PositionRegisterSingleton::current();
+    let position_register = singleton.position_opt.as_ref().unwrap();
+    position_register
+        .funding_rate_opt
+        .and_then(|rate| rate.to_f64())
+        .unwrap_or(0.)
+}
+
+/// Adds the order action to the script state, returns the new order id
+fn push_order_action(action: impl FnOnce(i32) -> OrderAction) -> i64 {
+    let singleton = ScriptStateSingleton::current();
+    let script_state = singleton.script_state_opt.as_ref().unwrap();
+    let mut script_state = script_state.clone();
+    let id = script_state.next_order_id;
+    script_state.next_order_id += 1;
+    script_state.order_actions.push(action(id));
+    ScriptStateSingleton::set_current(script_state);
+    id as i64
+}
+
+/// Places a pending order, returns its id
+fn place_order(
+    operation: Operation,
+    kind: OrderKind,
+    time_in_force: &str,
+) -> Result<i64, Box<EvalAltResult>> {
+    let singleton = ScriptStateSingleton::current();
+    let script_state = singleton.script_state_opt.as_ref().unwrap();
+    let now = script_state.now;
+    let time_in_force = parse_time_in_force(time_in_force, now)?;
+    let log = script_state.log.clone();
+    Ok(push_order_action(|id| {
+        OrderAction::Place(Order::new(id, operation, kind, time_in_force, now, log))
+    }))
+}
+
+/// Time in force of a script order, a typo raises a script error
+fn parse_time_in_force(
+    time_in_force: &str,
+    now: DateTime<Utc>,
+) -> Result<TimeInForce, Box<EvalAltResult>> {
+    TimeInForce::parse(time_in_force, now).map_err(|e| e.to_string().into())
+}
+
+fn buy_quantity(quantity: f64) -> Operation {
+    Operation::Buy(Quantity(fdec(quantity)))
+}
+
+fn sell_quantity(quantity: f64) -> Operation {
+    Operation::Sell(Quantity(fdec(quantity)))
+}
+
+fn price_of(price: f64) -> Price {
+    Price(fdec(price))
+}
+
+pub fn buy_limit(quantity: f64, price: f64) -> Result<i64, Box<EvalAltResult>> {
+    buy_limit_tif(quantity, price, "gtc")
+}
+
+pub fn buy_limit_tif(
+    quantity: f64,
+    price: f64,
+    time_in_force: &str,
+) -> Result<i64, Box<EvalAltResult>> {
+    let kind = OrderKind::Limit(price_of(price));
+    place_order(buy_quantity(quantity), kind, time_in_force)
+}
+
+pub fn sell_limit(quantity: f64, price: f64) -> Result<i64, Box<EvalAltResult>> {
+    sell_limit_tif(quantity, price, "gtc")
+}
+
+pub fn sell_limit_tif(
+    quantity: f64,
+    price: f64,
+    time_in_force: &str,
+) -> Result<i64, Box<EvalAltResult>> {
+    let kind = OrderKind::Limit(price_of(price));
+    place_order(sell_quantity(quantity), kind, time_in_force)
+}
+
+pub fn buy_stop(quantity: f64, stop: f64) -> Result<i64, Box<EvalAltResult>> {
+    buy_stop_tif(quantity, stop, "gtc")
+}
+
+pub fn buy_stop_tif(
+    quantity: f64,
+    stop: f64,
+    time_in_force: &str,
+) -> Result<i64, Box<EvalAltResult>> {
+    let kind = OrderKind::Stop(price_of(stop));
+    place_order(buy_quantity(quantity), kind, time_in_force)
+}
+
+pub fn sell_stop(quantity: f64, stop: f64) -> Result<i64, Box<EvalAltResult>> {
+    sell_stop_tif(quantity, stop, "gtc")
+}
+
+pub fn sell_stop_tif(
+    quantity: f64,
+    stop: f64,
+    time_in_force: &str,
+) -> Result<i64, Box<EvalAltResult>> {
+    let kind = OrderKind::Stop(price_of(stop));
+    place_order(sell_quantity(quantity), kind, time_in_force)
+}
+
+pub fn buy_stop_limit(quantity: f64, stop: f64, limit: f64) -> Result<i64, Box<EvalAltResult>> {
+    buy_stop_limit_tif(quantity, stop, limit, "gtc")
+}
+
+pub fn buy_stop_limit_tif(
+    quantity: f64,
+    stop: f64,
+    limit: f64,
+    time_in_force: &str,
+) -> Result<i64, Box<EvalAltResult>> {
+    let kind = OrderKind::StopLimit {
+        stop: price_of(stop),
+        limit: price_of(limit),
+    };
+    place_order(buy_quantity(quantity), kind, time_in_force)
+}
+
+pub fn sell_stop_limit(quantity: f64, stop: f64, limit: f64) -> Result<i64, Box<EvalAltResult>> {
+    sell_stop_limit_tif(quantity, stop, limit, "gtc")
+}
+
+pub fn sell_stop_limit_tif(
+    quantity: f64,
+    stop: f64,
+    limit: f64,
+    time_in_force: &str,
+) -> Result<i64, Box<EvalAltResult>> {
+    let kind = OrderKind::StopLimit {
+        stop: price_of(stop),
+        limit: price_of(limit),
+    };
+    place_order(sell_quantity(quantity), kind, time_in_force)
+}
+
+/// Replaces the order quantity and price (limit of stop limits), returns the new order id
+pub fn replace_order(id: i64, quantity: f64, price: f64) -> i64 {
+    push_order_action(|new_id| OrderAction::Replace {
+        id: id as i32,
+        new_id,
+        quantity: Quantity(fdec(quantity)),
+        price: price_of(price),
+    })
+}
+
+pub fn cancel(id: i64) {
+    let singleton = ScriptStateSingleton::current();
+    let script_state = singleton.script_state_opt.as_ref().unwrap();
+    let mut script_state = script_state.clone();
+    script_state
+        .order_actions
+        .push(OrderAction::Cancel(id as i32));
+    ScriptStateSingleton::set_current(script_state);
+}
+
+pub fn cancel_all() {
+    let singleton = ScriptStateSingleton::current();
+    let script_state = singleton.script_state_opt.as_ref().unwrap();
+    let mut script_state = script_state.clone();
+    script_state.order_actions.push(OrderAction::CancelAll);
+    ScriptStateSingleton::set_current(script_state);
+}
+
+/// Pending orders placed before this candle
+pub fn open_orders() -> i64 {
+    let singleton = PositionRegisterSingleton::current();
+    let position_register = singleton.position_opt.as_ref().unwrap();
+    position_register.order_book.orders().len() as i64
+}
+
 pub fn change_trend_buy() -> bool {
     let singleton = ScriptStateSingleton::current();
     let script_state_provider = singleton.script_state_opt.as_ref().unwrap();
@@ -214,3 +1106,57 @@ pub fn asset_to_fiat(asset_quantity: f64) -> f64 {
         .to_f64()
         .unwrap()
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn serie(values: &[f64]) -> Array {
+        to_array(values.to_vec())
+    }
+
+    #[test]
+    fn crosses_test() {
+        assert!(crosses_above(serie(&[1., 3.]), serie(&[2., 2.])));
+        assert!(!crosses_above(serie(&[3., 4.]), serie(&[2., 2.])));
+        assert!(crosses_below(serie(&[0., 3., 1.]), serie(&[2., 2.])));
+        assert!(!crosses_below(serie(&[1.]), serie(&[2.])));
+        assert!(crosses_above_level(serie(&[25., 29., 31.]), 30.));
+        assert!(!crosses_below_level(serie(&[25., 29., 31.]), 30.));
+        assert!(!crosses_above(serie(&[f64::NAN, 3.]), serie(&[2., 2.])));
+    }
+
+    #[test]
+    fn rising_falling_test() {
+        assert!(rising(serie(&[5., 1., 2., 3.]), 2));
+        assert!(!rising(serie(&[5., 1., 2., 3.]), 3));
+        assert!(!rising(serie(&[1., 2.]), 2));
+        assert!(falling(serie(&[3., 2., 1.]), 2));
+        assert!(value_at(&[1., 2., 3.], 2) == 1.);
+        assert!(value_at(&[1., 2., 3.], 3).is_nan());
+    }
+
+    #[test]
+    fn registered_divergences_test() {
+        // Checked before reading the context
+        assert!(registered_divergences(15, 7, "unknown", &[14]).is_err());
+        assert!(registered_divergences(15, 7, "rsi", &[14, 2]).is_err());
+    }
+
+    #[test]
+    fn parse_candle_type_test() {
+        assert_eq!(parse_candle_type("raw").unwrap(), CandleType::Raw);
+        assert!(parse_candle_type("rengo:10").is_err());
+    }
+
+    #[test]
+    fn parse_time_in_force_test() {
+        let now = Utc::now();
+        assert!(matches!(
+            parse_time_in_force("gtt:30", now),
+            Ok(TimeInForce::Gtt(_))
+        ));
+        assert!(parse_time_in_force("day", now).is_err());
+        assert!(parse_time_in_force("gtt:soon", now).is_err());
+    }
+}
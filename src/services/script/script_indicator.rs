@@ -0,0 +1,230 @@
+use crate::config::definition::TacDefinition;
+use crate::model::candle::Candle;
+use crate::services::technicals::ind_registry::PlotStyle;
+use crate::services::technicals::serie::Serie;
+use crate::services::technicals::serie_indicator::SerieIndicator;
+use crate::services::technicals::technical::TecSerieIndicators;
+use eyre::eyre;
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST};
+use rust_decimal::prelude::ToPrimitive;
+use std::collections::HashMap;
+
+/// Script function prefix of indicators plotted in a lower panel
+pub const INDICATOR_PREFIX: &str = "indicator_";
+/// Script function prefix of indicators plotted over the candles
+pub const OVERLAY_PREFIX: &str = "overlay_";
+
+/// Indicator declared by a script as `fn indicator_<name>(candles)` or
+/// `fn overlay_<name>(candles)`, returning one value for each of the last candles
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScriptIndicatorDefinition {
+    pub name: String,
+    pub fn_name: String,
+    pub plot_style: PlotStyle,
+}
+
+impl ScriptIndicatorDefinition {
+    pub fn tac_definition(&self) -> TacDefinition {
+        TacDefinition::new(&self.name, &[&self.name])
+    }
+}
+
+pub fn script_indicators(ast: &AST) -> Vec<ScriptIndicatorDefinition> {
+    ast.iter_functions()
+        .filter(|f| f.params.len() == 1)
+        .filter_map(|f| {
+            let (name, plot_style) = if let Some(name) = f.name.strip_prefix(INDICATOR_PREFIX) {
+                (name, PlotStyle::Lower)
+            } else {
+                (f.name.strip_prefix(OVERLAY_PREFIX)?, PlotStyle::Upper)
+            };
+            Some(ScriptIndicatorDefinition {
+                name: name.to_string(),
+                fn_name: f.name.to_string(),
+                plot_style,
+            })
+        })
+        .collect()
+}
+
+/// Computes a script indicator, candles are passed as an array of maps with `time` (close time
+/// timestamp), `open`, `high`, `low`, `close` and `volume`
+pub fn eval_script_indicator(
+    engine: &Engine,
+    ast: &AST,
+    definition: &ScriptIndicatorDefinition,
+    candles: &[Candle],
+) -> eyre::Result<SerieIndicator> {
+    let values: Array = engine
+        .call_fn(
+            &mut Scope::new(),
+            ast,
+            &definition.fn_name,
+            (candles_to_array(candles),),
+        )
+        .map_err(|e| eyre!("Script indicator {} failed: {}", definition.name, e))?;
+
+    // Values are aligned with the last candles, first ones may have no value yet
+    let skip = candles.len().checked_sub(values.len()).ok_or_else(|| {
+        eyre!(
+            "Script indicator {} returned {} values for {} candles!",
+            definition.name,
+            values.len(),
+            candles.len()
+        )
+    })?;
+
+    let series = candles[skip..]
+        .iter()
+        .zip(values.iter())
+        .map(|(candle, value)| {
+            let value = value
+                .as_float()
+                .or_else(|_| value.as_int().map(|i| i as f64))
+                .map_err(|t| eyre!("Script indicator {} returned {}!", definition.name, t))?;
+            Ok(Serie::new(candle.close_time, value))
+        })
+        .collect::<eyre::Result<Vec<_>>>()?;
+
+    Ok(SerieIndicator::from(&definition.name, series))
+}
+
+fn candles_to_array(candles: &[Candle]) -> Array {
+    candles
+        .iter()
+        .map(|c| {
+            let mut map = Map::new();
+            map.insert("time".into(), Dynamic::from(c.close_time.timestamp()));
+            map.insert("open".into(), Dynamic::from(c.open.to_f64().unwrap()));
+            map.insert("high".into(), Dynamic::from(c.high.to_f64().unwrap()));
+            map.insert("low".into(), Dynamic::from(c.low.to_f64().unwrap()));
+            map.insert("close".into(), Dynamic::from(c.close.to_f64().unwrap()));
+            map.insert("volume".into(), Dynamic::from(c.volume.to_f64().unwrap()));
+            Dynamic::from(map)
+        })
+        .collect()
+}
+
+/// Script indicator serie to plot in a lower panel
+pub struct ScriptIndicatorTec {
+    name: String,
+    indicators: HashMap<String, SerieIndicator>,
+}
+
+impl ScriptIndicatorTec {
+    pub fn new(serie: SerieIndicator) -> Self {
+        let name = serie.name.clone();
+        let mut indicators = HashMap::new();
+        indicators.insert(name.clone(), serie);
+        Self { name, indicators }
+    }
+}
+
+impl TecSerieIndicators for ScriptIndicatorTec {
+    fn serie_indicators(&self) -> &HashMap<String, SerieIndicator> {
+        &self.indicators
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::services::technicals::ind_provider::IndicatorProvider;
+    use crate::services::technicals::ind_stream::IndicatorStreams;
+    use crate::services::technicals::indicator::Indicator;
+    use crate::utils::candles_utils::tests::candle_at;
+    use chrono::Utc;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn script_indicator_test() {
+        let candles = (0..5)
+            .map(|i| {
+                candle_at(
+                    i,
+                    Decimal::from(10 + i),
+                    Decimal::from(12 + i),
+                    Decimal::from(9 + i),
+                    Decimal::from(11 + i),
+                    Decimal::from(1),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let engine = Engine::new();
+        let ast = engine
+            .compile(
+                r#"
+                fn run() {}
+                fn indicator_range(candles) {
+                    candles.map(|c| c.high - c.low)
+                }
+                fn overlay_mid(candles) {
+                    let result = [];
+                    for i in 1..candles.len() {
+                        result.push((candles[i].close + candles[i - 1].close) / 2.0);
+                    }
+                    result
+                }
+                "#,
+            )
+            .unwrap();
+
+        let definitions = script_indicators(&ast);
+        assert_eq!(definitions.len(), 2);
+        let range = definitions.iter().find(|d| d.name == "range").unwrap();
+        assert_eq!(range.plot_style, PlotStyle::Lower);
+        let mid = definitions.iter().find(|d| d.name == "mid").unwrap();
+        assert_eq!(mid.plot_style, PlotStyle::Upper);
+
+        let range_serie = eval_script_indicator(&engine, &ast, range, &candles).unwrap();
+        assert_eq!(range_serie.series.len(), 5);
+        assert_eq!(range_serie.value().unwrap(), 3.);
+
+        // Shorter series are aligned with the last candles
+        let mid_serie = eval_script_indicator(&engine, &ast, mid, &candles).unwrap();
+        assert_eq!(mid_serie.series.len(), 4);
+        assert_eq!(mid_serie.series[0].date_time, candles[1].close_time);
+        assert_eq!(mid_serie.value().unwrap(), 14.5);
+    }
+
+    #[test]
+    fn script_indicator_look_ahead_test() {
+        let mut streams = IndicatorStreams::new();
+        streams.push_candles(
+            (0..5)
+                .map(|i| {
+                    let close = Decimal::from(10 + i);
+                    candle_at(i, close, close, close, close, Decimal::from(1))
+                })
+                .collect(),
+        );
+        let now = streams.closed_candles(Utc::now(), usize::MAX)[2].close_time;
+
+        let engine = Engine::new();
+        let ast = engine
+            .compile(
+                r#"
+                fn indicator_last(candles) {
+                    let last = candles[candles.len() - 1].close;
+                    candles.map(|c| last)
+                }
+                "#,
+            )
+            .unwrap();
+        let definition = &script_indicators(&ast)[0];
+
+        // Only the candles closed until now are passed, as the trade context does
+        let candles = streams.closed_candles(now, usize::MAX);
+        let serie = eval_script_indicator(&engine, &ast, definition, candles).unwrap();
+        let mut provider = IndicatorProvider::new();
+        provider.set_script_serie(15, candles.last().map(|c| c.close_time), serie);
+
+        let values = provider.script_history(15, "last", now, 3).unwrap();
+        assert_eq!(values, vec![12., 12., 12.]);
+    }
+}
@@ -0,0 +1,482 @@
+use super::position_register::PositionRegister;
+use super::script_back_test::{
+    back_test, funding_rates, lower_candles, path_to_description, selection_candles,
+    BackTestSettings,
+};
+use super::singleton_engine::EngineSingleton;
+use crate::app::Application;
+use crate::model::position::Position;
+use crate::services::trading::flow_register::FlowRegister;
+use crate::services::trading::performance::{trade_pnls, PerformanceReport};
+use colored::Colorize;
+use eyre::bail;
+use ifmt::iformat;
+use log::{info, warn};
+use pad::{Alignment, PadStr};
+use rand::Rng;
+use rayon::prelude::*;
+use rust_decimal_macros::dec;
+use sqlx::PgPool;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+    str::FromStr,
+    sync::{Arc, Mutex, RwLock},
+    time::Instant,
+};
+
+/// Results table of the last optimization
+pub const OPTIMIZE_FILE: &str = "out/optimize.csv";
+/// Results logged at the end of the optimization
+const TOP_RESULTS: usize = 10;
+
+/// Values of a script parameter from its start to its end, both included
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParamRange {
+    pub name: String,
+    pub start: f64,
+    pub end: f64,
+    pub step: f64,
+}
+
+/// Parses `<name>=<start>:<end>` or `<name>=<start>:<end>:<step>`, the step is 1 by default
+impl FromStr for ParamRange {
+    type Err = eyre::Error;
+
+    fn from_str(value: &str) -> eyre::Result<Self> {
+        let (name, range) = match value.split_once('=') {
+            Some((name, range)) if !name.is_empty() => (name, range),
+            _ => bail!("Parameter range {} without name", value),
+        };
+        let numbers = range
+            .split(':')
+            .map(|n| n.parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let (start, end, step) = match numbers[..] {
+            [start, end] => (start, end, 1.),
+            [start, end, step] => (start, end, step),
+            _ => bail!("Unknown parameter range {}", value),
+        };
+        if step <= 0. || end < start {
+            bail!("Parameter range {} can't reach its end", value);
+        }
+        Ok(Self {
+            name: name.to_string(),
+            start,
+            end,
+            step,
+        })
+    }
+}
+
+impl ParamRange {
+    pub fn values(&self) -> Vec<f64> {
+        // Tolerance for steps without an exact float
+        let steps = ((self.end - self.start) / self.step + 1e-9).floor() as usize;
+        (0..=steps)
+            .map(|i| self.start + i as f64 * self.step)
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Search {
+    /// Every combination of the parameter values
+    Grid,
+    /// Distinct combinations drawn at random from the parameter values, the whole grid when the
+    /// runs reach its size
+    Random(usize),
+}
+
+/// Parses `grid` or `random:<runs>`
+impl FromStr for Search {
+    type Err = eyre::Error;
+
+    fn from_str(value: &str) -> eyre::Result<Self> {
+        match value.split_once(':') {
+            None if value == "grid" => Ok(Search::Grid),
+            Some(("random", runs)) => match runs.parse::<usize>()? {
+                runs if runs > 0 => Ok(Search::Random(runs)),
+                _ => bail!("Search {} needs some runs", value),
+            },
+            _ => bail!("Unknown search {}", value),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Objective {
+    /// Total return after costs
+    NetReturn,
+    Sharpe,
+    /// Total return of the runs with a max drawdown under the fraction
+    ReturnMaxDrawdown(f64),
+}
+
+/// Parses `net_return`, `sharpe` or `return_max_drawdown:<fraction>`
+impl FromStr for Objective {
+    type Err = eyre::Error;
+
+    fn from_str(value: &str) -> eyre::Result<Self> {
+        match value.split_once(':') {
+            None if value == "net_return" => Ok(Objective::NetReturn),
+            None if value == "sharpe" => Ok(Objective::Sharpe),
+            Some(("return_max_drawdown", max)) => match max.parse::<f64>()? {
+                max if max > 0. => Ok(Objective::ReturnMaxDrawdown(max)),
+                _ => bail!("Objective {} needs a positive drawdown", value),
+            },
+            _ => bail!("Unknown objective {}", value),
+        }
+    }
+}
+
+impl Objective {
+    /// Score to maximize, None when the run breaks the constraint
+    pub fn score(&self, report: &PerformanceReport) -> Option<f64> {
+        match self {
+            Objective::NetReturn => Some(report.total_return),
+            Objective::Sharpe => Some(report.sharpe),
+            Objective::ReturnMaxDrawdown(max) if report.max_drawdown <= *max => {
+                Some(report.total_return)
+            }
+            Objective::ReturnMaxDrawdown(_) => None,
+        }
+    }
+}
+
+/// Back test of a parameter set
+#[derive(Clone, Debug)]
+pub struct OptimizeResult {
+    pub params: HashMap<String, f64>,
+    pub report: PerformanceReport,
+    pub score_opt: Option<f64>,
+}
+
+/// Parameter sets of the search
+pub fn param_sets(ranges: &[ParamRange], search: Search) -> Vec<HashMap<String, f64>> {
+    match search {
+        Search::Grid => ranges.iter().fold(vec![HashMap::new()], |sets, range| {
+            sets.iter()
+                .flat_map(|set| {
+                    range.values().into_iter().map(move |value| {
+                        let mut set = set.clone();
+                        set.insert(range.name.clone(), value);
+                        set
+                    })
+                })
+                .collect()
+        }),
+        Search::Random(runs) => {
+            let values = ranges.iter().map(|r| r.values()).collect::<Vec<_>>();
+            let grid_size = values
+                .iter()
+                .fold(1_usize, |size, values| size.saturating_mul(values.len()));
+            if runs >= grid_size {
+                return param_sets(ranges, Search::Grid);
+            }
+
+            // Indexes of the values of each combination, drawn again when repeated
+            let mut rng = rand::thread_rng();
+            let mut combinations = HashSet::new();
+            while combinations.len() < runs {
+                combinations.insert(
+                    values
+                        .iter()
+                        .map(|values| rng.gen_range(0..values.len()))
+                        .collect::<Vec<_>>(),
+                );
+            }
+            combinations
+                .into_iter()
+                .map(|indexes| {
+                    ranges
+                        .iter()
+                        .zip(values.iter())
+                        .zip(indexes)
+                        .map(|((range, values), i)| (range.name.clone(), values[i]))
+                        .collect()
+                })
+                .collect()
+        }
+    }
+}
+
+/// Sorts the results from the best score, the runs breaking the constraint last
+pub fn rank(results: &mut [OptimizeResult]) {
+    results.sort_by(|a, b| {
+        b.score_opt
+            .partial_cmp(&a.score_opt)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+/// Back tests the script over the parameter sets in parallel, without saving the runs, and ranks
+/// them by the objective
+pub fn optimize_script<P: AsRef<Path>>(
+    pool: Arc<RwLock<PgPool>>,
+    app: &mut Application,
+    script_file: P,
+    settings: BackTestSettings,
+    ranges: &[ParamRange],
+    search: Search,
+    objective: Objective,
+) -> eyre::Result<Vec<OptimizeResult>> {
+    let start = Instant::now();
+    let param_sets = param_sets(ranges, search);
+    info!("Optimizing {} parameter sets...", param_sets.len());
+
+    let (prices, _) = selection_candles(app)?;
+    let lower_candles = lower_candles(app, settings.fill_source)?;
+    let funding_rates = funding_rates(pool, app, settings.margin_opt)?;
+
+    let app = &*app;
+    let script_file = script_file.as_ref();
+    let position_description = path_to_description(script_file);
+    // Parameters the script read in any run
+    let read_params = Mutex::new(HashSet::new());
+
+    let mut results = param_sets
+        .into_par_iter()
+        .map(|params| {
+            // Script singletons are thread local, each run installs its engine on its worker
+            EngineSingleton::install_with_params(script_file, params.clone())?;
+
+            let position = Position::from_fiat(&position_description, dec!(1000));
+            let position_register = PositionRegister::new(
+                position,
+                FlowRegister::in_memory(),
+                settings.cost_model,
+                settings.margin_opt,
+                settings.lot_mode,
+            );
+            let trader = back_test(
+                app,
+                &prices,
+                &lower_candles,
+                position_register,
+                funding_rates.clone(),
+                settings,
+            )?;
+            read_params
+                .lock()
+                .unwrap()
+                .extend(EngineSingleton::current().read_params());
+
+            let flows = trader.position_register().flow_register.flows();
+            let report = PerformanceReport::new(trader.equity(), &trade_pnls(flows), &prices);
+            let score_opt = objective.score(&report);
+            Ok(OptimizeResult {
+                params,
+                report,
+                score_opt,
+            })
+        })
+        .collect::<eyre::Result<Vec<_>>>()?;
+    rank(&mut results);
+
+    let read_params = read_params.into_inner().unwrap();
+    for range in ranges.iter().filter(|r| !read_params.contains(&r.name)) {
+        warn!(
+            "Parameter {} never read by the script param(...), check its name!",
+            range.name
+        );
+    }
+
+    save_results(OPTIMIZE_FILE, ranges, &results)?;
+    info!(
+        "{}",
+        iformat!(
+            "Finished optimization, runs: {results.len()} elapsed: {start.elapsed():?}\n\
+            {results_table(ranges, &results[..results.len().min(TOP_RESULTS)])}"
+        )
+        .bright_cyan()
+    );
+    info!("Saved optimization results {}", OPTIMIZE_FILE);
+
+    Ok(results)
+}
+
+/// Ranked results as CSV, a run breaking the constraint has no score
+pub fn save_results<P: AsRef<Path>>(
+    path: P,
+    ranges: &[ParamRange],
+    results: &[OptimizeResult],
+) -> eyre::Result<()> {
+    let mut lines = vec![ranges
+        .iter()
+        .map(|r| r.name.clone())
+        .chain(
+            [
+                "score",
+                "total_return",
+                "annualized_return",
+                "max_drawdown",
+                "sharpe",
+                "sortino",
+                "win_rate",
+                "profit_factor",
+                "trades",
+            ]
+            .iter()
+            .map(|c| c.to_string()),
+        )
+        .collect::<Vec<_>>()
+        .join(",")];
+    for result in results.iter() {
+        let report = &result.report;
+        let params = ranges.iter().map(|r| result.params[&r.name].to_string());
+        let metrics = vec![
+            result.score_opt.map(|s| s.to_string()).unwrap_or_default(),
+            report.total_return.to_string(),
+            report.annualized_return.to_string(),
+            report.max_drawdown.to_string(),
+            report.sharpe.to_string(),
+            report.sortino.to_string(),
+            report.win_rate.to_string(),
+            report.profit_factor.to_string(),
+            report.trades.to_string(),
+        ];
+        lines.push(params.chain(metrics).collect::<Vec<_>>().join(","));
+    }
+    fs::write(path, lines.join("\n") + "\n")?;
+    Ok(())
+}
+
+fn results_table(ranges: &[ParamRange], results: &[OptimizeResult]) -> String {
+    let cell = |value: String| value.pad_to_width_with_alignment(12, Alignment::Right);
+    let header = ranges
+        .iter()
+        .map(|r| r.name.clone())
+        .chain(
+            ["score", "return", "drawdown", "sharpe", "trades"]
+                .iter()
+                .map(|c| c.to_string()),
+        )
+        .map(cell)
+        .collect::<String>();
+    let rows = results.iter().map(|result| {
+        let report = &result.report;
+        ranges
+            .iter()
+            .map(|r| result.params[&r.name].to_string())
+            .chain(vec![
+                result
+                    .score_opt
+                    .map(|s| format!("{:.4}", s))
+                    .unwrap_or_else(|| "-".to_string()),
+                format!("{:.2}%", report.total_return * 100.),
+                format!("{:.2}%", report.max_drawdown * 100.),
+                format!("{:.2}", report.sharpe),
+                report.trades.to_string(),
+            ])
+            .map(cell)
+            .collect::<String>()
+    });
+    std::iter::once(header)
+        .chain(rows)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::services::script::script_fns::{param_float, param_int};
+    use std::thread;
+
+    #[test]
+    fn param_range_test() {
+        let range = ParamRange::from_str("rsi_buy=20:30:2.5").unwrap();
+        assert_eq!(range.name, "rsi_buy");
+        assert_eq!(range.values(), vec![20., 22.5, 25., 27.5, 30.]);
+        assert_eq!(
+            ParamRange::from_str("fast=0.1:0.3:0.1")
+                .unwrap()
+                .values()
+                .len(),
+            3
+        );
+        assert_eq!(ParamRange::from_str("fast=8:10").unwrap().values().len(), 3);
+        assert!(ParamRange::from_str("=1:2").is_err());
+        assert!(ParamRange::from_str("fast=10:8").is_err());
+        assert!(ParamRange::from_str("fast=1:2:0").is_err());
+
+        assert_eq!(Search::from_str("grid").unwrap(), Search::Grid);
+        assert_eq!(Search::from_str("random:20").unwrap(), Search::Random(20));
+        assert!(Search::from_str("random:0").is_err());
+        assert_eq!(
+            Objective::from_str("return_max_drawdown:0.2").unwrap(),
+            Objective::ReturnMaxDrawdown(0.2)
+        );
+        assert!(Objective::from_str("profit").is_err());
+    }
+
+    #[test]
+    fn param_sets_test() {
+        let ranges = vec![
+            ParamRange::from_str("fast=10:12").unwrap(),
+            ParamRange::from_str("slow=20:40:10").unwrap(),
+        ];
+        let grid = param_sets(&ranges, Search::Grid);
+        assert_eq!(grid.len(), 9);
+        assert!(grid.iter().any(|s| s["fast"] == 12. && s["slow"] == 30.));
+
+        let random = param_sets(&ranges, Search::Random(5));
+        assert_eq!(random.len(), 5);
+        assert!(random.iter().all(|s| grid.contains(s)));
+        assert!(random
+            .iter()
+            .enumerate()
+            .all(|(i, s)| !random[i + 1..].contains(s)));
+        // Capped to the distinct combinations
+        assert_eq!(param_sets(&ranges, Search::Random(20)).len(), 9);
+    }
+
+    #[test]
+    fn rank_test() {
+        let report = |total_return: f64, max_drawdown: f64| PerformanceReport {
+            total_return,
+            max_drawdown,
+            ..Default::default()
+        };
+        let objective = Objective::ReturnMaxDrawdown(0.2);
+        let mut results = [report(0.1, 0.1), report(0.5, 0.3), report(0.2, 0.15)]
+            .iter()
+            .map(|report| OptimizeResult {
+                params: HashMap::new(),
+                report: report.clone(),
+                score_opt: objective.score(report),
+            })
+            .collect::<Vec<_>>();
+        rank(&mut results);
+        let scores = results.iter().map(|r| r.score_opt).collect::<Vec<_>>();
+        assert_eq!(scores, vec![Some(0.2), Some(0.1), None]);
+    }
+
+    #[test]
+    fn parallel_params_test() {
+        // Each thread sees the parameters of its own engine
+        let handles = (1..=4)
+            .map(|i| {
+                thread::spawn(move || {
+                    EngineSingleton {
+                        engine_scope: None,
+                        params: vec![("period".to_string(), i as f64)].into_iter().collect(),
+                        ..Default::default()
+                    }
+                    .make_current();
+                    thread::yield_now();
+                    param_int("period", 0)
+                })
+            })
+            .collect::<Vec<_>>();
+        let periods = handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(periods, vec![1, 2, 3, 4]);
+        assert_eq!(param_float("period", 1.5), 1.5);
+        assert!(EngineSingleton::current().read_params().contains("period"));
+    }
+}
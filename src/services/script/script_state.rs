@@ -1,5 +1,7 @@
 use crate::model::operation::Operation;
+use crate::model::order::OrderAction;
 use crate::services::trading::trend::trend_direction::TrendDirection;
+use chrono::{DateTime, Utc};
 
 #[derive(Clone)]
 pub struct ScriptState {
@@ -7,4 +9,9 @@ pub struct ScriptState {
     pub operation_opt: Option<Operation>,
     pub changed_trend: Option<TrendDirection>,
     pub trend_direction: TrendDirection,
+    pub order_actions: Vec<OrderAction>,
+    /// Id of the next order placed by the script
+    pub next_order_id: i32,
+    /// Time of the run, orders are placed at it
+    pub now: DateTime<Utc>,
 }
@@ -9,6 +9,8 @@ use crate::services::trading::trade_context_provider::TradeContextProvider;
 use crate::services::trading::trade_operation::TradeOperation;
 use crate::services::trading::trend::trend_direction::TrendDirection;
 use crate::services::trading::trend::trend_provider::TrendProvider;
+use eyre::eyre;
+use rhai::{Dynamic, Engine, Scope, AST};
 
 pub struct ScriptTrendProvider {}
 
@@ -53,14 +55,16 @@ fn call_back_trend_provider(
         operation_opt: None,
         changed_trend,
         trend_direction: TrendDirection::None,
+        order_actions: Vec::new(),
+        next_order_id: position_register.order_book.next_id(),
+        now,
     });
 
     // Get engine to run script
     let engine_arc = EngineSingleton::current();
     let (engine, scope, ast) = &engine_arc.engine_scope.as_ref().unwrap();
 
-    // Run script
-    let _: () = engine.call_fn(&mut scope.clone(), &ast, "run", ()).unwrap();
+    run_script(engine, scope, ast)?;
 
     let singleton = ScriptStateSingleton::current();
     let script_state = singleton.script_state_opt.as_ref().unwrap();
@@ -73,5 +77,56 @@ fn call_back_trend_provider(
     Ok(TrendState {
         trend_direction,
         trade_operation_opt,
+        order_actions: script_state.order_actions.clone(),
     })
 }
+
+/// Runs the script, a script error (e.g. a candle type typo) fails the run instead of panicking,
+/// the value of the last statement (e.g. a placed order id) is ignored
+fn run_script(engine: &Engine, scope: &Scope, ast: &AST) -> eyre::Result<()> {
+    engine
+        .call_fn::<Dynamic>(&mut scope.clone(), ast, "run", ())
+        .map(|_| ())
+        .map_err(|e| eyre!("Script run failed: {}", e))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::services::script::script_fns::{buy_limit_tif, set_candle_type};
+    use chrono::Utc;
+
+    #[test]
+    fn run_script_error_test() {
+        let mut engine = Engine::new();
+        engine.register_fn("set_candle_type", set_candle_type);
+        let ast = engine
+            .compile(r#"fn run() { set_candle_type(15, "rengo:10"); }"#)
+            .unwrap();
+        assert!(run_script(&engine, &Scope::new(), &ast).is_err());
+    }
+
+    #[test]
+    fn run_script_order_error_test() {
+        ScriptStateSingleton::set_current(ScriptState {
+            log: None,
+            operation_opt: None,
+            changed_trend: None,
+            trend_direction: TrendDirection::None,
+            order_actions: Vec::new(),
+            next_order_id: 1,
+            now: Utc::now(),
+        });
+        let mut engine = Engine::new();
+        engine.register_fn("buy_limit", buy_limit_tif);
+        let ast = engine
+            .compile(r#"fn run() { buy_limit(1.0, 100.0, "day"); }"#)
+            .unwrap();
+        assert!(run_script(&engine, &Scope::new(), &ast).is_err());
+
+        let ast = engine
+            .compile(r#"fn run() { buy_limit(1.0, 100.0, "gtt:30"); }"#)
+            .unwrap();
+        run_script(&engine, &Scope::new(), &ast).unwrap();
+    }
+}
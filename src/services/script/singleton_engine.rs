@@ -1,15 +1,26 @@
 use super::script_fns::*;
+use super::script_indicator::{
+    eval_script_indicator, script_indicators, ScriptIndicatorDefinition,
+};
+use crate::model::candle::Candle;
+use crate::services::technicals::serie_indicator::SerieIndicator;
+use eyre::eyre;
 use rhai::{Engine, Scope, AST};
 use std::{
+    collections::{HashMap, HashSet},
     fs,
     path::Path,
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex, RwLock},
 };
 
 /// Singleton for engine script
 #[derive(Default)]
 pub struct EngineSingleton {
     pub engine_scope: Option<(Engine, Scope<'static>, AST)>,
+    /// Values overriding the script `param` defaults
+    pub params: HashMap<String, f64>,
+    /// Names the script read with `param`
+    pub read_params: Mutex<HashSet<String>>,
 }
 
 impl EngineSingleton {
@@ -24,24 +35,111 @@ impl EngineSingleton {
     pub fn set_current(engine_scope: (Engine, Scope<'static>, AST)) {
         Self {
             engine_scope: Some(engine_scope),
+            ..Default::default()
         }
         .make_current();
     }
 
+    /// Value overriding the script parameter, the name is recorded as read
+    pub fn param(&self, name: &str) -> Option<f64> {
+        self.read_params.lock().unwrap().insert(name.to_string());
+        self.params.get(name).copied()
+    }
+
+    /// Names of the parameters the script read so far
+    pub fn read_params(&self) -> HashSet<String> {
+        self.read_params.lock().unwrap().clone()
+    }
+
+    /// Indicators declared by the installed script
+    pub fn script_indicators(&self) -> Vec<ScriptIndicatorDefinition> {
+        self.engine_scope
+            .as_ref()
+            .map(|(_, _, ast)| script_indicators(ast))
+            .unwrap_or_default()
+    }
+
+    pub fn eval_indicator(&self, name: &str, candles: &[Candle]) -> eyre::Result<SerieIndicator> {
+        let (engine, _, ast) = self
+            .engine_scope
+            .as_ref()
+            .ok_or_else(|| eyre!("Script engine not installed!"))?;
+        let definition = script_indicators(ast)
+            .into_iter()
+            .find(|d| d.name == name)
+            .ok_or_else(|| eyre!("Not found script indicator {}!", name))?;
+        eval_script_indicator(engine, ast, &definition, candles)
+    }
+
     /// Create engine script and register functions
     pub fn install<P: AsRef<Path>>(script_file: P) -> eyre::Result<()> {
+        Self::install_with_params(script_file, HashMap::new())
+    }
+
+    /// Install the script overriding its parameters
+    pub fn install_with_params<P: AsRef<Path>>(
+        script_file: P,
+        params: HashMap<String, f64>,
+    ) -> eyre::Result<()> {
         // Create engine script and register functions
         let mut engine = Engine::new();
         // Current context/indicators
         engine.register_fn("price", price);
-        engine.register_fn("rsi", rsi);
-        engine.register_fn("ema", ema);
-        engine.register_fn("sma", sma);
-        engine.register_fn("macd", macd);
-        engine.register_fn("min", min);
-        engine.register_fn("max", max);
-        engine.register_fn("macd_signal", macd_signal);
-        engine.register_fn("macd_divergence", macd_divergence);
+        engine.register_fn("open", open);
+        engine.register_fn("high", high);
+        engine.register_fn("low", low);
+        engine.register_fn("close", close);
+        engine.register_fn("volume", volume);
+        engine.register_fn("candle_time", candle_time);
+        engine.register_fn("opens", opens);
+        engine.register_fn("highs", highs);
+        engine.register_fn("lows", lows);
+        engine.register_fn("closes", closes);
+        engine.register_fn("volumes", volumes);
+        engine.register_fn("candle_times", candle_times);
+        engine.register_fn("tops", tops);
+        engine.register_fn("bottoms", bottoms);
+        engine.register_fn("zigzag", zigzag);
+        engine.register_fn("higher_high", higher_high);
+        engine.register_fn("lower_high", lower_high);
+        engine.register_fn("higher_low", higher_low);
+        engine.register_fn("lower_low", lower_low);
+        engine.register_fn("chart_patterns", last_patterns);
+        engine.register_fn("chart_patterns", last_patterns_tolerance);
+        engine.register_fn("chart_pattern", is_pattern);
+        engine.register_fn("nearest_support", nearest_support_default);
+        engine.register_fn("nearest_support", nearest_support_neighbors);
+        engine.register_fn("nearest_resistance", nearest_resistance_default);
+        engine.register_fn("nearest_resistance", nearest_resistance_neighbors);
+        engine.register_fn("poc", poc);
+        engine.register_fn("poc", poc_bins);
+        engine.register_fn("value_area_high", value_area_high);
+        engine.register_fn("value_area_high", value_area_high_bins);
+        engine.register_fn("value_area_low", value_area_low);
+        engine.register_fn("value_area_low", value_area_low_bins);
+        register_divergences(&mut engine);
+        engine.register_fn("symbol_close", symbol_close);
+        engine.register_fn("symbol_closes", symbol_closes);
+        engine.register_fn("ratio", ratio);
+        engine.register_fn("correlation", correlation);
+        engine.register_fn("beta", beta);
+        engine.register_fn("spread", spread);
+        engine.register_fn("price_above_cloud", price_above_cloud);
+        engine.register_fn("price_below_cloud", price_below_cloud);
+        engine.register_fn("price_in_cloud", price_in_cloud);
+        engine.register_fn("tk_cross_up", tk_cross_up);
+        engine.register_fn("tk_cross_down", tk_cross_down);
+        register_indicators(&mut engine);
+        engine.register_fn("indicator", indicator);
+        engine.register_fn("set_candle_type", set_candle_type);
+        engine.register_fn("indicator_at", indicator_at);
+        engine.register_fn("indicator_series", indicator_series);
+        engine.register_fn("crosses_above", crosses_above);
+        engine.register_fn("crosses_above", crosses_above_level);
+        engine.register_fn("crosses_below", crosses_below);
+        engine.register_fn("crosses_below", crosses_below_level);
+        engine.register_fn("rising", rising);
+        engine.register_fn("falling", falling);
         // Conversion functions
         engine.register_fn("fiat_to_asset", fiat_to_asset);
         engine.register_fn("asset_to_fiat", asset_to_fiat);
@@ -51,14 +149,42 @@ impl EngineSingleton {
         engine.register_fn("is_bought", is_bought);
         engine.register_fn("is_sold", is_sold);
         engine.register_fn("gain_perc", gain_perc);
+        engine.register_fn("position_side", position_side);
+        engine.register_fn("liquidation_price", liquidation_price);
+        engine.register_fn("funding_rate", funding_rate);
+        engine.register_fn("entry_price", entry_price);
+        engine.register_fn("unrealized_pnl", unrealized_pnl);
+        engine.register_fn("bars_in_trade", bars_in_trade);
         // Operations
         engine.register_fn("sell", sell);
         engine.register_fn("buy", buy);
+        engine.register_fn("short", short);
+        engine.register_fn("cover", cover);
+        // Pending orders
+        engine.register_fn("buy_limit", buy_limit);
+        engine.register_fn("buy_limit", buy_limit_tif);
+        engine.register_fn("sell_limit", sell_limit);
+        engine.register_fn("sell_limit", sell_limit_tif);
+        engine.register_fn("buy_stop", buy_stop);
+        engine.register_fn("buy_stop", buy_stop_tif);
+        engine.register_fn("sell_stop", sell_stop);
+        engine.register_fn("sell_stop", sell_stop_tif);
+        engine.register_fn("buy_stop_limit", buy_stop_limit);
+        engine.register_fn("buy_stop_limit", buy_stop_limit_tif);
+        engine.register_fn("sell_stop_limit", sell_stop_limit);
+        engine.register_fn("sell_stop_limit", sell_stop_limit_tif);
+        engine.register_fn("replace_order", replace_order);
+        engine.register_fn("cancel", cancel);
+        engine.register_fn("cancel_all", cancel_all);
+        engine.register_fn("open_orders", open_orders);
         // Trend
         engine.register_fn("change_trend_sell", change_trend_sell);
         engine.register_fn("change_trend_buy", change_trend_buy);
         engine.register_fn("set_change_trend_sell", set_change_trend_sell);
         engine.register_fn("set_change_trend_buy", set_change_trend_buy);
+        // Parameters
+        engine.register_fn("param", param_int);
+        engine.register_fn("param", param_float);
         // Debugging
         engine.register_fn("log", log);
 
@@ -66,7 +192,12 @@ impl EngineSingleton {
         let script_content = fs::read_to_string(script_file)?;
         let ast = engine.compile(&script_content)?;
         // Define script engine singleton
-        EngineSingleton::set_current((engine, Scope::new(), ast));
+        Self {
+            engine_scope: Some((engine, Scope::new(), ast)),
+            params,
+            ..Default::default()
+        }
+        .make_current();
 
         Ok(())
     }
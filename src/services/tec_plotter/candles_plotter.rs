@@ -1,11 +1,14 @@
 use crate::services::tec_plotter::plotter_indicator_context::PlotterIndicatorContext;
 use crate::{config::selection::Selection, model::candle::Candle};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use plotters::{coord::types::RangedCoordf32, prelude::*};
 use plotters_bitmap::{bitmap_pixel::RGBPixel, BitMapBackend};
 use rust_decimal::prelude::ToPrimitive;
 use rust_decimal_macros::dec;
 
+/// Candle body width in pixels when candles are far apart
+const MAX_CANDLE_WIDTH: u32 = 8;
+
 pub struct CandlePlotter<'a> {
     candles: &'a [Candle],
 }
@@ -36,6 +39,19 @@ impl<'a> PlotterIndicatorContext for CandlePlotter<'a> {
         // Into::<ShapeStyle>::into(&RGBColor(16, 196, 64)).filled(),
         // Into::<ShapeStyle>::into(&RGBColor(164, 16, 64)).filled(),
 
+        // Candles as wide as the closest ones apart, bricks are irregularly spaced
+        let width = self
+            .candles
+            .windows(2)
+            .map(|w| {
+                let previous = chart_context.backend_coord(&(w[0].close_time, 0.));
+                let current = chart_context.backend_coord(&(w[1].close_time, 0.));
+                (current.0 - previous.0) as u32
+            })
+            .min()
+            .map(|distance| (distance * 2 / 3).clamp(1, MAX_CANDLE_WIDTH))
+            .unwrap_or(MAX_CANDLE_WIDTH);
+
         let candle_series = self.candles.iter().map(|x| {
             CandleStick::new(
                 x.close_time,
@@ -45,7 +61,7 @@ impl<'a> PlotterIndicatorContext for CandlePlotter<'a> {
                 x.close.to_f32().unwrap(),
                 &green,
                 &red,
-                4,
+                width,
             )
         });
         chart_context.draw_series(candle_series)?;
@@ -58,3 +74,66 @@ impl<'a> PlotterIndicatorContext for CandlePlotter<'a> {
         (min.to_f64().unwrap(), max.to_f64().unwrap())
     }
 }
+
+/// Bricks closed in the same candle of the timeframe are one second apart, spreads them over
+/// the candle duration so they don't overlap when plotted
+pub fn spread_bricks(bricks: &[Candle], minutes: i32) -> Vec<Candle> {
+    let duration = Duration::minutes(minutes as i64);
+    let seconds = duration.num_seconds();
+    // Close time of the timeframe candle the brick closed in
+    let source_close_time = |brick: &Candle| {
+        let offset = (brick.close_time.timestamp() + 1).rem_euclid(seconds);
+        brick.close_time + Duration::seconds((seconds - offset) % seconds)
+    };
+
+    let mut result = Vec::with_capacity(bricks.len());
+    let mut start = 0;
+    while start < bricks.len() {
+        let close_time = source_close_time(&bricks[start]);
+        let len = bricks[start..]
+            .iter()
+            .take_while(|b| source_close_time(b) == close_time)
+            .count();
+        for (i, brick) in bricks[start..start + len].iter().enumerate() {
+            result.push(Candle {
+                close_time: close_time - duration * (len - 1 - i) as i32 / len as i32,
+                ..*brick
+            });
+        }
+        start += len;
+    }
+    result
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::utils::candles_utils::tests::candle_at;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn spread_bricks_test() {
+        let source = candle_at(1, dec!(1), dec!(1), dec!(1), dec!(1), Decimal::from(1));
+        let bricks = (0..3)
+            .map(|i| Candle {
+                close_time: source.close_time - Duration::seconds(2 - i),
+                ..source
+            })
+            .collect::<Vec<_>>();
+        let next = candle_at(2, dec!(1), dec!(1), dec!(1), dec!(1), Decimal::from(1));
+
+        let mut candles = bricks;
+        candles.push(next);
+        let spread = spread_bricks(&candles, 15);
+        let times = spread.iter().map(|c| c.close_time).collect::<Vec<_>>();
+        assert_eq!(
+            times,
+            vec![
+                source.close_time - Duration::minutes(10),
+                source.close_time - Duration::minutes(5),
+                source.close_time,
+                next.close_time,
+            ]
+        );
+    }
+}
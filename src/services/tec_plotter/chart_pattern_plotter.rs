@@ -0,0 +1,87 @@
+use crate::config::selection::Selection;
+use crate::services::tec_plotter::plotter_indicator_context::PlotterIndicatorContext;
+use crate::services::trading::chart_pattern::{ChartPattern, TrendLine};
+use chrono::{DateTime, Utc};
+use plotters::{coord::types::RangedCoordf32, prelude::*};
+use plotters_bitmap::bitmap_pixel::RGBPixel;
+use plotters_bitmap::BitMapBackend;
+
+/// Trendlines and breakout level of each chart pattern, named at its start
+pub struct ChartPatternPlotter<'a> {
+    patterns: &'a [ChartPattern],
+}
+
+impl<'a> ChartPatternPlotter<'a> {
+    pub fn new(patterns: &'a [ChartPattern]) -> Self {
+        Self { patterns }
+    }
+}
+
+fn line_points(line: &TrendLine) -> Vec<(DateTime<Utc>, f32)> {
+    vec![
+        (line.start_time, line.start_price as f32),
+        (line.end_time, line.end_price as f32),
+    ]
+}
+
+/// Breakout level continues after the pattern for a quarter of its duration
+fn breakout_end(pattern: &ChartPattern) -> DateTime<Utc> {
+    pattern.end_time + (pattern.end_time - pattern.start_time) / 4
+}
+
+impl<'a> PlotterIndicatorContext for ChartPatternPlotter<'a> {
+    fn plot(
+        &self,
+        _selection: &Selection,
+        chart_context: &mut ChartContext<
+            BitMapBackend<RGBPixel>,
+            Cartesian2d<RangedDateTime<DateTime<Utc>>, RangedCoordf32>,
+        >,
+    ) -> eyre::Result<()> {
+        let green = RGBColor(16, 160, 64);
+        let red = RGBColor(192, 16, 64);
+
+        for pattern in self.patterns.iter() {
+            let color = if pattern.bullish { green } else { red };
+            chart_context.draw_series(LineSeries::new(line_points(&pattern.upper), &color))?;
+            chart_context.draw_series(LineSeries::new(line_points(&pattern.lower), &color))?;
+
+            chart_context.draw_series(LineSeries::new(
+                vec![
+                    (pattern.end_time, pattern.breakout as f32),
+                    (breakout_end(pattern), pattern.breakout as f32),
+                ],
+                ShapeStyle::from(&color).stroke_width(2),
+            ))?;
+
+            chart_context.draw_series(std::iter::once(Text::new(
+                format!("{} {:.2}", pattern.pattern_type, pattern.quality),
+                (pattern.start_time, pattern.upper.start_price as f32),
+                ("sans-serif", 12).into_font().color(&color),
+            )))?;
+        }
+        Ok(())
+    }
+
+    fn min_max(&self) -> (f64, f64) {
+        self.patterns
+            .iter()
+            .flat_map(|p| {
+                [
+                    p.upper.start_price,
+                    p.upper.end_price,
+                    p.lower.start_price,
+                    p.lower.end_price,
+                ]
+            })
+            .fold((f64::MAX, f64::MIN), |(min, max), price| {
+                (min.min(price), max.max(price))
+            })
+    }
+
+    fn time_range(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let start_time = self.patterns.iter().map(|p| p.start_time).min()?;
+        let end_time = self.patterns.iter().map(breakout_end).max()?;
+        Some((start_time, end_time))
+    }
+}
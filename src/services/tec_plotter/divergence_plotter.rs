@@ -0,0 +1,117 @@
+use super::plotter_indicator_area::PlotterIndicatorArea;
+use crate::config::selection::Selection;
+use crate::services::tec_plotter::plotter_indicator_context::PlotterIndicatorContext;
+use crate::services::technicals::divergence_tec::Divergence;
+use crate::services::technicals::serie_indicator::SerieIndicator;
+use crate::services::technicals::technical::TecSerieIndicators;
+use chrono::{DateTime, Utc};
+use plotters::coord::types::{RangedCoordf32, RangedCoordf64};
+use plotters::prelude::*;
+use plotters_bitmap::{bitmap_pixel::RGBPixel, BitMapBackend};
+
+fn divergence_color(divergence: &Divergence) -> RGBColor {
+    if divergence.divergence_type.is_bullish() {
+        RGBColor(16, 160, 64)
+    } else {
+        RGBColor(192, 16, 64)
+    }
+}
+
+/// Hidden divergences are drawn thinner than regular ones
+fn divergence_style(divergence: &Divergence) -> ShapeStyle {
+    let width = if divergence.divergence_type.is_hidden() {
+        1
+    } else {
+        2
+    };
+    ShapeStyle::from(&divergence_color(divergence)).stroke_width(width)
+}
+
+/// Lines connecting the price swings of the divergences
+pub struct DivergencePlotter<'a> {
+    divergences: &'a [Divergence],
+}
+
+impl<'a> DivergencePlotter<'a> {
+    pub fn new(divergences: &'a [Divergence]) -> Self {
+        Self { divergences }
+    }
+}
+
+impl<'a> PlotterIndicatorContext for DivergencePlotter<'a> {
+    fn plot(
+        &self,
+        _selection: &Selection,
+        chart_context: &mut ChartContext<
+            BitMapBackend<RGBPixel>,
+            Cartesian2d<RangedDateTime<DateTime<Utc>>, RangedCoordf32>,
+        >,
+    ) -> eyre::Result<()> {
+        for divergence in self.divergences.iter() {
+            chart_context.draw_series(LineSeries::new(
+                vec![
+                    (divergence.start_time, divergence.start_price as f32),
+                    (divergence.end_time, divergence.end_price as f32),
+                ],
+                divergence_style(divergence),
+            ))?;
+        }
+        Ok(())
+    }
+
+    fn min_max(&self) -> (f64, f64) {
+        self.divergences
+            .iter()
+            .fold((f64::MAX, f64::MIN), |(min, max), d| {
+                (
+                    min.min(d.start_price.min(d.end_price)),
+                    max.max(d.start_price.max(d.end_price)),
+                )
+            })
+    }
+}
+
+/// Oscillator panel with lines connecting its values at the divergences swings
+pub struct DivergenceAreaPlotter<'a> {
+    plotter: &'a dyn PlotterIndicatorArea,
+    divergences: &'a [Divergence],
+}
+
+impl<'a> DivergenceAreaPlotter<'a> {
+    pub fn new(plotter: &'a dyn PlotterIndicatorArea, divergences: &'a [Divergence]) -> Self {
+        Self {
+            plotter,
+            divergences,
+        }
+    }
+}
+
+impl<'a> PlotterIndicatorArea for DivergenceAreaPlotter<'a> {
+    fn tec_serie_indicators(&self) -> &dyn TecSerieIndicators {
+        self.plotter.tec_serie_indicators()
+    }
+
+    fn indicator_color(&self, indicator: &SerieIndicator) -> RGBColor {
+        self.plotter.indicator_color(indicator)
+    }
+
+    fn plot_overlay(
+        &self,
+        chart_context: &mut ChartContext<
+            BitMapBackend<RGBPixel>,
+            Cartesian2d<RangedDateTime<DateTime<Utc>>, RangedCoordf64>,
+        >,
+    ) -> eyre::Result<()> {
+        self.plotter.plot_overlay(chart_context)?;
+        for divergence in self.divergences.iter() {
+            chart_context.draw_series(LineSeries::new(
+                vec![
+                    (divergence.start_time, divergence.start_value),
+                    (divergence.end_time, divergence.end_value),
+                ],
+                divergence_style(divergence),
+            ))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use super::plotter_indicator_area::PlotterIndicatorArea;
+use crate::config::definition::TacDefinition;
+use crate::model::candle::Candle;
+use crate::model::equity_point::EquityPoint;
+use crate::services::technicals::serie::Serie;
+use crate::services::technicals::serie_indicator::SerieIndicator;
+use crate::services::technicals::technical::TecSerieIndicators;
+use plotters::style::{RGBColor, BLACK};
+use rust_decimal::prelude::ToPrimitive;
+use std::collections::HashMap;
+
+pub const TEC_EQUITY: &str = "equity";
+pub const IND_EQUITY: &str = "equity";
+pub const IND_BUY_AND_HOLD: &str = "buy_and_hold";
+pub const TEC_DRAWDOWN: &str = "drawdown";
+pub const IND_DRAWDOWN: &str = "drawdown";
+
+/// Back test equity series to plot in a lower panel
+pub struct EquityTec {
+    name: String,
+    indicators: HashMap<String, SerieIndicator>,
+}
+
+impl EquityTec {
+    /// Strategy equity against holding the asset bought with the initial equity, the equity
+    /// points are at the price candles close
+    pub fn equity(equity: &[EquityPoint], prices: &[Candle]) -> Self {
+        let strategy = equity
+            .iter()
+            .map(|p| Serie::new(p.time, p.equity.to_f64().unwrap()))
+            .collect::<Vec<_>>();
+        let buy_and_hold = match (equity.first(), prices.first()) {
+            (Some(first), Some(candle)) => {
+                let asset = first.equity / candle.open;
+                prices
+                    .iter()
+                    .map(|c| Serie::new(c.close_time, (asset * c.close).to_f64().unwrap()))
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+        Self::from(
+            TEC_EQUITY,
+            vec![
+                SerieIndicator::from(IND_EQUITY, strategy),
+                SerieIndicator::from(IND_BUY_AND_HOLD, buy_and_hold),
+            ],
+        )
+    }
+
+    /// Percent under the previous equity peak
+    pub fn drawdown(equity: &[EquityPoint]) -> Self {
+        let mut peak = 0_f64;
+        let drawdown = equity
+            .iter()
+            .map(|p| {
+                let value = p.equity.to_f64().unwrap();
+                peak = peak.max(value);
+                let drawdown = if peak > 0. {
+                    (value / peak - 1.) * 100.
+                } else {
+                    0.
+                };
+                Serie::new(p.time, drawdown)
+            })
+            .collect();
+        Self::from(
+            TEC_DRAWDOWN,
+            vec![SerieIndicator::from(IND_DRAWDOWN, drawdown)],
+        )
+    }
+
+    fn from(name: &str, series: Vec<SerieIndicator>) -> Self {
+        let indicators = series
+            .into_iter()
+            .map(|s| (s.name.clone(), s))
+            .collect::<HashMap<_, _>>();
+        Self {
+            name: name.to_string(),
+            indicators,
+        }
+    }
+
+    pub fn tac_definition(&self) -> TacDefinition {
+        let names = self.indicators.keys().map(|k| &k[..]).collect::<Vec<_>>();
+        TacDefinition::new(&self.name, &names)
+    }
+
+    /// A flat series can't be plotted
+    pub fn is_flat(&self) -> bool {
+        self.indicators
+            .values()
+            .flat_map(|i| i.series.iter())
+            .all(|s| s.value == 0.)
+    }
+}
+
+impl TecSerieIndicators for EquityTec {
+    fn serie_indicators(&self) -> &HashMap<String, SerieIndicator> {
+        &self.indicators
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+/// Lower panel of the equity or the drawdown
+pub struct EquityPlotter<'a> {
+    tec: &'a EquityTec,
+}
+
+impl<'a> EquityPlotter<'a> {
+    pub fn new(tec: &'a EquityTec) -> Self {
+        Self { tec }
+    }
+}
+
+impl<'a> PlotterIndicatorArea for EquityPlotter<'a> {
+    fn indicator_color(&self, indicator: &SerieIndicator) -> RGBColor {
+        match &indicator.name[..] {
+            IND_EQUITY => RGBColor(0, 128, 128),
+            IND_BUY_AND_HOLD => RGBColor(128, 128, 128),
+            IND_DRAWDOWN => RGBColor(164, 0, 16),
+            _ => BLACK,
+        }
+    }
+
+    fn tec_serie_indicators(&self) -> &dyn TecSerieIndicators {
+        self.tec as &dyn TecSerieIndicators
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::utils::date_utils::str_to_datetime;
+    use chrono::Duration;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn equity_tec_test() {
+        let start = str_to_datetime("2020-01-12 00:00:00");
+        let equity = [1000, 1200, 900, 1300]
+            .iter()
+            .enumerate()
+            .map(|(i, e)| {
+                EquityPoint::new(start + Duration::hours(i as i64), Decimal::from(*e), true)
+            })
+            .collect::<Vec<_>>();
+
+        let drawdown = EquityTec::drawdown(&equity);
+        let values = drawdown.serie_indicators()[IND_DRAWDOWN]
+            .series
+            .iter()
+            .map(|s| s.value)
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![0., 0., -25., 0.]);
+        assert!(!drawdown.is_flat());
+        assert!(EquityTec::drawdown(&equity[..2]).is_flat());
+    }
+}
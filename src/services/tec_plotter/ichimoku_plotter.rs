@@ -0,0 +1,91 @@
+use crate::config::selection::Selection;
+use crate::services::tec_plotter::plotter_indicator_context::PlotterIndicatorContext;
+use crate::services::technicals::ichimoku_tec::{
+    IchimokuTec, IND_CHIKOU, IND_KIJUN, IND_SENKOU_A, IND_SENKOU_B, IND_TENKAN,
+};
+use crate::services::technicals::indicator::Indicator;
+use chrono::{DateTime, Utc};
+use plotters::{coord::types::RangedCoordf32, prelude::*};
+use plotters_bitmap::{bitmap_pixel::RGBPixel, BitMapBackend};
+
+pub struct IchimokuPlotter<'a> {
+    ichimoku_tec: &'a IchimokuTec,
+}
+
+impl<'a> IchimokuPlotter<'a> {
+    pub fn new(ichimoku_tec: &'a IchimokuTec) -> Self {
+        Self { ichimoku_tec }
+    }
+}
+
+impl<'a> PlotterIndicatorContext for IchimokuPlotter<'a> {
+    fn plot(
+        &self,
+        _selection: &Selection,
+        chart_context: &mut ChartContext<
+            BitMapBackend<RGBPixel>,
+            Cartesian2d<RangedDateTime<DateTime<Utc>>, RangedCoordf32>,
+        >,
+    ) -> eyre::Result<()> {
+        let green = RGBColor(16, 196, 64);
+        let red = RGBColor(164, 16, 64);
+
+        // Cloud between senkou spans, green when span A is over span B
+        let senkou_a = &self.ichimoku_tec.serie_indicator(IND_SENKOU_A).series;
+        let senkou_b = &self.ichimoku_tec.serie_indicator(IND_SENKOU_B).series;
+        let cloud = senkou_a.windows(2).zip(senkou_b.windows(2)).map(|(a, b)| {
+            let color = if a[1].value >= b[1].value {
+                green.mix(0.2)
+            } else {
+                red.mix(0.2)
+            };
+            Polygon::new(
+                vec![
+                    (a[0].date_time, a[0].value as f32),
+                    (a[1].date_time, a[1].value as f32),
+                    (b[1].date_time, b[1].value as f32),
+                    (b[0].date_time, b[0].value as f32),
+                ],
+                ShapeStyle::from(&color).filled(),
+            )
+        });
+        chart_context.draw_series(cloud)?;
+
+        let lines = [
+            (IND_SENKOU_A, green.mix(0.6)),
+            (IND_SENKOU_B, red.mix(0.6)),
+            (IND_TENKAN, RGBColor(0, 96, 255).to_rgba()),
+            (IND_KIJUN, RGBColor(128, 0, 0).to_rgba()),
+            (IND_CHIKOU, RGBColor(96, 96, 96).to_rgba()),
+        ];
+        for (name, color) in lines.iter() {
+            let line_series = LineSeries::new(
+                self.ichimoku_tec
+                    .serie_indicator(name)
+                    .series
+                    .iter()
+                    .map(|s| (s.date_time, s.value as f32)),
+                color,
+            );
+            chart_context.draw_series(line_series)?;
+        }
+        Ok(())
+    }
+
+    fn min_max(&self) -> (f64, f64) {
+        self.ichimoku_tec
+            .indicators
+            .values()
+            .map(|i| i.min_max())
+            .reduce(|p, c| (p.0.min(c.0), p.1.max(c.1)))
+            .unwrap_or((f64::MAX, f64::MIN))
+    }
+
+    fn time_range(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        self.ichimoku_tec
+            .indicators
+            .values()
+            .filter_map(|i| i.time_range())
+            .reduce(|p, c| (p.0.min(c.0), p.1.max(c.1)))
+    }
+}
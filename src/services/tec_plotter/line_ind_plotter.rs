@@ -42,4 +42,8 @@ impl<'a> PlotterIndicatorContext for LineIndicatorPlotter<'a> {
     fn min_max(&self) -> (f64, f64) {
         self.indicator.min_max()
     }
+
+    fn time_range(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        self.indicator.time_range()
+    }
 }
@@ -0,0 +1,26 @@
+use super::plotter_indicator_area::PlotterIndicatorArea;
+use crate::services::technicals::serie_indicator::SerieIndicator;
+use crate::services::technicals::technical::TecSerieIndicators;
+use plotters::style::RGBColor;
+
+/// Lower panel drawing all selected series of a technical with the same color
+pub struct LinesAreaPlotter<'a> {
+    tec: &'a dyn TecSerieIndicators,
+    color: RGBColor,
+}
+
+impl<'a> LinesAreaPlotter<'a> {
+    pub fn new(tec: &'a dyn TecSerieIndicators, color: RGBColor) -> Self {
+        Self { tec, color }
+    }
+}
+
+impl<'a> PlotterIndicatorArea for LinesAreaPlotter<'a> {
+    fn indicator_color(&self, _indicator: &SerieIndicator) -> RGBColor {
+        self.color
+    }
+
+    fn tec_serie_indicators(&self) -> &dyn TecSerieIndicators {
+        self.tec
+    }
+}
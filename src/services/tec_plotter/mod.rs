@@ -1,12 +1,22 @@
 pub mod candles_plotter;
+pub mod chart_pattern_plotter;
+pub mod divergence_plotter;
+pub mod equity_plotter;
+pub mod ichimoku_plotter;
 pub mod line_ind_plotter;
+pub mod lines_area_plotter;
 pub mod macd_plotter;
+pub mod pair_plotter;
 pub mod plot_selection;
 pub mod plotter;
 pub mod plotter_indicator_area;
 pub mod plotter_indicator_context;
 pub mod plotter_utils;
+pub mod registered_plotter;
 pub mod rsi_plotter;
+pub mod support_resistance_plotter;
 pub mod theme_plotter;
 pub mod top_bottom_plotter;
 pub mod trading_plotter;
+pub mod volume_plotter;
+pub mod volume_profile_plotter;
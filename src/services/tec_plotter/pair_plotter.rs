@@ -0,0 +1,33 @@
+use super::plotter_indicator_area::PlotterIndicatorArea;
+use crate::services::technicals::pair_tec::{IND_BETA, IND_CORRELATION, IND_RATIO, IND_SPREAD};
+use crate::services::technicals::serie_indicator::SerieIndicator;
+use crate::services::technicals::technical::TecSerieIndicators;
+use plotters::prelude::*;
+use plotters::style::BLACK;
+
+/// Ratio or spread panel of the selection symbol against the pair symbol
+pub struct PairPlotter<'a> {
+    pair_tec: &'a dyn TecSerieIndicators,
+}
+
+impl<'a> PairPlotter<'a> {
+    pub fn new(pair_tec: &'a dyn TecSerieIndicators) -> Self {
+        PairPlotter { pair_tec }
+    }
+}
+
+impl<'a> PlotterIndicatorArea for PairPlotter<'a> {
+    fn indicator_color(&self, indicator: &SerieIndicator) -> RGBColor {
+        match &indicator.name[..] {
+            IND_RATIO => RGBColor(0, 128, 128),
+            IND_SPREAD => RGBColor(255, 140, 0),
+            IND_CORRELATION => RGBColor(128, 0, 128),
+            IND_BETA => RGBColor(96, 96, 96),
+            _ => BLACK,
+        }
+    }
+
+    fn tec_serie_indicators(&self) -> &dyn TecSerieIndicators {
+        self.pair_tec
+    }
+}
@@ -1,15 +1,41 @@
 use crate::config::selection::Selection;
+use crate::repository::trade_agg_repository::TradeAggRepository;
 use crate::services::provider::candles_provider::CandlesProvider;
-use crate::services::tec_plotter::candles_plotter::CandlePlotter;
+use crate::services::tec_plotter::candles_plotter::{spread_bricks, CandlePlotter};
+use crate::services::tec_plotter::divergence_plotter::{DivergenceAreaPlotter, DivergencePlotter};
+use crate::services::tec_plotter::ichimoku_plotter::IchimokuPlotter;
 use crate::services::tec_plotter::line_ind_plotter::LineIndicatorPlotter;
 use crate::services::tec_plotter::macd_plotter::MacdPlotter;
+use crate::services::tec_plotter::pair_plotter::PairPlotter;
 use crate::services::tec_plotter::plotter::Plotter;
+use crate::services::tec_plotter::plotter_indicator_area::PlotterIndicatorArea;
 use crate::services::tec_plotter::plotter_indicator_context::PlotterIndicatorContext;
+use crate::services::tec_plotter::registered_plotter::RegisteredPlotter;
 use crate::services::tec_plotter::rsi_plotter::RsiPlotter;
+use crate::services::tec_plotter::support_resistance_plotter::SupportResistancePlotter;
 use crate::services::tec_plotter::top_bottom_plotter::TopBottomPlotter;
-use crate::services::technicals::macd_tec::MacdTec;
-use crate::services::technicals::rsi_tec::RsiTec;
+use crate::services::tec_plotter::volume_plotter::VolumePlotter;
+use crate::services::tec_plotter::volume_profile_plotter::VolumeProfilePlotter;
+use crate::services::technicals::divergence_tec::{DivergenceTec, TEC_DIVERGENCE};
+use crate::services::technicals::ichimoku_tec::{IchimokuTec, TEC_ICHIMOKU};
+use crate::services::technicals::ind_registry::{PlotStyle, REGISTRY};
+use crate::services::technicals::macd_tec::{MacdTec, IND_MACD};
+use crate::services::technicals::pair_tec::{
+    PairRatioTec, PairSpreadTec, PAIR_PERIOD, TEC_PAIR_RATIO, TEC_PAIR_SPREAD,
+};
+use crate::services::technicals::rsi_tec::{RsiTec, IND_RSI};
+use crate::services::technicals::support_resistance_tec::{
+    SupportResistanceTec, TEC_SUPPORT_RESISTANCE, ZONE_NEIGHBORS,
+};
+use crate::services::technicals::technical::TecSerieIndicators;
 use crate::services::technicals::top_bottom_tec::TopBottomTec;
+use crate::services::technicals::volume_profile_tec::{
+    VolumeProfileTec, PROFILE_BINS, TEC_VOLUME_PROFILE,
+};
+use crate::services::technicals::volume_tec::{VolumeTec, TEC_VOLUME};
+use crate::services::technicals::vwap_tec::{
+    VwapTec, IND_VWAP, IND_VWAP_LOWER, IND_VWAP_UPPER, TEC_VWAP,
+};
 use crate::EmaTec;
 use colored::Colorize;
 use ifmt::iformat;
@@ -20,7 +46,10 @@ use std::time::Instant;
 pub struct PlotterSelection<'a> {
     selection: Selection,
     candles_provider: Box<dyn CandlesProvider>,
+    trade_agg_repository_opt: Option<TradeAggRepository>,
+    pair_candles_provider_opt: Option<Box<dyn CandlesProvider>>,
     additional_plotters: Vec<Box<dyn PlotterIndicatorContext + 'a>>,
+    additional_lower_plotters: Vec<Box<dyn PlotterIndicatorArea + 'a>>,
 }
 
 impl<'a> PlotterSelection<'a> {
@@ -28,15 +57,36 @@ impl<'a> PlotterSelection<'a> {
         Self {
             selection,
             candles_provider,
+            trade_agg_repository_opt: None,
+            pair_candles_provider_opt: None,
             additional_plotters: Vec::new(),
+            additional_lower_plotters: Vec::new(),
         }
     }
 
+    /// Read volume profile from trades instead of candles when available
+    pub fn set_trade_agg_repository(&mut self, trade_agg_repository: TradeAggRepository) {
+        self.trade_agg_repository_opt = Some(trade_agg_repository);
+    }
+
+    /// Candles of the pair symbol for the pair technicals
+    pub fn set_pair_candles_provider(&mut self, pair_candles_provider: Box<dyn CandlesProvider>) {
+        self.pair_candles_provider_opt = Some(pair_candles_provider);
+    }
+
     /// Push additional custom plotter
     pub fn push_plotter_ind(&mut self, plotter_indicator: Box<dyn PlotterIndicatorContext + 'a>) {
         self.additional_plotters.push(plotter_indicator);
     }
 
+    /// Push additional custom plotter in a lower panel, its tac must be selected
+    pub fn push_plotter_lower_ind(
+        &mut self,
+        plotter_indicator: Box<dyn PlotterIndicatorArea + 'a>,
+    ) {
+        self.additional_lower_plotters.push(plotter_indicator);
+    }
+
     pub fn plot(&mut self) -> eyre::Result<()> {
         let total_start = Instant::now();
 
@@ -51,6 +101,12 @@ impl<'a> PlotterSelection<'a> {
             .into_iter()
             .filter(|c| c.open_time >= start_time && c.open_time <= end_time)
             .collect::<Vec<_>>();
+        let candles = if self.selection.candles_selection.candle_type.is_time_based() {
+            candles
+        } else {
+            let minutes = self.selection.candles_selection.symbol_minutes.minutes;
+            spread_bricks(&candles, minutes)
+        };
 
         // TODO must obey the Selection.tacs
         // Default technicals
@@ -61,6 +117,90 @@ impl<'a> PlotterSelection<'a> {
         let top_bottom_tec = TopBottomTec::new(&candles, candles.len(), 7);
         let top_bottoms = top_bottom_tec.top_bottoms()?;
 
+        // Divergences of the main output of the selected registered oscillators with default
+        // params, only when selected
+        let divergence_tecs = match self.selection.tacs.get(TEC_DIVERGENCE) {
+            Some(tac) => REGISTRY
+                .iter()
+                .filter(|d| tac.indicators.contains(d.name))
+                .map(|d| {
+                    let tec = (d.build)(&candles, &d.default_params());
+                    let divergences = tec
+                        .serie_indicators()
+                        .get(d.outputs[0].name)
+                        .map(|oscillator| DivergenceTec::new(&top_bottoms, oscillator).divergences)
+                        .unwrap_or_default();
+                    (d, tec, divergences)
+                })
+                .collect::<Vec<_>>(),
+            None => Vec::new(),
+        };
+        let oscillator_divergences = |name: &str| {
+            divergence_tecs
+                .iter()
+                .find(|(d, _, _)| d.name == name)
+                .map(|(_, _, divergences)| divergences.as_slice())
+                .unwrap_or(&[])
+        };
+
+        // Volume technicals, only when selected
+        let is_vwap = self.selection.tacs.contains_key(TEC_VWAP);
+        let vwap_tec_opt = is_vwap.then(|| VwapTec::new(&candles, 2.));
+        let anchored_vwap_tec_opt = is_vwap
+            .then(|| VwapTec::anchored_last_top_bottom(&candles, &top_bottom_tec, 2.).ok())
+            .flatten();
+        let volume_profile_tec_opt = if !self.selection.tacs.contains_key(TEC_VOLUME_PROFILE) {
+            None
+        } else if let Some(trade_agg_repository) = &self.trade_agg_repository_opt {
+            Some(VolumeProfileTec::from_selection(
+                trade_agg_repository,
+                &self.selection.candles_selection,
+                &candles,
+                PROFILE_BINS,
+            )?)
+        } else {
+            Some(VolumeProfileTec::new(&candles, PROFILE_BINS))
+        };
+
+        // Zones weighted by the volume profile when selected
+        let support_resistance_tec_opt = self
+            .selection
+            .tacs
+            .contains_key(TEC_SUPPORT_RESISTANCE)
+            .then(|| {
+                SupportResistanceTec::new(&candles, ZONE_NEIGHBORS, volume_profile_tec_opt.as_ref())
+            });
+
+        let volume_tec_opt = self
+            .selection
+            .tacs
+            .contains_key(TEC_VOLUME)
+            .then(|| VolumeTec::new(&candles, 20));
+        let ichimoku_tec_opt = self
+            .selection
+            .tacs
+            .contains_key(TEC_ICHIMOKU)
+            .then(|| IchimokuTec::new(&candles, 9, 26, 52));
+
+        // Pair technicals, only when selected with a pair symbol
+        let pair_candles = match self.pair_candles_provider_opt.as_mut() {
+            Some(pair_candles_provider) => pair_candles_provider.candles()?,
+            None => Vec::new(),
+        };
+        let pair_ratio_tec_opt = (self.selection.tacs.contains_key(TEC_PAIR_RATIO)
+            && !pair_candles.is_empty())
+        .then(|| PairRatioTec::new(&candles, &pair_candles));
+        let pair_spread_tec_opt = (self.selection.tacs.contains_key(TEC_PAIR_SPREAD)
+            && !pair_candles.is_empty())
+        .then(|| PairSpreadTec::new(&candles, &pair_candles, PAIR_PERIOD));
+
+        // Registered indicators with default params, only when selected and plotted from here
+        let registered_tecs = REGISTRY
+            .iter()
+            .filter(|d| d.plot_style.is_some() && self.selection.tacs.contains_key(d.name))
+            .map(|d| (d, (d.build)(&candles, &d.default_params())))
+            .collect::<Vec<_>>();
+
         // Create plotter object
         let mut plotter = Plotter::new(self.selection.clone());
 
@@ -80,6 +220,86 @@ impl<'a> PlotterSelection<'a> {
         plotter.add_plotter_upper_ind(&ema_short_plotter);
         plotter.add_plotter_upper_ind(&ema_long_plotter);
 
+        // vwap = blue, bands = light blue, anchored vwap = dark cyan
+        let blue = RGBColor(30, 80, 200);
+        let light_blue = RGBColor(120, 160, 230);
+        let dark_cyan = RGBColor(0, 139, 139);
+        let mut vwap_plotters = Vec::new();
+        if let Some(vwap_tec) = &vwap_tec_opt {
+            for (name, color) in [
+                (IND_VWAP, blue),
+                (IND_VWAP_UPPER, light_blue),
+                (IND_VWAP_LOWER, light_blue),
+            ]
+            .iter()
+            {
+                vwap_plotters.push(LineIndicatorPlotter::new(
+                    vwap_tec.indicators.get(*name).unwrap(),
+                    *color,
+                ));
+            }
+        }
+        if let Some(anchored_vwap_tec) = &anchored_vwap_tec_opt {
+            vwap_plotters.push(LineIndicatorPlotter::new(
+                anchored_vwap_tec.main_serie_indicator(),
+                dark_cyan,
+            ));
+        }
+        vwap_plotters
+            .iter()
+            .for_each(|p| plotter.add_plotter_upper_ind(p));
+
+        // volume profile = gray
+        let volume_profile_plotter_opt = volume_profile_tec_opt
+            .as_ref()
+            .map(|tec| VolumeProfilePlotter::new(tec, RGBColor(96, 96, 96)));
+        if let Some(volume_profile_plotter) = &volume_profile_plotter_opt {
+            plotter.add_plotter_upper_ind(volume_profile_plotter);
+        }
+
+        // support/resistance zones = steel blue
+        let support_resistance_plotter_opt = support_resistance_tec_opt
+            .as_ref()
+            .map(|tec| SupportResistancePlotter::new(tec, RGBColor(70, 130, 180)));
+        if let Some(support_resistance_plotter) = &support_resistance_plotter_opt {
+            plotter.add_plotter_upper_ind(support_resistance_plotter);
+        }
+
+        let ichimoku_plotter_opt = ichimoku_tec_opt.as_ref().map(IchimokuPlotter::new);
+        if let Some(ichimoku_plotter) = &ichimoku_plotter_opt {
+            plotter.add_plotter_upper_ind(ichimoku_plotter);
+        }
+
+        let mut registered_upper_plotters = Vec::new();
+        for (definition, tec) in registered_tecs
+            .iter()
+            .filter(|(d, _)| d.plot_style == Some(PlotStyle::Upper))
+        {
+            let selected = &self.selection.tacs[definition.name].indicators;
+            for output in definition.outputs.iter() {
+                if let Some(indicator) = tec
+                    .serie_indicators()
+                    .get(output.name)
+                    .filter(|_| selected.contains(output.name))
+                {
+                    let (r, g, b) = output.color;
+                    registered_upper_plotters
+                        .push(LineIndicatorPlotter::new(indicator, RGBColor(r, g, b)));
+                }
+            }
+        }
+        registered_upper_plotters
+            .iter()
+            .for_each(|p| plotter.add_plotter_upper_ind(p));
+
+        let divergence_plotters = divergence_tecs
+            .iter()
+            .map(|(_, _, divergences)| DivergencePlotter::new(divergences))
+            .collect::<Vec<_>>();
+        divergence_plotters
+            .iter()
+            .for_each(|p| plotter.add_plotter_upper_ind(p));
+
         // Custom indicators
         self.additional_plotters
             .iter()
@@ -87,11 +307,61 @@ impl<'a> PlotterSelection<'a> {
 
         // Lower indicators plotters
         let macd_plotter = MacdPlotter::new(&macd_tac);
+        let macd_plotter =
+            DivergenceAreaPlotter::new(&macd_plotter, oscillator_divergences(IND_MACD));
         plotter.add_plotter_lower_ind(&macd_plotter);
 
         let rsi_plotter = RsiPlotter::new(&rsi_tac);
+        let rsi_plotter = DivergenceAreaPlotter::new(&rsi_plotter, oscillator_divergences(IND_RSI));
         plotter.add_plotter_lower_ind(&rsi_plotter);
 
+        // Other oscillators with divergences, in their own panel unless plotted already
+        let oscillator_plotters = divergence_tecs
+            .iter()
+            .filter(|(d, _, _)| d.plot_style.is_none() && d.name != IND_RSI && d.name != IND_MACD)
+            .map(|(d, tec, divergences)| (RegisteredPlotter::new(d, tec.as_ref()), divergences))
+            .collect::<Vec<_>>();
+        let oscillator_area_plotters = oscillator_plotters
+            .iter()
+            .map(|(p, divergences)| DivergenceAreaPlotter::new(p, divergences))
+            .collect::<Vec<_>>();
+        oscillator_area_plotters
+            .iter()
+            .for_each(|p| plotter.add_plotter_lower_ind(p));
+
+        let volume_plotter_opt = volume_tec_opt
+            .as_ref()
+            .map(|volume_tec| VolumePlotter::new(&candles, volume_tec));
+        if let Some(volume_plotter) = &volume_plotter_opt {
+            plotter.add_plotter_lower_ind(volume_plotter);
+        }
+
+        let pair_plotters = [
+            pair_ratio_tec_opt
+                .as_ref()
+                .map(|tec| PairPlotter::new(tec as &dyn TecSerieIndicators)),
+            pair_spread_tec_opt
+                .as_ref()
+                .map(|tec| PairPlotter::new(tec as &dyn TecSerieIndicators)),
+        ];
+        pair_plotters
+            .iter()
+            .flatten()
+            .for_each(|p| plotter.add_plotter_lower_ind(p));
+
+        let registered_lower_plotters = registered_tecs
+            .iter()
+            .filter(|(d, _)| d.plot_style == Some(PlotStyle::Lower))
+            .map(|(d, tec)| RegisteredPlotter::new(d, tec.as_ref()))
+            .collect::<Vec<_>>();
+        registered_lower_plotters
+            .iter()
+            .for_each(|p| plotter.add_plotter_lower_ind(p));
+
+        self.additional_lower_plotters
+            .iter()
+            .for_each(|p| plotter.add_plotter_lower_ind(&**p));
+
         plotter.plot(&self.selection.image_name)?;
 
         let elapsed = format!("{:?}", total_start.elapsed());
@@ -113,9 +383,17 @@ impl<'a> PlotterSelection<'a> {
 pub fn plot_selection<'a>(
     selection: Selection,
     candles_provider: Box<dyn CandlesProvider>,
+    trade_agg_repository_opt: Option<TradeAggRepository>,
+    pair_candles_provider_opt: Option<Box<dyn CandlesProvider>>,
     additional_plotters: Vec<Box<dyn PlotterIndicatorContext + 'a>>,
 ) -> eyre::Result<()> {
     let mut plotter_selection = PlotterSelection::from(selection, candles_provider);
+    if let Some(trade_agg_repository) = trade_agg_repository_opt {
+        plotter_selection.set_trade_agg_repository(trade_agg_repository);
+    }
+    if let Some(pair_candles_provider) = pair_candles_provider_opt {
+        plotter_selection.set_pair_candles_provider(pair_candles_provider);
+    }
     additional_plotters
         .into_iter()
         .for_each(|p| plotter_selection.push_plotter_ind(p));
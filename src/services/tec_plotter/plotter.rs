@@ -32,10 +32,19 @@ impl<'a> Plotter<'a> {
     }
 
     pub fn plot<P: AsRef<Path>>(&self, image_path: P) -> eyre::Result<()> {
-        let symbol_minutes = &self.selection.candles_selection.symbol_minutes;
+        // Extend time axis for series plotted beyond the selection (e.g. displaced forward)
+        let mut selection = self.selection.clone();
+        selection.candles_selection.end_time = self
+            .plotters_ind_upper
+            .iter()
+            .filter_map(|i| i.time_range())
+            .fold(selection.candles_selection.end_time, |acc, r| acc.max(r.1));
+        let selection = &selection;
+
+        let symbol_minutes = &selection.candles_selection.symbol_minutes;
 
-        let from_date = self.selection.candles_selection.start_time;
-        let to_date = self.selection.candles_selection.end_time;
+        let from_date = selection.candles_selection.start_time;
+        let to_date = selection.candles_selection.end_time;
 
         let (min_price, max_price) = self
             .plotters_ind_upper
@@ -87,12 +96,12 @@ impl<'a> Plotter<'a> {
             .draw()?;
 
         for plotter_upper_ind in self.plotters_ind_upper.iter() {
-            plotter_upper_ind.plot(&self.selection, &mut chart_context_upper)?;
+            plotter_upper_ind.plot(selection, &mut chart_context_upper)?;
         }
 
         for (plotter_lower_ind, lower_area) in plotter_areas.iter() {
             lower_area.fill(&bg_color)?;
-            plotter_lower_ind.plot(&self.selection, &lower_area)?;
+            plotter_lower_ind.plot(selection, &lower_area)?;
         }
         Ok(())
     }
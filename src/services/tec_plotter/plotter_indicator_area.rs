@@ -1,14 +1,18 @@
 use crate::services::technicals::indicator::Indicator;
 use crate::services::technicals::technical::TecSerieIndicators;
 use crate::{config::selection::Selection, services::technicals::serie_indicator::SerieIndicator};
+use chrono::{DateTime, Utc};
 use eyre::bail;
 use eyre::eyre;
 use log::debug;
-use plotters::{coord::Shift, prelude::DrawingArea, style::RGBColor};
 use plotters::{
-    prelude::{ChartBuilder, LabelAreaPosition, LineSeries},
+    coord::types::RangedCoordf64,
+    prelude::{
+        Cartesian2d, ChartBuilder, ChartContext, LabelAreaPosition, LineSeries, RangedDateTime,
+    },
     style::WHITE,
 };
+use plotters::{coord::Shift, prelude::DrawingArea, style::RGBColor};
 use plotters_bitmap::{bitmap_pixel::RGBPixel, BitMapBackend};
 
 pub trait PlotterIndicatorArea {
@@ -45,6 +49,17 @@ pub trait PlotterIndicatorArea {
 
     fn indicator_color(&self, indicator: &SerieIndicator) -> RGBColor;
 
+    /// Draws over the indicators of the panel
+    fn plot_overlay(
+        &self,
+        _chart_context: &mut ChartContext<
+            BitMapBackend<RGBPixel>,
+            Cartesian2d<RangedDateTime<DateTime<Utc>>, RangedCoordf64>,
+        >,
+    ) -> eyre::Result<()> {
+        Ok(())
+    }
+
     fn plot_indicators(
         &self,
         indicators: &[&SerieIndicator],
@@ -86,6 +101,7 @@ pub trait PlotterIndicatorArea {
             );
             cart_context_lower.draw_series(macd_series)?;
         }
+        self.plot_overlay(&mut cart_context_lower)?;
 
         Ok(())
     }
@@ -15,4 +15,9 @@ pub trait PlotterIndicatorContext {
     ) -> eyre::Result<()>;
 
     fn min_max(&self) -> (f64, f64);
+
+    /// Date time range plotted when it could exceed the selection (e.g. displaced series)
+    fn time_range(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        None
+    }
 }
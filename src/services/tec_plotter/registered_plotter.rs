@@ -0,0 +1,32 @@
+use super::plotter_indicator_area::PlotterIndicatorArea;
+use crate::services::technicals::ind_registry::IndicatorDefinition;
+use crate::services::technicals::serie_indicator::SerieIndicator;
+use crate::services::technicals::technical::TecSerieIndicators;
+use plotters::style::{RGBColor, BLACK};
+
+/// Lower panel of a registered indicator, with the outputs default colors
+pub struct RegisteredPlotter<'a> {
+    definition: &'a IndicatorDefinition,
+    tec: &'a dyn TecSerieIndicators,
+}
+
+impl<'a> RegisteredPlotter<'a> {
+    pub fn new(definition: &'a IndicatorDefinition, tec: &'a dyn TecSerieIndicators) -> Self {
+        Self { definition, tec }
+    }
+}
+
+impl<'a> PlotterIndicatorArea for RegisteredPlotter<'a> {
+    fn indicator_color(&self, indicator: &SerieIndicator) -> RGBColor {
+        self.definition
+            .outputs
+            .iter()
+            .find(|o| o.name == indicator.name)
+            .map(|o| RGBColor(o.color.0, o.color.1, o.color.2))
+            .unwrap_or(BLACK)
+    }
+
+    fn tec_serie_indicators(&self) -> &dyn TecSerieIndicators {
+        self.tec
+    }
+}
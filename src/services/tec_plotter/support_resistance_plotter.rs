@@ -0,0 +1,65 @@
+use crate::config::selection::Selection;
+use crate::services::tec_plotter::plotter_indicator_context::PlotterIndicatorContext;
+use crate::services::technicals::support_resistance_tec::SupportResistanceTec;
+use chrono::{DateTime, Utc};
+use plotters::{coord::types::RangedCoordf32, prelude::*};
+use plotters_bitmap::{bitmap_pixel::RGBPixel, BitMapBackend};
+
+/// Draws support/resistance zones along the whole chart, stronger zones are more opaque
+pub struct SupportResistancePlotter<'a> {
+    support_resistance: &'a SupportResistanceTec,
+    color: RGBColor,
+}
+
+impl<'a> SupportResistancePlotter<'a> {
+    pub fn new(support_resistance: &'a SupportResistanceTec, color: RGBColor) -> Self {
+        Self {
+            support_resistance,
+            color,
+        }
+    }
+}
+
+impl<'a> PlotterIndicatorContext for SupportResistancePlotter<'a> {
+    fn plot(
+        &self,
+        selection: &Selection,
+        chart_context: &mut ChartContext<
+            BitMapBackend<RGBPixel>,
+            Cartesian2d<RangedDateTime<DateTime<Utc>>, RangedCoordf32>,
+        >,
+    ) -> eyre::Result<()> {
+        let zones = &self.support_resistance.zones;
+        let max_strength = zones.iter().fold(0f64, |acc, z| acc.max(z.strength));
+        if max_strength <= 0. {
+            return Ok(());
+        }
+
+        let start_time = selection.candles_selection.start_time;
+        let end_time = selection.candles_selection.end_time;
+        for zone in zones.iter() {
+            let opacity = 0.1 + 0.3 * zone.strength / max_strength;
+            chart_context.draw_series(std::iter::once(Rectangle::new(
+                [
+                    (start_time, zone.price_low as f32),
+                    (end_time, zone.price_high as f32),
+                ],
+                ShapeStyle::from(&self.color.mix(opacity)).filled(),
+            )))?;
+            let price = zone.price() as f32;
+            chart_context.draw_series(LineSeries::new(
+                vec![(start_time, price), (end_time, price)],
+                &self.color.mix(opacity * 2.),
+            ))?;
+        }
+        Ok(())
+    }
+
+    fn min_max(&self) -> (f64, f64) {
+        let zones = &self.support_resistance.zones;
+        match (zones.first(), zones.last()) {
+            (Some(first), Some(last)) => (first.price_low, last.price_high),
+            _ => (f64::MAX, f64::MIN),
+        }
+    }
+}
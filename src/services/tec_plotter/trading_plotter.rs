@@ -1,6 +1,6 @@
 use super::plotter_indicator_context::PlotterIndicatorContext;
 use crate::services::trading::trade_operation::TradeOperation;
-use crate::{config::selection::Selection, model::side::Side};
+use crate::{config::selection::Selection, model::operation::Operation};
 use chrono::{DateTime, Utc};
 use plotters::{
     coord::types::RangedCoordf32,
@@ -11,6 +11,7 @@ use plotters_bitmap::{bitmap_pixel::RGBPixel, BitMapBackend};
 use rust_decimal::prelude::ToPrimitive;
 use rust_decimal_macros::dec;
 
+/// Markers of the filled trades at their fill time and price, colored by operation
 pub struct TradingPlotter<'a> {
     trades: &'a [TradeOperation],
 }
@@ -32,31 +33,20 @@ impl<'a> PlotterIndicatorContext for TradingPlotter<'a> {
     ) -> eyre::Result<()> {
         let red = RGBColor(164, 0, 16);
         let green = RGBColor(16, 128, 32);
+        // Short side in its own colors
+        let purple = RGBColor(128, 0, 128);
+        let blue = RGBColor(16, 64, 160);
 
-        let trades = &self.trades;
-
-        let sell_iter = trades
-            .iter()
-            .filter(|p| p.operation.to_side() == Side::Sold)
-            .map(|c| (c.now, c.price.0.to_f32().unwrap()))
-            .collect::<Vec<_>>();
-
-        let buy_iter = trades
-            .iter()
-            .filter(|p| p.operation.to_side() == Side::Bought)
-            .map(|c| (c.now, c.price.0.to_f32().unwrap()))
-            .collect::<Vec<_>>();
-
-        chart_context.draw_series(
-            sell_iter
-                .iter()
-                .map(|point| TriangleMarker::new(*point, 10, &red)),
-        )?;
-        chart_context.draw_series(
-            buy_iter
-                .iter()
-                .map(|point| TriangleMarker::new(*point, 10, &green)),
-        )?;
+        for trade in self.trades.iter() {
+            let color = match trade.operation {
+                Operation::Buy(_) => &green,
+                Operation::Sell(_) => &red,
+                Operation::Short(_) => &purple,
+                Operation::Cover(_) => &blue,
+            };
+            let point = (trade.now, trade.price.0.to_f32().unwrap());
+            chart_context.draw_series(std::iter::once(TriangleMarker::new(point, 10, color)))?;
+        }
 
         // let lows = PointSeries::of_element(
         //     sell_iter.into_iter(),
@@ -0,0 +1,95 @@
+use super::plotter_indicator_area::PlotterIndicatorArea;
+use crate::config::selection::Selection;
+use crate::model::candle::Candle;
+use crate::services::technicals::technical::TecSerieIndicators;
+use crate::services::technicals::volume_tec::{VolumeTec, IND_VOLUME_SMA};
+use crate::services::technicals::{indicator::Indicator, serie_indicator::SerieIndicator};
+use eyre::eyre;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use plotters_bitmap::{bitmap_pixel::RGBPixel, BitMapBackend};
+use rust_decimal::prelude::ToPrimitive;
+
+/// Volume bars coloured by candle direction, with the volume moving average
+pub struct VolumePlotter<'a> {
+    candles: &'a [Candle],
+    volume_tec: &'a VolumeTec,
+}
+
+impl<'a> VolumePlotter<'a> {
+    pub fn new(candles: &'a [Candle], volume_tec: &'a VolumeTec) -> Self {
+        VolumePlotter {
+            candles,
+            volume_tec,
+        }
+    }
+}
+
+impl<'a> PlotterIndicatorArea for VolumePlotter<'a> {
+    fn indicator_color(&self, indicator: &SerieIndicator) -> RGBColor {
+        match &indicator.name[..] {
+            IND_VOLUME_SMA => RGBColor(255, 165, 0),
+            _ => BLACK,
+        }
+    }
+
+    fn tec_serie_indicators(&self) -> &dyn TecSerieIndicators {
+        self.volume_tec
+    }
+
+    fn plot(
+        &self,
+        selection: &Selection,
+        lower: &DrawingArea<BitMapBackend<RGBPixel>, Shift>,
+    ) -> eyre::Result<()> {
+        let from_date = selection.candles_selection.start_time;
+        let to_date = selection.candles_selection.end_time;
+
+        let volume_sma = self
+            .volume_tec
+            .indicators
+            .get(IND_VOLUME_SMA)
+            .ok_or_else(|| eyre!("Indicator {} not found!", IND_VOLUME_SMA))?;
+
+        let max_volume = self
+            .candles
+            .iter()
+            .map(|c| c.volume.to_f64().unwrap())
+            .fold(volume_sma.min_max().1, f64::max);
+
+        let mut chart_context = ChartBuilder::on(lower)
+            .set_label_area_size(LabelAreaPosition::Left, 30)
+            .set_label_area_size(LabelAreaPosition::Right, 80)
+            .y_label_area_size(80)
+            .x_label_area_size(30)
+            .build_cartesian_2d(from_date..to_date, 0f64..max_volume.max(1.))?;
+
+        chart_context
+            .configure_mesh()
+            .light_line_style(WHITE)
+            .draw()?;
+
+        let red = RGBColor(164, 16, 64);
+        let green = RGBColor(16, 196, 64);
+
+        let bars = self.candles.iter().map(|c| {
+            let color = if c.close >= c.open { green } else { red };
+            Rectangle::new(
+                [
+                    (c.open_time, 0.),
+                    (c.close_time, c.volume.to_f64().unwrap()),
+                ],
+                ShapeStyle::from(&color.mix(0.6)).filled(),
+            )
+        });
+        chart_context.draw_series(bars)?;
+
+        let sma_series = LineSeries::new(
+            volume_sma.series.iter().map(|s| (s.date_time, s.value)),
+            &self.indicator_color(volume_sma),
+        );
+        chart_context.draw_series(sma_series)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+use crate::config::selection::Selection;
+use crate::services::tec_plotter::plotter_indicator_context::PlotterIndicatorContext;
+use crate::services::technicals::volume_profile_tec::VolumeProfileTec;
+use chrono::{DateTime, Duration, Utc};
+use plotters::{coord::types::RangedCoordf32, prelude::*};
+use plotters_bitmap::{bitmap_pixel::RGBPixel, BitMapBackend};
+
+/// Share of the chart width used by the largest volume bar
+const HISTOGRAM_WIDTH: f64 = 0.25;
+
+/// Draws the volume profile as a histogram on the right side of price chart
+pub struct VolumeProfilePlotter<'a> {
+    volume_profile: &'a VolumeProfileTec,
+    color: RGBColor,
+}
+
+impl<'a> VolumeProfilePlotter<'a> {
+    pub fn new(volume_profile: &'a VolumeProfileTec, color: RGBColor) -> Self {
+        Self {
+            volume_profile,
+            color,
+        }
+    }
+}
+
+impl<'a> PlotterIndicatorContext for VolumeProfilePlotter<'a> {
+    fn plot(
+        &self,
+        selection: &Selection,
+        chart_context: &mut ChartContext<
+            BitMapBackend<RGBPixel>,
+            Cartesian2d<RangedDateTime<DateTime<Utc>>, RangedCoordf32>,
+        >,
+    ) -> eyre::Result<()> {
+        let levels = &self.volume_profile.levels;
+        let max_volume = levels.iter().fold(0f64, |acc, l| acc.max(l.volume));
+        if max_volume <= 0. {
+            return Ok(());
+        }
+
+        let start_time = selection.candles_selection.start_time;
+        let end_time = selection.candles_selection.end_time;
+        let width_seconds = (end_time - start_time).num_seconds() as f64 * HISTOGRAM_WIDTH;
+
+        let value_area_color = self.color.mix(0.4);
+        let outside_color = self.color.mix(0.15);
+        let (val, vah) = (
+            self.volume_profile.val().unwrap_or_default(),
+            self.volume_profile.vah().unwrap_or_default(),
+        );
+
+        let bars = levels.iter().map(|l| {
+            let bar_width = Duration::seconds((width_seconds * l.volume / max_volume) as i64);
+            let color = if l.price_low >= val && l.price_high <= vah {
+                value_area_color
+            } else {
+                outside_color
+            };
+            Rectangle::new(
+                [
+                    (end_time - bar_width, l.price_low as f32),
+                    (end_time, l.price_high as f32),
+                ],
+                ShapeStyle::from(&color).filled(),
+            )
+        });
+        chart_context.draw_series(bars)?;
+
+        // Point of control and value area bounds along the whole chart
+        let lines = [
+            (self.volume_profile.poc(), self.color.mix(0.9)),
+            (self.volume_profile.vah(), self.color.mix(0.5)),
+            (self.volume_profile.val(), self.color.mix(0.5)),
+        ];
+        for (price_opt, color) in lines.iter() {
+            if let Some(price) = price_opt {
+                let price = *price as f32;
+                chart_context.draw_series(LineSeries::new(
+                    vec![(start_time, price), (end_time, price)],
+                    color,
+                ))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn min_max(&self) -> (f64, f64) {
+        let levels = &self.volume_profile.levels;
+        match (levels.first(), levels.last()) {
+            (Some(first), Some(last)) => (first.price_low, last.price_high),
+            _ => (f64::MAX, f64::MIN),
+        }
+    }
+}
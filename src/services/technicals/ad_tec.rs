@@ -0,0 +1,129 @@
+use super::ind_registry::{first_param, IndicatorDefinition, IndicatorOutput};
+use super::ind_stream::NextFn;
+use super::indicator::Indicator;
+use super::technical::TecSerieIndicators;
+use super::{
+    serie::Serie,
+    serie_indicator::SerieIndicator,
+    technical::{TechnicalDefinition, TechnicalIndicators},
+};
+use crate::config::definition::TacDefinition;
+use crate::model::candle::Candle;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+
+pub const IND_AD: &str = "ad";
+
+pub const TEC_AD: &str = "ad";
+
+pub const DEFINITION: IndicatorDefinition = IndicatorDefinition {
+    name: TEC_AD,
+    params: &[("period", 20)],
+    outputs: &[IndicatorOutput {
+        name: IND_AD,
+        script_opt: Some(IND_AD),
+        color: (30, 80, 200),
+        stream_opt: Some(stream),
+    }],
+    plot_style: None,
+    lookback: first_param,
+    build,
+};
+
+// Accumulated since the first candle, the period only sets the candles computed from
+fn build(candles: &[Candle], _params: &[usize]) -> Box<dyn TecSerieIndicators + Send + Sync> {
+    Box::new(AdTec::new(candles))
+}
+
+fn stream(_params: &[usize]) -> eyre::Result<NextFn> {
+    let mut ad = 0.;
+    Ok(Box::new(move |c| {
+        ad += money_flow_volume(c);
+        ad
+    }))
+}
+
+pub struct AdTec {
+    pub indicators: HashMap<String, SerieIndicator>,
+}
+
+impl TechnicalDefinition for AdTec {
+    fn definition() -> TacDefinition {
+        DEFINITION.tac_definition()
+    }
+}
+
+impl TechnicalIndicators for AdTec {
+    fn get_indicator(&self, name: &str) -> Option<&dyn Indicator> {
+        self.indicators.get(name).map(|s| s as &dyn Indicator)
+    }
+
+    fn main_indicator(&self) -> &dyn Indicator {
+        self.indicators.get(IND_AD).unwrap() as &dyn Indicator
+    }
+
+    fn name(&self) -> String {
+        TEC_AD.to_string()
+    }
+}
+
+impl TecSerieIndicators for AdTec {
+    fn serie_indicators(&self) -> &HashMap<String, SerieIndicator> {
+        &self.indicators
+    }
+
+    fn name(&self) -> String {
+        TEC_AD.to_string()
+    }
+}
+
+impl AdTec {
+    /// Accumulation/distribution line accumulated since the first candle
+    pub fn new(candles: &[Candle]) -> Self {
+        let mut ad_series = Vec::with_capacity(candles.len());
+
+        let mut ad = 0.;
+        for candle in candles.iter() {
+            ad += money_flow_volume(candle);
+            ad_series.push(Serie::new(candle.close_time, ad));
+        }
+
+        let mut indicators = HashMap::new();
+        indicators.insert(IND_AD.to_string(), SerieIndicator::from(IND_AD, ad_series));
+
+        Self { indicators }
+    }
+}
+
+/// Candle volume weighted by the close location inside the candle range, from -volume when
+/// closed at low to +volume when closed at high
+pub fn money_flow_volume(candle: &Candle) -> f64 {
+    let range = candle.high - candle.low;
+    if range == dec!(0) {
+        return 0.;
+    }
+    let multiplier = ((candle.close - candle.low) - (candle.high - candle.close)) / range;
+    (multiplier * candle.volume).to_f64().unwrap()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::utils::candles_utils::tests::candle;
+
+    #[test]
+    fn money_flow_volume_test() {
+        assert_eq!(money_flow_volume(&candle(12, 10, 12, 100)), 100.);
+        assert_eq!(money_flow_volume(&candle(12, 10, 10, 100)), -100.);
+        assert_eq!(money_flow_volume(&candle(12, 10, 11, 100)), 0.);
+        assert_eq!(money_flow_volume(&candle(10, 10, 10, 100)), 0.);
+    }
+
+    #[test]
+    fn ad_test() {
+        let candles = [candle(12, 10, 12, 100), candle(14, 10, 13, 40)];
+        let ad_tec = AdTec::new(&candles);
+        assert_eq!(ad_tec.main_indicator().value().unwrap(), 120.);
+    }
+}
@@ -0,0 +1,143 @@
+use super::ind_registry::{first_param, IndicatorDefinition, IndicatorOutput, PlotStyle};
+use super::indicator::Indicator;
+use super::technical::TecSerieIndicators;
+use super::{
+    serie::Serie,
+    serie_indicator::SerieIndicator,
+    technical::{TechnicalDefinition, TechnicalIndicators},
+};
+use crate::config::definition::TacDefinition;
+use crate::model::candle::Candle;
+use rust_decimal::prelude::ToPrimitive;
+use std::collections::HashMap;
+
+pub const IND_BB_UPPER: &str = "bb_upper";
+pub const IND_BB_MIDDLE: &str = "bb_middle";
+pub const IND_BB_LOWER: &str = "bb_lower";
+
+pub const TEC_BOLLINGER: &str = "bollinger";
+
+pub const DEFINITION: IndicatorDefinition = IndicatorDefinition {
+    name: TEC_BOLLINGER,
+    params: &[("period", 20), ("deviations", 2)],
+    outputs: &[
+        IndicatorOutput {
+            name: IND_BB_UPPER,
+            script_opt: Some(IND_BB_UPPER),
+            color: (120, 160, 230),
+            stream_opt: None,
+        },
+        IndicatorOutput {
+            name: IND_BB_MIDDLE,
+            script_opt: Some(IND_BB_MIDDLE),
+            color: (30, 80, 200),
+            stream_opt: None,
+        },
+        IndicatorOutput {
+            name: IND_BB_LOWER,
+            script_opt: Some(IND_BB_LOWER),
+            color: (120, 160, 230),
+            stream_opt: None,
+        },
+    ],
+    plot_style: Some(PlotStyle::Upper),
+    lookback: first_param,
+    build,
+};
+
+fn build(candles: &[Candle], params: &[usize]) -> Box<dyn TecSerieIndicators + Send + Sync> {
+    Box::new(BollingerTec::new(candles, params[0], params[1] as f64))
+}
+
+pub struct BollingerTec {
+    pub indicators: HashMap<String, SerieIndicator>,
+}
+
+impl TechnicalDefinition for BollingerTec {
+    fn definition() -> TacDefinition {
+        DEFINITION.tac_definition()
+    }
+}
+
+impl TechnicalIndicators for BollingerTec {
+    fn get_indicator(&self, name: &str) -> Option<&dyn Indicator> {
+        self.indicators.get(name).map(|s| s as &dyn Indicator)
+    }
+
+    fn main_indicator(&self) -> &dyn Indicator {
+        self.indicators.get(IND_BB_MIDDLE).unwrap() as &dyn Indicator
+    }
+
+    fn name(&self) -> String {
+        TEC_BOLLINGER.to_string()
+    }
+}
+
+impl TecSerieIndicators for BollingerTec {
+    fn serie_indicators(&self) -> &HashMap<String, SerieIndicator> {
+        &self.indicators
+    }
+
+    fn name(&self) -> String {
+        TEC_BOLLINGER.to_string()
+    }
+}
+
+impl BollingerTec {
+    /// Close moving average with bands `deviations` standard deviations away
+    pub fn new(candles: &[Candle], period: usize, deviations: f64) -> Self {
+        let mut upper_series = Vec::with_capacity(candles.len());
+        let mut middle_series = Vec::with_capacity(candles.len());
+        let mut lower_series = Vec::with_capacity(candles.len());
+
+        let closes = candles
+            .iter()
+            .map(|c| c.close.to_f64().unwrap())
+            .collect::<Vec<_>>();
+
+        for (i, candle) in candles.iter().enumerate() {
+            let window = &closes[(i + 1).saturating_sub(period)..=i];
+            let len = window.len() as f64;
+            let mean = window.iter().sum::<f64>() / len;
+            let variance = window.iter().map(|c| (c - mean).powi(2)).sum::<f64>() / len;
+            let band = deviations * variance.sqrt();
+
+            upper_series.push(Serie::new(candle.close_time, mean + band));
+            middle_series.push(Serie::new(candle.close_time, mean));
+            lower_series.push(Serie::new(candle.close_time, mean - band));
+        }
+
+        let mut indicators = HashMap::new();
+        let mut insert = |name: &str, series| {
+            indicators.insert(name.to_string(), SerieIndicator::from(name, series));
+        };
+        insert(IND_BB_UPPER, upper_series);
+        insert(IND_BB_MIDDLE, middle_series);
+        insert(IND_BB_LOWER, lower_series);
+
+        Self { indicators }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::utils::candles_utils::tests;
+
+    fn candle(close: i64) -> Candle {
+        tests::candle(close, close, close, 1)
+    }
+
+    #[test]
+    fn bollinger_test() {
+        let candles = [candle(10), candle(2), candle(4), candle(6)];
+        let tec = (DEFINITION.build)(&candles, &[3, 2]);
+        let value = |name| tec.serie_indicators().get(name).unwrap().value().unwrap();
+
+        // Mean of 2, 4, 6 is 4 and population deviation is sqrt(8 / 3)
+        let band = 2. * (8f64 / 3.).sqrt();
+        assert_eq!(value(IND_BB_MIDDLE), 4.);
+        assert_eq!(value(IND_BB_UPPER), 4. + band);
+        assert_eq!(value(IND_BB_LOWER), 4. - band);
+    }
+}
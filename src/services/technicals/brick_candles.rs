@@ -0,0 +1,389 @@
+use crate::model::candle::Candle;
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+/// Open and close of a brick, high and low are its extremes
+type Brick = (Decimal, Decimal);
+
+/// Renko bricks of `brick_size` from the closes, a reversal needs two bricks
+pub fn renko(candles: &[Candle], brick_size: Decimal) -> Vec<Candle> {
+    BrickStream::renko(brick_size).candles(candles)
+}
+
+/// Renko bricks sized by the average true range of the first `period` candles of the selection,
+/// none until `period` candles are known. Later candles don't change the size, so bricks already
+/// formed never change, but the size depends on the selection start time and doesn't follow
+/// later volatility.
+pub fn renko_atr(candles: &[Candle], period: usize) -> Vec<Candle> {
+    BrickStream::renko_atr(period).candles(candles)
+}
+
+/// Bars closing once their high low range reaches `range`, prices move inside each candle
+/// from open to the nearest extreme, the other extreme and close
+pub fn range_bars(candles: &[Candle], range: Decimal) -> Vec<Candle> {
+    BrickStream::range_bars(range).candles(candles)
+}
+
+/// Lines of the closes, a new line follows the trend and a reversal needs the close to break the
+/// extreme of the last `lines` lines
+pub fn line_break(candles: &[Candle], lines: usize) -> Vec<Candle> {
+    BrickStream::line_break(lines).candles(candles)
+}
+
+/// Bricks made from the source candles one at a time, the forming brick is carried to the next
+/// candle so candles loaded in chunks continue the same bricks
+pub struct BrickStream {
+    bricks: BrickCandles,
+    builder: BrickBuilder,
+}
+
+enum BrickBuilder {
+    Renko(Renko),
+    /// Period and first candles, until the bricks can be sized
+    RenkoAtr(usize, Vec<Candle>),
+    /// Range, and open, high and low of the forming bar
+    RangeBar(Decimal, Option<(Decimal, Decimal, Decimal)>),
+    /// Lines to break, last lines and start price
+    LineBreak(usize, Vec<Brick>, Option<Decimal>),
+}
+
+impl BrickStream {
+    pub fn renko(brick_size: Decimal) -> Self {
+        Self::new(BrickBuilder::Renko(Renko::new(brick_size)))
+    }
+
+    pub fn renko_atr(period: usize) -> Self {
+        Self::new(BrickBuilder::RenkoAtr(period, Vec::new()))
+    }
+
+    pub fn range_bars(range: Decimal) -> Self {
+        Self::new(BrickBuilder::RangeBar(range, None))
+    }
+
+    pub fn line_break(lines: usize) -> Self {
+        Self::new(BrickBuilder::LineBreak(lines, Vec::new(), None))
+    }
+
+    fn new(builder: BrickBuilder) -> Self {
+        Self {
+            bricks: BrickCandles::new(),
+            builder,
+        }
+    }
+
+    fn candles(mut self, candles: &[Candle]) -> Vec<Candle> {
+        candles.iter().flat_map(|c| self.next(c)).collect()
+    }
+
+    /// Bricks closed by the candle, oldest first
+    pub fn next(&mut self, candle: &Candle) -> Vec<Candle> {
+        match &mut self.builder {
+            BrickBuilder::Renko(renko) => {
+                let formed = renko.next(candle.close);
+                self.bricks.push(candle, &formed);
+            }
+            BrickBuilder::RenkoAtr(period, first) => {
+                first.push(*candle);
+                if first.len() < *period {
+                    return Vec::new();
+                }
+                let first = std::mem::take(first);
+                let brick_size = average_true_range(&first);
+                self.builder = BrickBuilder::Renko(Renko::new(brick_size));
+                return first.iter().flat_map(|c| self.next(c)).collect();
+            }
+            BrickBuilder::RangeBar(range, bar_opt) => {
+                let path = if candle.close >= candle.open {
+                    [candle.open, candle.low, candle.high, candle.close]
+                } else {
+                    [candle.open, candle.high, candle.low, candle.close]
+                };
+                let range = *range;
+                let mut formed = Vec::new();
+                for price in path.iter() {
+                    let (open, high, low) = bar_opt.get_or_insert((*price, *price, *price));
+                    loop {
+                        if *price > *high && *price - *low >= range {
+                            let close = *low + range;
+                            formed.push((*open, close));
+                            *open = close;
+                            *high = close;
+                            *low = close;
+                        } else if *price < *low && *high - *price >= range {
+                            let close = *high - range;
+                            formed.push((*open, close));
+                            *open = close;
+                            *high = close;
+                            *low = close;
+                        } else {
+                            *high = (*high).max(*price);
+                            *low = (*low).min(*price);
+                            break;
+                        }
+                    }
+                }
+                self.bricks.push_bars(candle, &formed, range);
+            }
+            BrickBuilder::LineBreak(lines, history, start_opt) => {
+                let price = candle.close;
+                let start = *start_opt.get_or_insert(candle.open);
+                let line_opt = match history.last() {
+                    None if price != start => Some((start, price)),
+                    None => None,
+                    Some(last) => {
+                        let up = last.1 > last.0;
+                        let highest = history.iter().map(|l| l.0.max(l.1)).max().unwrap();
+                        let lowest = history.iter().map(|l| l.0.min(l.1)).min().unwrap();
+                        if (up && price > last.1) || (!up && price < last.1) {
+                            Some((last.1, price))
+                        } else if (up && price < lowest) || (!up && price > highest) {
+                            Some((last.0, price))
+                        } else {
+                            None
+                        }
+                    }
+                };
+                let formed = line_opt.into_iter().collect::<Vec<_>>();
+                history.extend(formed.iter());
+                // Only the last lines can be broken
+                let excess = history.len().saturating_sub(*lines);
+                history.drain(..excess);
+                self.bricks.push(candle, &formed);
+            }
+        }
+        std::mem::take(&mut self.bricks.candles)
+    }
+}
+
+/// Bricks of `brick_size` around the last brick close
+struct Renko {
+    brick_size: Decimal,
+    base_opt: Option<Decimal>,
+    up_opt: Option<bool>,
+}
+
+impl Renko {
+    fn new(brick_size: Decimal) -> Self {
+        Self {
+            brick_size,
+            base_opt: None,
+            up_opt: None,
+        }
+    }
+
+    fn next(&mut self, price: Decimal) -> Vec<Brick> {
+        let brick_size = self.brick_size;
+        let base = self.base_opt.get_or_insert(price);
+        let mut formed = Vec::new();
+        if brick_size <= dec!(0) {
+            return formed;
+        }
+        loop {
+            let brick = match self.up_opt {
+                Some(true) if price <= *base - brick_size * dec!(2) => {
+                    (*base - brick_size, *base - brick_size * dec!(2))
+                }
+                Some(false) if price >= *base + brick_size * dec!(2) => {
+                    (*base + brick_size, *base + brick_size * dec!(2))
+                }
+                Some(false) | None if price <= *base - brick_size => (*base, *base - brick_size),
+                Some(true) | None if price >= *base + brick_size => (*base, *base + brick_size),
+                _ => break,
+            };
+            *base = brick.1;
+            self.up_opt = Some(brick.1 > brick.0);
+            formed.push(brick);
+        }
+        formed
+    }
+}
+
+/// Average true range of the candles, zero when empty
+fn average_true_range(candles: &[Candle]) -> Decimal {
+    if candles.is_empty() {
+        return dec!(0);
+    }
+    let true_ranges = candles.iter().enumerate().map(|(i, c)| match i {
+        0 => c.high - c.low,
+        _ => {
+            let previous_close = candles[i - 1].close;
+            (c.high - c.low)
+                .max((c.high - previous_close).abs())
+                .max((c.low - previous_close).abs())
+        }
+    });
+    true_ranges.sum::<Decimal>() / Decimal::from(candles.len())
+}
+
+/// Candles of the bricks, bricks closed in a source candle end at its close one second apart,
+/// a brick opens one second after the previous one closed
+struct BrickCandles {
+    candles: Vec<Candle>,
+    pending_volume: Decimal,
+    last_close_time_opt: Option<DateTime<Utc>>,
+}
+
+impl BrickCandles {
+    fn new() -> Self {
+        Self {
+            candles: Vec::new(),
+            pending_volume: dec!(0),
+            last_close_time_opt: None,
+        }
+    }
+
+    /// Renko and line break bricks, with the open and close as extremes
+    fn push(&mut self, source: &Candle, bricks: &[Brick]) {
+        let bars = bricks
+            .iter()
+            .map(|(open, close)| (*open, (*open).max(*close), (*open).min(*close), *close))
+            .collect::<Vec<_>>();
+        self.push_candles(source, &bars);
+    }
+
+    /// Range bars, extending from the close to `range` away in the opposite direction
+    fn push_bars(&mut self, source: &Candle, bricks: &[Brick], range: Decimal) {
+        let bars = bricks
+            .iter()
+            .map(|(open, close)| {
+                let (high, low) = if close > open {
+                    (*close, *close - range)
+                } else {
+                    (*close + range, *close)
+                };
+                (*open, high, low, *close)
+            })
+            .collect::<Vec<_>>();
+        self.push_candles(source, &bars);
+    }
+
+    fn push_candles(&mut self, source: &Candle, bars: &[(Decimal, Decimal, Decimal, Decimal)]) {
+        self.pending_volume += source.volume;
+        if bars.is_empty() {
+            return;
+        }
+
+        let volume = self.pending_volume / Decimal::from(bars.len());
+        self.pending_volume = dec!(0);
+        for (i, (open, high, low, close)) in bars.iter().enumerate() {
+            let close_time = source.close_time - Duration::seconds((bars.len() - 1 - i) as i64);
+            let open_time = self
+                .last_close_time_opt
+                .map(|t| t + Duration::seconds(1))
+                .unwrap_or(source.open_time)
+                .min(close_time);
+            self.last_close_time_opt = Some(close_time);
+            self.candles.push(Candle {
+                open_time,
+                close_time,
+                open: *open,
+                high: *high,
+                low: *low,
+                close: *close,
+                volume,
+                ..*source
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::utils::candles_utils::tests::candle_at;
+
+    fn candles(prices: &[(i64, i64, i64, i64)]) -> Vec<Candle> {
+        prices
+            .iter()
+            .zip(0..)
+            .map(|((open, high, low, close), i)| {
+                candle_at(
+                    i,
+                    Decimal::from(*open),
+                    Decimal::from(*high),
+                    Decimal::from(*low),
+                    Decimal::from(*close),
+                    dec!(10),
+                )
+            })
+            .collect()
+    }
+
+    fn open_closes(candles: &[Candle]) -> Vec<(Decimal, Decimal)> {
+        candles.iter().map(|c| (c.open, c.close)).collect()
+    }
+
+    fn ordered(candles: &[Candle]) -> bool {
+        candles
+            .windows(2)
+            .all(|w| w[0].close_time < w[1].open_time && w[1].open_time <= w[1].close_time)
+    }
+
+    #[test]
+    fn renko_test() {
+        let source = candles(&[
+            (100, 100, 100, 100),
+            (100, 125, 100, 125),
+            (125, 125, 105, 115),
+            (115, 115, 95, 95),
+        ]);
+        let bricks = renko(&source, dec!(10));
+        assert_eq!(
+            open_closes(&bricks),
+            vec![
+                (dec!(100), dec!(110)),
+                (dec!(110), dec!(120)),
+                (dec!(110), dec!(100)),
+            ]
+        );
+        assert_eq!(bricks[1].close_time, source[1].close_time);
+        assert_eq!(bricks[2].close_time, source[3].close_time);
+        assert_eq!(bricks[2].volume, dec!(20));
+        assert!(ordered(&bricks));
+
+        let atr_bricks = renko_atr(&source, 2);
+        assert_eq!(atr_bricks.len(), 3);
+        assert_eq!(atr_bricks[0].close, dec!(112.5));
+    }
+
+    #[test]
+    fn range_bars_test() {
+        let source = candles(&[(100, 106, 98, 104), (104, 105, 90, 92)]);
+        let bars = range_bars(&source, dec!(5));
+        assert_eq!(
+            open_closes(&bars),
+            vec![
+                (dec!(100), dec!(103)),
+                (dec!(103), dec!(101)),
+                (dec!(101), dec!(96)),
+                (dec!(96), dec!(91)),
+            ]
+        );
+        assert_eq!((bars[0].high, bars[0].low), (dec!(103), dec!(98)));
+        assert_eq!((bars[1].high, bars[1].low), (dec!(106), dec!(101)));
+        assert!(ordered(&bars));
+    }
+
+    #[test]
+    fn line_break_test() {
+        let source = candles(&[
+            (100, 100, 100, 101),
+            (101, 102, 101, 102),
+            (102, 103, 102, 103),
+            (103, 103, 101, 101),
+            (101, 101, 99, 99),
+        ]);
+        let lines = line_break(&source, 3);
+        assert_eq!(
+            open_closes(&lines),
+            vec![
+                (dec!(100), dec!(101)),
+                (dec!(101), dec!(102)),
+                (dec!(102), dec!(103)),
+                (dec!(102), dec!(99)),
+            ]
+        );
+        assert!(ordered(&lines));
+    }
+}
@@ -0,0 +1,141 @@
+use super::ind_registry::{first_param, IndicatorDefinition, IndicatorOutput};
+use super::ind_stream::{close_volume, push_window, NextFn};
+use super::indicator::Indicator;
+use super::technical::TecSerieIndicators;
+use super::{
+    serie::Serie,
+    serie_indicator::SerieIndicator,
+    technical::{TechnicalDefinition, TechnicalIndicators},
+};
+use crate::config::definition::TacDefinition;
+use crate::model::candle::Candle;
+use crate::services::technicals::ad_tec::money_flow_volume;
+use rust_decimal::prelude::ToPrimitive;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+
+pub const IND_CMF: &str = "cmf";
+
+pub const TEC_CMF: &str = "cmf";
+
+pub const DEFINITION: IndicatorDefinition = IndicatorDefinition {
+    name: TEC_CMF,
+    params: &[("period", 20)],
+    outputs: &[IndicatorOutput {
+        name: IND_CMF,
+        script_opt: Some(IND_CMF),
+        color: (0, 128, 128),
+        stream_opt: Some(stream),
+    }],
+    plot_style: None,
+    lookback: first_param,
+    build,
+};
+
+fn build(candles: &[Candle], params: &[usize]) -> Box<dyn TecSerieIndicators + Send + Sync> {
+    Box::new(CmfTec::new(candles, params[0]))
+}
+
+/// Window sums are recomputed in the batch order to keep the same rounding
+fn stream(params: &[usize]) -> eyre::Result<NextFn> {
+    let period = params[0];
+    let mut window = VecDeque::with_capacity(period + 1);
+    Ok(Box::new(move |c| {
+        push_window(&mut window, period, (money_flow_volume(c), close_volume(c)));
+        let volume = window.iter().map(|w| w.1).sum::<f64>();
+        if volume > 0. {
+            window.iter().map(|w| w.0).sum::<f64>() / volume
+        } else {
+            0.
+        }
+    }))
+}
+
+/// Chaikin money flow, from -1 when every candle closed at low to 1 when at high
+pub struct CmfTec {
+    pub indicators: HashMap<String, SerieIndicator>,
+}
+
+impl TechnicalDefinition for CmfTec {
+    fn definition() -> TacDefinition {
+        DEFINITION.tac_definition()
+    }
+}
+
+impl TechnicalIndicators for CmfTec {
+    fn get_indicator(&self, name: &str) -> Option<&dyn Indicator> {
+        self.indicators.get(name).map(|s| s as &dyn Indicator)
+    }
+
+    fn main_indicator(&self) -> &dyn Indicator {
+        self.indicators.get(IND_CMF).unwrap() as &dyn Indicator
+    }
+
+    fn name(&self) -> String {
+        TEC_CMF.to_string()
+    }
+}
+
+impl TecSerieIndicators for CmfTec {
+    fn serie_indicators(&self) -> &HashMap<String, SerieIndicator> {
+        &self.indicators
+    }
+
+    fn name(&self) -> String {
+        TEC_CMF.to_string()
+    }
+}
+
+impl CmfTec {
+    /// Chaikin money flow, money flow volume over volume of last `period` candles
+    pub fn new(candles: &[Candle], period: usize) -> Self {
+        let mut cmf_series = Vec::with_capacity(candles.len());
+
+        let money_flow_volumes = candles.iter().map(money_flow_volume).collect::<Vec<_>>();
+        let volumes = candles
+            .iter()
+            .map(|c| c.volume.to_f64().unwrap())
+            .collect::<Vec<_>>();
+
+        for (i, candle) in candles.iter().enumerate() {
+            let start = (i + 1).saturating_sub(period);
+            let volume = volumes[start..=i].iter().sum::<f64>();
+            let cmf = if volume > 0. {
+                money_flow_volumes[start..=i].iter().sum::<f64>() / volume
+            } else {
+                0.
+            };
+            cmf_series.push(Serie::new(candle.close_time, cmf));
+        }
+
+        let mut indicators = HashMap::new();
+        indicators.insert(
+            IND_CMF.to_string(),
+            SerieIndicator::from(IND_CMF, cmf_series),
+        );
+
+        Self { indicators }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::utils::candles_utils::tests::candle;
+
+    #[test]
+    fn cmf_test() {
+        let candles = [
+            candle(12, 10, 12, 100),
+            candle(14, 10, 13, 40),
+            candle(12, 10, 10, 60),
+        ];
+        let cmf_tec = CmfTec::new(&candles, 2);
+        let values = cmf_tec.indicators[IND_CMF]
+            .series
+            .iter()
+            .map(|s| s.value)
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![1., 120. / 140., (20. - 60.) / 100.]);
+    }
+}
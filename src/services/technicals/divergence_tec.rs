@@ -0,0 +1,189 @@
+use super::macd_tec::IND_MACD;
+use super::rsi_tec::IND_RSI;
+use super::serie_indicator::SerieIndicator;
+use super::technical::TechnicalDefinition;
+use super::top_bottom::{TopBottom, TopBottomType};
+use crate::config::definition::TacDefinition;
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+
+pub const TEC_DIVERGENCE: &str = "divergence";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DivergenceType {
+    /// Lower low of price, higher low of oscillator
+    RegularBullish,
+    /// Higher high of price, lower high of oscillator
+    RegularBearish,
+    /// Higher low of price, lower low of oscillator
+    HiddenBullish,
+    /// Lower high of price, higher high of oscillator
+    HiddenBearish,
+}
+
+impl DivergenceType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DivergenceType::RegularBullish => "regular_bullish",
+            DivergenceType::RegularBearish => "regular_bearish",
+            DivergenceType::HiddenBullish => "hidden_bullish",
+            DivergenceType::HiddenBearish => "hidden_bearish",
+        }
+    }
+
+    pub fn is_bullish(&self) -> bool {
+        matches!(
+            self,
+            DivergenceType::RegularBullish | DivergenceType::HiddenBullish
+        )
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        matches!(
+            self,
+            DivergenceType::HiddenBullish | DivergenceType::HiddenBearish
+        )
+    }
+}
+
+/// Previous and last swings of same type with the oscillator values at their candles
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Divergence {
+    pub divergence_type: DivergenceType,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub start_price: f64,
+    pub end_price: f64,
+    pub start_value: f64,
+    pub end_value: f64,
+}
+
+/// Divergences of price swings and an oscillator, selected indicators are the names of the
+/// registered oscillators plotted with their divergences, RSI and MACD by default
+pub struct DivergenceTec {
+    pub divergences: Vec<Divergence>,
+}
+
+impl TechnicalDefinition for DivergenceTec {
+    fn definition() -> TacDefinition {
+        TacDefinition::new(TEC_DIVERGENCE, &[IND_RSI, IND_MACD])
+    }
+}
+
+impl DivergenceTec {
+    pub fn new(top_bottoms: &[TopBottom], oscillator: &SerieIndicator) -> Self {
+        Self {
+            divergences: divergences(top_bottoms, oscillator),
+        }
+    }
+}
+
+/// Divergences between each pair of consecutive tops and of consecutive bottoms, sorted by
+/// their last swing
+pub fn divergences(top_bottoms: &[TopBottom], oscillator: &SerieIndicator) -> Vec<Divergence> {
+    let mut result = [TopBottomType::Top, TopBottomType::Bottom]
+        .iter()
+        .flat_map(|type_p| {
+            let swings = top_bottoms
+                .iter()
+                .filter(|tb| tb.type_p == *type_p)
+                .collect::<Vec<_>>();
+            swings
+                .windows(2)
+                .filter_map(|pair| divergence(pair[0], pair[1], oscillator))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    result.sort_by_key(|d| d.end_time);
+    result
+}
+
+fn divergence(
+    previous: &TopBottom,
+    last: &TopBottom,
+    oscillator: &SerieIndicator,
+) -> Option<Divergence> {
+    let start_value = value_at(oscillator, previous.close_time)?;
+    let end_value = value_at(oscillator, last.close_time)?;
+    let start_price = previous.price.to_f64().unwrap();
+    let end_price = last.price.to_f64().unwrap();
+
+    let price_higher = end_price > start_price;
+    let price_lower = end_price < start_price;
+    let value_higher = end_value > start_value;
+    let value_lower = end_value < start_value;
+    let divergence_type = match previous.type_p {
+        TopBottomType::Bottom if price_lower && value_higher => DivergenceType::RegularBullish,
+        TopBottomType::Bottom if price_higher && value_lower => DivergenceType::HiddenBullish,
+        TopBottomType::Top if price_higher && value_lower => DivergenceType::RegularBearish,
+        TopBottomType::Top if price_lower && value_higher => DivergenceType::HiddenBearish,
+        _ => return None,
+    };
+
+    Some(Divergence {
+        divergence_type,
+        start_time: previous.close_time,
+        end_time: last.close_time,
+        start_price,
+        end_price,
+        start_value,
+        end_value,
+    })
+}
+
+fn value_at(oscillator: &SerieIndicator, date_time: DateTime<Utc>) -> Option<f64> {
+    let index = oscillator
+        .series
+        .binary_search_by_key(&date_time, |s| s.date_time)
+        .ok()?;
+    Some(oscillator.series[index].value).filter(|v| v.is_finite())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::services::technicals::serie::Serie;
+    use crate::utils::date_utils::str_to_datetime;
+    use chrono::Duration;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn divergence_test() {
+        let start = str_to_datetime("2020-01-12 12:00:00");
+        let time = |i: i64| start + Duration::hours(i);
+        let top_bottoms = [
+            (TopBottomType::Bottom, 0, 100),
+            (TopBottomType::Top, 1, 120),
+            (TopBottomType::Bottom, 2, 95),
+            (TopBottomType::Top, 3, 125),
+            (TopBottomType::Bottom, 4, 97),
+            (TopBottomType::Top, 5, 122),
+        ]
+        .iter()
+        .map(|(type_p, i, price)| TopBottom::new(type_p.clone(), time(*i), Decimal::from(*price)))
+        .collect::<Vec<_>>();
+        let oscillator = SerieIndicator::from(
+            "rsi",
+            [30., 70., 35., 65., 30., 60.]
+                .iter()
+                .enumerate()
+                .map(|(i, value)| Serie::new(time(i as i64), *value))
+                .collect(),
+        );
+
+        let types = divergences(&top_bottoms, &oscillator)
+            .iter()
+            .map(|d| (d.divergence_type, d.end_time))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![
+                (DivergenceType::RegularBullish, time(2)),
+                (DivergenceType::RegularBearish, time(3)),
+                (DivergenceType::HiddenBullish, time(4)),
+            ]
+        );
+        assert!(DivergenceType::HiddenBullish.is_bullish());
+        assert!(DivergenceType::HiddenBullish.is_hidden());
+    }
+}
@@ -1,4 +1,7 @@
+use super::ind_registry::{first_param, IndicatorDefinition, IndicatorOutput};
+use super::ind_stream::{close, invalid_periods, NextFn};
 use super::indicator::Indicator;
+use super::technical::TecSerieIndicators;
 use super::{
     serie::Serie,
     serie_indicator::SerieIndicator,
@@ -14,14 +17,36 @@ pub const IND_EMA: &str = "ema";
 
 pub const TEC_EMA: &str = "ema";
 
+pub const DEFINITION: IndicatorDefinition = IndicatorDefinition {
+    name: TEC_EMA,
+    params: &[("period", 17)],
+    outputs: &[IndicatorOutput {
+        name: IND_EMA,
+        script_opt: Some(IND_EMA),
+        color: (128, 0, 128),
+        stream_opt: Some(stream),
+    }],
+    plot_style: None,
+    lookback: first_param,
+    build,
+};
+
+fn build(candles: &[Candle], params: &[usize]) -> Box<dyn TecSerieIndicators + Send + Sync> {
+    Box::new(EmaTec::new(candles, params[0]))
+}
+
+fn stream(params: &[usize]) -> eyre::Result<NextFn> {
+    let mut ema = Ema::new(params[0]).map_err(invalid_periods)?;
+    Ok(Box::new(move |c| ema.next(close(c))))
+}
+
 pub struct EmaTec {
     pub indicators: HashMap<String, SerieIndicator>,
 }
 
 impl TechnicalDefinition for EmaTec {
-    fn definition() -> crate::config::definition::TacDefinition {
-        let indicators = vec![IND_EMA];
-        TacDefinition::new(IND_EMA, &indicators)
+    fn definition() -> TacDefinition {
+        DEFINITION.tac_definition()
     }
 }
 
@@ -39,6 +64,16 @@ impl TechnicalIndicators for EmaTec {
     }
 }
 
+impl TecSerieIndicators for EmaTec {
+    fn serie_indicators(&self) -> &HashMap<String, SerieIndicator> {
+        &self.indicators
+    }
+
+    fn name(&self) -> String {
+        TEC_EMA.to_string()
+    }
+}
+
 impl<'a> EmaTec {
     pub fn new(candles: &[Candle], period: usize) -> Self {
         let mut ema_series = Vec::with_capacity(candles.len());
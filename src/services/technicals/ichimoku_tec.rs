@@ -0,0 +1,312 @@
+use super::ind_registry::{IndicatorDefinition, IndicatorOutput};
+use super::indicator::Indicator;
+use super::technical::TecSerieIndicators;
+use super::value_indicator::ValueIndicator;
+use super::{
+    serie::Serie,
+    serie_indicator::SerieIndicator,
+    technical::{TechnicalDefinition, TechnicalIndicators},
+};
+use crate::config::definition::TacDefinition;
+use crate::model::candle::Candle;
+use chrono::Duration;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+
+pub const IND_TENKAN: &str = "tenkan";
+pub const IND_KIJUN: &str = "kijun";
+pub const IND_SENKOU_A: &str = "senkou_a";
+pub const IND_SENKOU_B: &str = "senkou_b";
+pub const IND_CHIKOU: &str = "chikou";
+/// Close position against the cloud of last candle: 1 above, -1 below, 0 inside
+pub const IND_CLOUD: &str = "cloud";
+/// Tenkan/kijun cross on last candle: 1 tenkan crossed above, -1 crossed below, 0 none
+pub const IND_TK_CROSS: &str = "tk_cross";
+
+pub const TEC_ICHIMOKU: &str = "ichimoku";
+
+pub const DEFINITION: IndicatorDefinition = IndicatorDefinition {
+    name: TEC_ICHIMOKU,
+    params: &[("tenkan", 9), ("kijun", 26), ("senkou", 52)],
+    outputs: &[
+        IndicatorOutput {
+            name: IND_TENKAN,
+            script_opt: None,
+            color: (0, 96, 255),
+            stream_opt: None,
+        },
+        IndicatorOutput {
+            name: IND_KIJUN,
+            script_opt: None,
+            color: (128, 0, 0),
+            stream_opt: None,
+        },
+        IndicatorOutput {
+            name: IND_SENKOU_A,
+            script_opt: None,
+            color: (16, 196, 64),
+            stream_opt: None,
+        },
+        IndicatorOutput {
+            name: IND_SENKOU_B,
+            script_opt: None,
+            color: (164, 16, 64),
+            stream_opt: None,
+        },
+        IndicatorOutput {
+            name: IND_CHIKOU,
+            script_opt: None,
+            color: (96, 96, 96),
+            stream_opt: None,
+        },
+        IndicatorOutput {
+            name: IND_CLOUD,
+            script_opt: None,
+            color: (0, 0, 0),
+            stream_opt: None,
+        },
+        IndicatorOutput {
+            name: IND_TK_CROSS,
+            script_opt: None,
+            color: (0, 0, 0),
+            stream_opt: None,
+        },
+    ],
+    plot_style: None,
+    lookback,
+    build,
+};
+
+// Cloud under current candle was projected kijun candles ago
+fn lookback(params: &[usize]) -> usize {
+    params[1] + params[2]
+}
+
+fn build(candles: &[Candle], params: &[usize]) -> Box<dyn TecSerieIndicators + Send + Sync> {
+    Box::new(IchimokuTec::new(candles, params[0], params[1], params[2]))
+}
+
+pub struct IchimokuTec {
+    pub indicators: HashMap<String, SerieIndicator>,
+    pub signals: HashMap<String, ValueIndicator>,
+}
+
+impl TechnicalDefinition for IchimokuTec {
+    fn definition() -> TacDefinition {
+        DEFINITION.tac_definition()
+    }
+}
+
+impl TechnicalIndicators for IchimokuTec {
+    fn get_indicator(&self, name: &str) -> Option<&dyn Indicator> {
+        self.indicators
+            .get(name)
+            .map(|s| s as &dyn Indicator)
+            .or_else(|| self.signals.get(name).map(|s| s as &dyn Indicator))
+    }
+
+    fn main_indicator(&self) -> &dyn Indicator {
+        self.indicators.get(IND_KIJUN).unwrap() as &dyn Indicator
+    }
+
+    fn name(&self) -> String {
+        TEC_ICHIMOKU.to_string()
+    }
+}
+
+impl TecSerieIndicators for IchimokuTec {
+    fn serie_indicators(&self) -> &HashMap<String, SerieIndicator> {
+        &self.indicators
+    }
+
+    fn name(&self) -> String {
+        TEC_ICHIMOKU.to_string()
+    }
+
+    fn output(&self, name: &str) -> Option<&dyn Indicator> {
+        self.get_indicator(name)
+    }
+}
+
+impl IchimokuTec {
+    /// Senkou spans are displaced `kijun_period` candles forward, beyond the last candle,
+    /// and chikou is displaced `kijun_period` candles back
+    pub fn new(
+        candles: &[Candle],
+        tenkan_period: usize,
+        kijun_period: usize,
+        senkou_period: usize,
+    ) -> Self {
+        let tenkans = mid_points(candles, tenkan_period);
+        let kijuns = mid_points(candles, kijun_period);
+        let senkous_b = mid_points(candles, senkou_period);
+        let senkous_a = tenkans
+            .iter()
+            .zip(kijuns.iter())
+            .map(|(t, k)| (t + k) / 2.)
+            .collect::<Vec<_>>();
+
+        let mut tenkan_series = Vec::with_capacity(candles.len());
+        let mut kijun_series = Vec::with_capacity(candles.len());
+        let mut senkou_a_series = Vec::with_capacity(candles.len());
+        let mut senkou_b_series = Vec::with_capacity(candles.len());
+        let mut chikou_series = Vec::with_capacity(candles.len());
+
+        for (i, candle) in candles.iter().enumerate() {
+            tenkan_series.push(Serie::new(candle.close_time, tenkans[i]));
+            kijun_series.push(Serie::new(candle.close_time, kijuns[i]));
+
+            let displaced_time = match candles.get(i + kijun_period) {
+                Some(future) => future.close_time,
+                None => {
+                    let forward = (i + kijun_period + 1 - candles.len()) as i64;
+                    candles.last().unwrap().close_time
+                        + Duration::minutes(candle.minutes as i64 * forward)
+                }
+            };
+            senkou_a_series.push(Serie::new(displaced_time, senkous_a[i]));
+            senkou_b_series.push(Serie::new(displaced_time, senkous_b[i]));
+
+            if i >= kijun_period {
+                chikou_series.push(Serie::new(
+                    candles[i - kijun_period].close_time,
+                    candle.close.to_f64().unwrap(),
+                ));
+            }
+        }
+
+        let mut indicators = HashMap::new();
+        let mut insert = |name: &str, series| {
+            indicators.insert(name.to_string(), SerieIndicator::from(name, series));
+        };
+        insert(IND_TENKAN, tenkan_series);
+        insert(IND_KIJUN, kijun_series);
+        insert(IND_SENKOU_A, senkou_a_series);
+        insert(IND_SENKOU_B, senkou_b_series);
+        insert(IND_CHIKOU, chikou_series);
+
+        let mut signals = HashMap::new();
+        let last = candles.len().checked_sub(1);
+
+        // Cloud under last candle was projected kijun_period candles ago
+        if let Some(projected) = last.and_then(|l| l.checked_sub(kijun_period)) {
+            let close = candles[last.unwrap()].close.to_f64().unwrap();
+            let top = senkous_a[projected].max(senkous_b[projected]);
+            let bottom = senkous_a[projected].min(senkous_b[projected]);
+            let cloud = if close > top {
+                1.
+            } else if close < bottom {
+                -1.
+            } else {
+                0.
+            };
+            signals.insert(IND_CLOUD.to_string(), ValueIndicator::new(cloud));
+        }
+
+        if let Some(last) = last.filter(|l| *l > 0) {
+            let tk_cross = if tenkans[last - 1] <= kijuns[last - 1] && tenkans[last] > kijuns[last]
+            {
+                1.
+            } else if tenkans[last - 1] >= kijuns[last - 1] && tenkans[last] < kijuns[last] {
+                -1.
+            } else {
+                0.
+            };
+            signals.insert(IND_TK_CROSS.to_string(), ValueIndicator::new(tk_cross));
+        }
+
+        Self {
+            indicators,
+            signals,
+        }
+    }
+
+    pub fn serie_indicator(&self, name: &str) -> &SerieIndicator {
+        self.indicators.get(name).unwrap()
+    }
+}
+
+/// Middle point between highest high and lowest low of last `period` candles
+fn mid_points(candles: &[Candle], period: usize) -> Vec<f64> {
+    (0..candles.len())
+        .map(|i| {
+            let window = &candles[(i + 1).saturating_sub(period)..=i];
+            let high = window.iter().map(|c| c.high).max().unwrap();
+            let low = window.iter().map(|c| c.low).min().unwrap();
+            ((high + low) / dec!(2)).to_f64().unwrap()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::utils::candles_utils::tests::candle_at;
+    use rust_decimal::Decimal;
+
+    fn candles(closes: &[i64]) -> Vec<Candle> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, close)| {
+                let (high, low) = (Decimal::from(*close + 1), Decimal::from(*close - 1));
+                let close = Decimal::from(*close);
+                candle_at(i as i64, close, high, low, close, Decimal::from(1))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ichimoku_displacement_test() {
+        let candles = candles(&[10, 11, 12, 13, 14, 15]);
+        let ichimoku = IchimokuTec::new(&candles, 2, 3, 4);
+
+        let last_close_time = candles.last().unwrap().close_time;
+        let senkou_a = &ichimoku.serie_indicator(IND_SENKOU_A).series;
+        assert_eq!(senkou_a.len(), candles.len());
+        assert_eq!(senkou_a[0].date_time, candles[3].close_time);
+        assert_eq!(
+            senkou_a.last().unwrap().date_time,
+            last_close_time + Duration::minutes(45)
+        );
+
+        // Tenkan of last candle is (15 + 1 + 14 - 1) / 2, kijun is (15 + 1 + 13 - 1) / 2
+        assert_eq!(ichimoku.serie_indicator(IND_TENKAN).value().unwrap(), 14.5);
+        assert_eq!(ichimoku.serie_indicator(IND_KIJUN).value().unwrap(), 14.);
+        assert_eq!(senkou_a.last().unwrap().value, 14.25);
+
+        let chikou = &ichimoku.serie_indicator(IND_CHIKOU).series;
+        assert_eq!(chikou.len(), 3);
+        assert_eq!(chikou[0].date_time, candles[0].close_time);
+        assert_eq!(chikou[0].value, 13.);
+    }
+
+    #[test]
+    fn ichimoku_signals_test() {
+        let rising = candles(&[10, 11, 12, 13, 14, 15]);
+        let ichimoku = IchimokuTec::new(&rising, 2, 3, 4);
+        assert_eq!(
+            ichimoku.get_indicator(IND_CLOUD).unwrap().value().unwrap(),
+            1.
+        );
+
+        let falling = candles(&[15, 14, 13, 12, 11, 10]);
+        let ichimoku = IchimokuTec::new(&falling, 2, 3, 4);
+        assert_eq!(
+            ichimoku.get_indicator(IND_CLOUD).unwrap().value().unwrap(),
+            -1.
+        );
+
+        let crossing = candles(&[20, 18, 16, 12, 14, 22]);
+        let ichimoku = IchimokuTec::new(&crossing, 2, 3, 4);
+        assert_eq!(
+            ichimoku
+                .get_indicator(IND_TK_CROSS)
+                .unwrap()
+                .value()
+                .unwrap(),
+            1.
+        );
+    }
+}
@@ -1,157 +1,99 @@
+use super::ind_registry::MAX_PARAMS;
 use super::indicator::Indicator;
-use super::min_max_tec::MinMaxTec;
-use super::min_max_tec::IND_MAX;
-use super::min_max_tec::IND_MIN;
-use super::{
-    ema_tec::{EmaTec, IND_EMA},
-    ind_type::IndicatorType,
-    macd_tec::{MacdTec, IND_MACD, IND_MACD_DIV, IND_MACD_SIG},
-    rsi_tec::{RsiTec, IND_RSI},
-    sma_tec::{SmaTec, IND_SMA},
-    technical::TechnicalIndicators,
-};
+use super::serie_indicator::SerieIndicator;
+use super::{ind_type::IndicatorType, technical::TecSerieIndicators};
+use crate::config::candle_type::CandleType;
 use crate::model::candle::Candle;
 use chrono::{DateTime, Utc};
 use eyre::eyre;
 use std::collections::HashMap;
 
+/// Timeframe, candle type, registered indicator name and params of a technical
+type TecKey = (i32, CandleType, &'static str, [usize; MAX_PARAMS]);
+
+/// Script indicator with the close time of the last candle it was computed from
+type ScriptSerie = (Option<DateTime<Utc>>, SerieIndicator);
+
 pub struct IndicatorProvider {
-    macds_tec_opt: Option<(DateTime<Utc>, usize, usize, usize, MacdTec)>,
-    min_max_tec_opt: Option<(DateTime<Utc>, usize, MinMaxTec)>,
-    tec_indicators:
-        HashMap<(String, usize), eyre::Result<Box<dyn TechnicalIndicators + Send + Sync>>>, // <= to allow trait with different lifetime
+    // Last technical computed of each key, with the time it was computed at
+    tecs: HashMap<TecKey, (DateTime<Utc>, Box<dyn TecSerieIndicators + Send + Sync>)>,
+    script_series: HashMap<(i32, String), ScriptSerie>,
 }
 
 impl IndicatorProvider {
     pub fn new() -> Self {
         Self {
-            macds_tec_opt: None,
-            min_max_tec_opt: None,
-            tec_indicators: HashMap::new(),
+            tecs: HashMap::new(),
+            script_series: HashMap::new(),
         }
     }
 
-    fn tec_indicator(
-        &mut self,
-        candles: &[Candle],
-        ind_name: &str,
-        period: usize,
-    ) -> eyre::Result<&dyn Indicator> {
-        self.tec_indicators.clear();
-        let result: &mut eyre::Result<Box<dyn TechnicalIndicators + Send + Sync>> = self
-            .tec_indicators
-            .entry((ind_name.to_string(), period))
-            .or_insert_with(|| {
-                let result: eyre::Result<Box<dyn TechnicalIndicators + Send + Sync>> =
-                    match ind_name {
-                        IND_EMA => Ok(Box::new(EmaTec::new(candles, period))
-                            as Box<dyn TechnicalIndicators + Send + Sync>),
-                        IND_SMA => Ok(Box::new(SmaTec::new(candles, period))
-                            as Box<dyn TechnicalIndicators + Send + Sync>),
-                        IND_RSI => Ok(Box::new(RsiTec::new(candles, period))
-                            as Box<dyn TechnicalIndicators + Send + Sync>),
-                        other => Err(eyre!("Not found indicator {}!", other)),
-                    };
-                result
-            });
-        let tac = match result {
-            Ok(tac) => tac,
-            Err(e) => return Err(eyre!("{}", e)),
-        };
-        Ok(tac.main_indicator())
+    /// Close time of the last candle the cached script indicator was computed from
+    pub fn script_serie_close_time(
+        &self,
+        minutes: i32,
+        name: &str,
+    ) -> Option<Option<DateTime<Utc>>> {
+        self.script_series
+            .get(&(minutes, name.to_string()))
+            .map(|e| e.0)
     }
 
-    fn min_max_indicator(
+    pub fn set_script_serie(
         &mut self,
-        now: DateTime<Utc>,
-        candles: &[Candle],
-        ind_name: &str,
-        period: usize,
-    ) -> eyre::Result<&dyn Indicator> {
-        self.min_max_tec_opt = self
-            .min_max_tec_opt
-            .take()
-            .filter(|e| e.0 == now && e.1 == period);
-        let macd = self
-            .min_max_tec_opt
-            .get_or_insert_with(|| (now, period, MinMaxTec::new(candles, period)));
-        let result = macd
-            .2
-            .get_indicator(ind_name)
-            .ok_or_else(|| -> eyre::Error { eyre!("Not found indicator {}!", ind_name) });
-        result
+        minutes: i32,
+        close_time_opt: Option<DateTime<Utc>>,
+        serie: SerieIndicator,
+    ) {
+        self.script_series
+            .insert((minutes, serie.name.clone()), (close_time_opt, serie));
     }
 
-    fn macd_indicator(
-        &mut self,
+    /// Script indicator values of last `len` candles closed until now, oldest first
+    pub fn script_history(
+        &self,
+        minutes: i32,
+        name: &str,
         now: DateTime<Utc>,
-        candles: &[Candle],
-        ind_name: &str,
-        fast_period: usize,
-        slow_period: usize,
-        signal_period: usize,
-    ) -> eyre::Result<&dyn Indicator> {
-        // Try to reuse the same triple macd/signal/divergence
-        self.macds_tec_opt = self.macds_tec_opt.take().filter(|e| {
-            e.0 == now && e.1 == fast_period && e.2 == slow_period && e.3 == signal_period
-        });
-        let macd = self.macds_tec_opt.get_or_insert_with(|| {
-            (
-                now,
-                fast_period,
-                slow_period,
-                signal_period,
-                MacdTec::new(candles, fast_period, slow_period, signal_period),
-            )
-        });
-        let result = macd
-            .4
-            .get_indicator(ind_name)
-            .ok_or_else(|| -> eyre::Error { eyre!("Not found indicator {}!", ind_name) });
-        result
+        len: usize,
+    ) -> eyre::Result<Vec<f64>> {
+        let (_, serie) = self
+            .script_series
+            .get(&(minutes, name.to_string()))
+            .ok_or_else(|| eyre!("Not found script indicator {}!", name))?;
+        let closed = serie.series.partition_point(|s| s.date_time <= now);
+        Ok(serie.series[closed.saturating_sub(len)..closed]
+            .iter()
+            .map(|s| s.value)
+            .collect())
     }
 
+    /// Indicator computed by its registered technical from the candles of the timeframe, the
+    /// technical is reused by the other outputs of the same params until now changes
     pub fn indicator(
         &mut self,
         now: DateTime<Utc>,
+        minutes: i32,
+        candle_type: CandleType,
         candles: &[Candle],
         indicator_type: &IndicatorType,
     ) -> eyre::Result<&dyn Indicator> {
-        let indicator = match indicator_type {
-            IndicatorType::Min(period) => self.min_max_indicator(now, candles, IND_MIN, *period)?,
-            IndicatorType::Max(period) => self.min_max_indicator(now, candles, IND_MAX, *period)?,
-            IndicatorType::Macd(fast_period, slow_period, signal_period) => self.macd_indicator(
-                now,
-                candles,
-                IND_MACD,
-                *fast_period,
-                *slow_period,
-                *signal_period,
-            )?,
-            IndicatorType::MacdSignal(fast_period, slow_period, signal_period) => self
-                .macd_indicator(
-                    now,
-                    candles,
-                    IND_MACD_SIG,
-                    *fast_period,
-                    *slow_period,
-                    *signal_period,
-                )?,
-            IndicatorType::MacdDivergence(fast_period, slow_period, signal_period) => self
-                .macd_indicator(
-                    now,
-                    candles,
-                    IND_MACD_DIV,
-                    *fast_period,
-                    *slow_period,
-                    *signal_period,
-                )?,
-            IndicatorType::Ema(period) => self.tec_indicator(candles, IND_EMA, *period)?,
-            IndicatorType::Sma(period) => self.tec_indicator(candles, IND_SMA, *period)?,
-            IndicatorType::Rsi(period) => self.tec_indicator(candles, IND_RSI, *period)?,
-            //IndicatorType::TopBottom(period) => self.tec_indicator(candles, TOP_BOTTOM_IND, *period)?,
-        };
-        Ok(indicator)
+        let (definition, output, params) = indicator_type
+            .registered()
+            .ok_or_else(|| eyre!("Not registered indicator {:?}!", indicator_type))?;
+        let (computed_at, tec) = self
+            .tecs
+            .entry((minutes, candle_type, definition.name, params))
+            .or_insert_with(|| {
+                let tec = (definition.build)(candles, &params[..definition.params.len()]);
+                (now, tec)
+            });
+        if *computed_at != now {
+            *tec = (definition.build)(candles, &params[..definition.params.len()]);
+            *computed_at = now;
+        }
+        tec.output(output)
+            .ok_or_else(|| eyre!("Not found indicator {}!", output))
     }
 }
 
@@ -160,3 +102,37 @@ impl Default for IndicatorProvider {
         Self::new()
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::services::technicals::sma_tec::{self, IND_SMA};
+    use crate::utils::candles_utils::tests::candle_at;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn indicator_cache_test() {
+        let candles = (0..30)
+            .map(|i| {
+                let close = Decimal::from(100 + i);
+                candle_at(i, close, close, close, close, Decimal::from(1))
+            })
+            .collect::<Vec<_>>();
+        let now = candles[29].close_time;
+        let sma = sma_tec::DEFINITION.indicator_type(IND_SMA, &[3]);
+        let mut provider = IndicatorProvider::new();
+        let mut value = |minutes, candles: &[Candle]| {
+            provider
+                .indicator(now, minutes, CandleType::default(), candles, &sma)
+                .unwrap()
+                .value()
+                .unwrap()
+        };
+
+        assert_eq!(value(15, &candles), 128.);
+        // Another timeframe at the same time is computed from its own candles
+        assert_eq!(value(60, &candles[..10]), 108.);
+        // Computed once until now changes
+        assert_eq!(value(15, &candles[..10]), 128.);
+    }
+}
@@ -0,0 +1,127 @@
+use super::ind_stream::NextFn;
+use super::ind_type::IndicatorType;
+use super::technical::TecSerieIndicators;
+use super::{
+    ad_tec, bollinger_tec, cmf_tec, ema_tec, ichimoku_tec, macd_tec, mfi_tec, min_max_tec, obv_tec,
+    rsi_tec, sma_tec, volume_tec,
+};
+use crate::config::definition::TacDefinition;
+use crate::model::candle::Candle;
+
+/// Max number of parameters of a registered indicator
+pub const MAX_PARAMS: usize = 3;
+
+/// Registered indicators, exposed to scripts, selection tacs and plotter, a new indicator is a
+/// module with its definition listed here
+pub static REGISTRY: &[IndicatorDefinition] = &[
+    ema_tec::DEFINITION,
+    sma_tec::DEFINITION,
+    rsi_tec::DEFINITION,
+    macd_tec::DEFINITION,
+    min_max_tec::DEFINITION,
+    obv_tec::DEFINITION,
+    ad_tec::DEFINITION,
+    mfi_tec::DEFINITION,
+    cmf_tec::DEFINITION,
+    volume_tec::DEFINITION,
+    ichimoku_tec::DEFINITION,
+    bollinger_tec::DEFINITION,
+];
+
+/// Where indicator series are plotted by default
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlotStyle {
+    /// Lines over the candles
+    Upper,
+    /// Lines in a panel under the candles
+    Lower,
+}
+
+/// Next value function of a streamed output from params
+pub type StreamFn = fn(&[usize]) -> eyre::Result<NextFn>;
+
+pub struct IndicatorOutput {
+    pub name: &'static str,
+    /// Script function name, none when the output is not exposed to scripts
+    pub script_opt: Option<&'static str>,
+    pub color: (u8, u8, u8),
+    /// Updated with each closed candle, with its history readable by scripts, none when computed
+    /// from the candles window
+    pub stream_opt: Option<StreamFn>,
+}
+
+/// Everything needed to compute, script and plot an indicator
+///
+/// Each output with a script name is a script function called with the minutes followed by
+/// the params, e.g. `bb_upper(15, 20, 2)`
+pub struct IndicatorDefinition {
+    pub name: &'static str,
+    /// Param names with default values used for plotting
+    pub params: &'static [(&'static str, usize)],
+    pub outputs: &'static [IndicatorOutput],
+    /// Plotted with default params when selected, none when plotted by its own plotter or
+    /// only computed for scripts
+    pub plot_style: Option<PlotStyle>,
+    /// Candles needed to compute the last value from params
+    pub lookback: fn(&[usize]) -> usize,
+    pub build: fn(&[Candle], &[usize]) -> Box<dyn TecSerieIndicators + Send + Sync>,
+}
+
+impl IndicatorDefinition {
+    pub fn tac_definition(&self) -> TacDefinition {
+        let indicators = self.outputs.iter().map(|o| o.name).collect::<Vec<_>>();
+        TacDefinition::new(self.name, &indicators)
+    }
+
+    pub fn default_params(&self) -> Vec<usize> {
+        self.params.iter().map(|p| p.1).collect()
+    }
+
+    pub fn indicator_type(&self, output: &'static str, params: &[usize]) -> IndicatorType {
+        IndicatorType::new(self.name, output, params)
+    }
+}
+
+pub fn definition(name: &str) -> Option<&'static IndicatorDefinition> {
+    REGISTRY.iter().find(|d| d.name == name)
+}
+
+/// Lookback of indicators computed over the last candles of their first param
+pub fn first_param(params: &[usize]) -> usize {
+    params[0]
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn registry_test() {
+        let mut names = HashSet::new();
+        let mut scripts = HashSet::new();
+        for definition in REGISTRY.iter() {
+            assert!((1..=MAX_PARAMS).contains(&definition.params.len()));
+            assert!((definition.lookback)(&definition.default_params()) > 0);
+            assert!(names.insert(definition.name));
+            for output in definition.outputs.iter() {
+                // Script outputs are function names, so must be unique
+                if let Some(script) = output.script_opt {
+                    assert!(scripts.insert(script), "{} duplicated", script);
+                }
+
+                // Typed indicators are computed by the definition they are read from
+                let indicator_type =
+                    definition.indicator_type(output.name, &definition.default_params());
+                let (registered, registered_output, _) = indicator_type.registered().unwrap();
+                assert_eq!(
+                    (registered.name, registered_output),
+                    (definition.name, output.name)
+                );
+                if let Some(stream) = output.stream_opt {
+                    assert!(stream(&definition.default_params()).is_ok());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,474 @@
+use super::brick_candles::BrickStream;
+use super::heikin_ashi::HeikinAshiStream;
+use super::ind_type::IndicatorType;
+use super::indicator::Indicator;
+use super::swing_stream::{SwingSerie, SwingStream};
+use super::top_bottom::TopBottom;
+use crate::config::candle_type::CandleType;
+use crate::model::candle::Candle;
+use chrono::{DateTime, Utc};
+use eyre::eyre;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::collections::{HashMap, VecDeque};
+
+/// Values kept by each streamed indicator for scripts to read previous candles
+pub const HISTORY_LEN: usize = 500;
+
+/// Next value of a streamed indicator from the next closed candle
+pub type NextFn = Box<dyn FnMut(&Candle) -> f64 + Send + Sync>;
+
+/// Indicator state updated with each new closed candle, its value is the same as the last
+/// value of the batch technical computed over all candles streamed so far
+pub struct StreamIndicator {
+    next: NextFn,
+    history: VecDeque<f64>,
+}
+
+impl Indicator for StreamIndicator {
+    fn value(&self) -> eyre::Result<f64> {
+        self.history
+            .back()
+            .copied()
+            .ok_or_else(|| eyre!("No candle streamed!"))
+    }
+
+    fn min_max(&self) -> (f64, f64) {
+        let value = self.history.back().copied().unwrap_or_default();
+        (value, value)
+    }
+}
+
+impl StreamIndicator {
+    /// Stream of an output registered with a stream function
+    pub fn new(indicator_type: &IndicatorType) -> eyre::Result<Self> {
+        let stream = indicator_type
+            .stream_opt()
+            .ok_or_else(|| eyre!("Indicator {:?} is not streamed!", indicator_type))?;
+        let next = stream(indicator_type.params())
+            .map_err(|_| eyre!("Invalid periods for {:?}!", indicator_type))?;
+        Ok(Self {
+            next,
+            history: VecDeque::with_capacity(HISTORY_LEN + 1),
+        })
+    }
+
+    /// Indicators with a streaming implementation, others are computed from candles window
+    pub fn is_streamed(indicator_type: &IndicatorType) -> bool {
+        indicator_type.stream_opt().is_some()
+    }
+
+    pub fn next(&mut self, candle: &Candle) {
+        push_window(&mut self.history, HISTORY_LEN, (self.next)(candle));
+    }
+
+    /// Last `len` values, oldest first, fewer while there are not enough candles streamed
+    pub fn history(&self, len: usize) -> Vec<f64> {
+        let skip = self.history.len().saturating_sub(len);
+        self.history.iter().skip(skip).copied().collect()
+    }
+}
+
+/// Closed candles of a timeframe shared by all indicators streamed from it, candles older than
+/// the history are dropped once streamed, so an indicator read for the first time later starts
+/// from the kept ones
+pub struct IndicatorStreams {
+    transform: CandleTransform,
+    last_source_opt: Option<Candle>,
+    candles: Vec<Candle>,
+    closed: usize,
+    streams: HashMap<IndicatorType, (usize, StreamIndicator)>,
+    swing_streams: HashMap<usize, (usize, SwingStream)>,
+}
+
+/// Candle type made from the exchange candles, with the state carried between pushes
+enum CandleTransform {
+    Raw,
+    HeikinAshi(HeikinAshiStream),
+    Bricks(BrickStream),
+}
+
+impl IndicatorStreams {
+    pub fn new() -> Self {
+        Self::with_candle_type(CandleType::Raw)
+    }
+
+    pub fn with_candle_type(candle_type: CandleType) -> Self {
+        let transform = match candle_type {
+            CandleType::Raw => CandleTransform::Raw,
+            CandleType::HeikinAshi => CandleTransform::HeikinAshi(HeikinAshiStream::default()),
+            CandleType::Renko(brick_size) => {
+                CandleTransform::Bricks(BrickStream::renko(brick_size))
+            }
+            CandleType::RenkoAtr(period) => CandleTransform::Bricks(BrickStream::renko_atr(period)),
+            CandleType::RangeBar(range) => CandleTransform::Bricks(BrickStream::range_bars(range)),
+            CandleType::LineBreak(lines) => CandleTransform::Bricks(BrickStream::line_break(lines)),
+        };
+        Self {
+            transform,
+            last_source_opt: None,
+            candles: Vec::new(),
+            closed: 0,
+            streams: HashMap::new(),
+            swing_streams: HashMap::new(),
+        }
+    }
+
+    /// Last exchange candle pushed, bricks made from it can close before it
+    pub fn last_source_candle(&self) -> Option<&Candle> {
+        self.last_source_opt.as_ref()
+    }
+
+    /// Last `len` candles closed until now, oldest first
+    pub fn closed_candles(&mut self, now: DateTime<Utc>, len: usize) -> &[Candle] {
+        self.close_until(now);
+        let closed = self.candles.partition_point(|c| c.close_time <= now);
+        &self.candles[closed.saturating_sub(len)..closed]
+    }
+
+    /// Appends the candles of the candle type made from the exchange candles after the last one,
+    /// candles can close after now and are streamed only once closed
+    pub fn push_candles(&mut self, sources: Vec<Candle>) {
+        let last_open_time = self.last_source_opt.map(|c| c.open_time);
+        for source in sources
+            .into_iter()
+            .filter(|c| last_open_time.map(|l| c.open_time > l).unwrap_or(true))
+        {
+            match &mut self.transform {
+                CandleTransform::Raw => self.candles.push(source),
+                CandleTransform::HeikinAshi(heikin_ashi) => {
+                    self.candles.push(heikin_ashi.next(&source))
+                }
+                CandleTransform::Bricks(bricks) => self.candles.extend(bricks.next(&source)),
+            }
+            self.last_source_opt = Some(source);
+        }
+    }
+
+    pub fn indicator(
+        &mut self,
+        now: DateTime<Utc>,
+        indicator_type: &IndicatorType,
+    ) -> eyre::Result<&dyn Indicator> {
+        Ok(self.stream(now, indicator_type)?)
+    }
+
+    /// Last `len` values until now, oldest first
+    pub fn history(
+        &mut self,
+        now: DateTime<Utc>,
+        indicator_type: &IndicatorType,
+        len: usize,
+    ) -> eyre::Result<Vec<f64>> {
+        Ok(self.stream(now, indicator_type)?.history(len))
+    }
+
+    /// Last `len` swings confirmed until now with `neighbors` candles each side, oldest first
+    pub fn swings(
+        &mut self,
+        now: DateTime<Utc>,
+        neighbors: usize,
+        swing_serie: SwingSerie,
+        len: usize,
+    ) -> Vec<TopBottom> {
+        self.close_until(now);
+        self.swing_streams
+            .entry(neighbors)
+            .or_insert_with(|| (0, SwingStream::new(neighbors)));
+        self.feed_streams();
+        self.swing_streams[&neighbors].1.swings(swing_serie, len)
+    }
+
+    /// Closes the candles until now, and drops the candles before the history once there are
+    /// twice as many
+    fn close_until(&mut self, now: DateTime<Utc>) {
+        while self.closed < self.candles.len() && self.candles[self.closed].close_time <= now {
+            self.closed += 1;
+        }
+
+        if self.closed >= 2 * HISTORY_LEN {
+            self.feed_streams();
+            let dropped = self.closed - HISTORY_LEN;
+            self.candles.drain(..dropped);
+            self.closed -= dropped;
+            self.streams
+                .values_mut()
+                .for_each(|(fed, _)| *fed -= dropped);
+            self.swing_streams
+                .values_mut()
+                .for_each(|(fed, _)| *fed -= dropped);
+        }
+    }
+
+    /// Feeds the closed candles to every stream
+    fn feed_streams(&mut self) {
+        let closed = &self.candles[..self.closed];
+        for (fed, stream) in self.streams.values_mut() {
+            closed[*fed..].iter().for_each(|c| stream.next(c));
+            *fed = closed.len();
+        }
+        for (fed, swing_stream) in self.swing_streams.values_mut() {
+            closed[*fed..].iter().for_each(|c| swing_stream.next(c));
+            *fed = closed.len();
+        }
+    }
+
+    /// Streams candles closed until now, a new indicator is fed from the first kept candle
+    fn stream(
+        &mut self,
+        now: DateTime<Utc>,
+        indicator_type: &IndicatorType,
+    ) -> eyre::Result<&StreamIndicator> {
+        self.close_until(now);
+
+        if !self.streams.contains_key(indicator_type) {
+            let stream = StreamIndicator::new(indicator_type)?;
+            self.streams.insert(*indicator_type, (0, stream));
+        }
+        self.feed_streams();
+        Ok(&self.streams[indicator_type].1)
+    }
+}
+
+impl Default for IndicatorStreams {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Min or max of last `period` values, in amortized constant time
+pub struct RollingExtreme {
+    period: usize,
+    count: usize,
+    keep: fn(&Decimal, &Decimal) -> bool,
+    candidates: VecDeque<(usize, Decimal)>,
+}
+
+impl RollingExtreme {
+    pub fn new(period: usize, keep: fn(&Decimal, &Decimal) -> bool) -> Self {
+        Self {
+            period,
+            count: 0,
+            keep,
+            candidates: VecDeque::new(),
+        }
+    }
+
+    pub fn next(&mut self, value: Decimal) -> f64 {
+        while let Some((_, last)) = self.candidates.back() {
+            if (self.keep)(last, &value) {
+                break;
+            }
+            self.candidates.pop_back();
+        }
+        self.candidates.push_back((self.count, value));
+        self.count += 1;
+        while let Some((index, _)) = self.candidates.front() {
+            if index + self.period.max(1) > self.count - 1 {
+                break;
+            }
+            self.candidates.pop_front();
+        }
+        self.candidates.front().unwrap().1.to_f64().unwrap()
+    }
+}
+
+pub fn push_window<T>(window: &mut VecDeque<T>, period: usize, value: T) {
+    window.push_back(value);
+    if window.len() > period.max(1) {
+        window.pop_front();
+    }
+}
+
+/// Error of a stream built with invalid params
+pub fn invalid_periods<E>(_: E) -> eyre::Report {
+    eyre!("Invalid periods!")
+}
+
+pub fn close(candle: &Candle) -> f64 {
+    candle.close.to_f64().unwrap()
+}
+
+pub fn close_volume(candle: &Candle) -> f64 {
+    candle.volume.to_f64().unwrap()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::services::technicals::brick_candles::{line_break, range_bars, renko, renko_atr};
+    use crate::services::technicals::cmf_tec::{self, IND_CMF};
+    use crate::services::technicals::ema_tec::{self, IND_EMA};
+    use crate::services::technicals::heikin_ashi::heikin_ashi;
+    use crate::services::technicals::ichimoku_tec::{self, IND_CLOUD};
+    use crate::services::technicals::macd_tec::{self, IND_MACD_SIG};
+    use crate::services::technicals::mfi_tec::{self, IND_MFI};
+    use crate::services::technicals::min_max_tec::{self, IND_MAX, IND_MIN};
+    use crate::services::technicals::obv_tec::{self, IND_OBV};
+    use crate::services::technicals::rsi_tec::{self, IND_RSI};
+    use crate::utils::candles_utils::tests::candle_at;
+    use rust_decimal_macros::dec;
+
+    fn candles(count: i64) -> Vec<Candle> {
+        (0..count)
+            .map(|i| {
+                let close = Decimal::from(100 + (i * 7) % 13) - Decimal::from(i % 5) / dec!(4);
+                candle_at(
+                    i,
+                    close - dec!(1),
+                    close + Decimal::from(i % 3),
+                    close - Decimal::from(1 + i % 4),
+                    close,
+                    Decimal::from(10 + (i * 11) % 17),
+                )
+            })
+            .collect()
+    }
+
+    fn batch_value(candles: &[Candle], indicator_type: &IndicatorType) -> f64 {
+        let (definition, output, _) = indicator_type.registered().unwrap();
+        let tec = (definition.build)(candles, indicator_type.params());
+        tec.output(output).unwrap().value().unwrap()
+    }
+
+    #[test]
+    fn stream_equals_batch_test() {
+        let candles = candles(60);
+        let indicator_types = [
+            ema_tec::DEFINITION.indicator_type(IND_EMA, &[9]),
+            rsi_tec::DEFINITION.indicator_type(IND_RSI, &[14]),
+            macd_tec::DEFINITION.indicator_type(IND_MACD_SIG, &[5, 12, 4]),
+            min_max_tec::DEFINITION.indicator_type(IND_MIN, &[7]),
+            min_max_tec::DEFINITION.indicator_type(IND_MAX, &[7]),
+            obv_tec::DEFINITION.indicator_type(IND_OBV, &[1]),
+            cmf_tec::DEFINITION.indicator_type(IND_CMF, &[10]),
+            mfi_tec::DEFINITION.indicator_type(IND_MFI, &[10]),
+        ];
+        for indicator_type in indicator_types.iter() {
+            let mut stream = StreamIndicator::new(indicator_type).unwrap();
+            for (i, candle) in candles.iter().enumerate() {
+                stream.next(candle);
+                if i + 1 < indicator_type.period() as usize {
+                    continue;
+                }
+                assert_eq!(
+                    stream.value().unwrap().to_bits(),
+                    batch_value(&candles[..=i], indicator_type).to_bits(),
+                    "{:?} at candle {}",
+                    indicator_type,
+                    i
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn indicator_streams_test() {
+        let candles = candles(60);
+        let mut indicator_streams = IndicatorStreams::new();
+        indicator_streams.push_candles(candles[..40].to_vec());
+        // Overlapping candles are not streamed twice
+        indicator_streams.push_candles(candles[30..].to_vec());
+
+        let ema = ema_tec::DEFINITION.indicator_type(IND_EMA, &[9]);
+        let now = candles[19].close_time;
+        let value = indicator_streams
+            .indicator(now, &ema)
+            .unwrap()
+            .value()
+            .unwrap();
+        assert_eq!(value, batch_value(&candles[..20], &ema));
+
+        // Indicator created later is fed from the first candle
+        let now = candles[49].close_time;
+        let rsi = rsi_tec::DEFINITION.indicator_type(IND_RSI, &[14]);
+        let value = indicator_streams
+            .indicator(now, &rsi)
+            .unwrap()
+            .value()
+            .unwrap();
+        assert_eq!(value, batch_value(&candles[..50], &rsi));
+        let value = indicator_streams
+            .indicator(now, &ema)
+            .unwrap()
+            .value()
+            .unwrap();
+        assert_eq!(value, batch_value(&candles[..50], &ema));
+
+        // History of previous candles, oldest first
+        let history = indicator_streams.history(now, &ema, 3).unwrap();
+        let expected = (47..50)
+            .map(|i| batch_value(&candles[..=i], &ema))
+            .collect::<Vec<_>>();
+        assert_eq!(history, expected);
+
+        // Candles closing after now are not visible
+        let closed = indicator_streams.closed_candles(candles[44].close_time, 3);
+        assert_eq!(closed, &candles[42..45]);
+        assert_eq!(indicator_streams.closed_candles(now, 100).len(), 50);
+
+        assert!(StreamIndicator::new(
+            &ichimoku_tec::DEFINITION.indicator_type(IND_CLOUD, &[9, 26, 52])
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn indicator_streams_trim_test() {
+        let candles = candles(3 * HISTORY_LEN as i64);
+        let mut indicator_streams = IndicatorStreams::new();
+        indicator_streams.push_candles(candles.clone());
+
+        let ema = ema_tec::DEFINITION.indicator_type(IND_EMA, &[9]);
+        indicator_streams
+            .indicator(candles[0].close_time, &ema)
+            .unwrap();
+        let now = candles.last().unwrap().close_time;
+        let value = indicator_streams
+            .indicator(now, &ema)
+            .unwrap()
+            .value()
+            .unwrap();
+        // Candles dropped were streamed before
+        assert_eq!(value, batch_value(&candles, &ema));
+        assert!(indicator_streams.candles.len() < 2 * HISTORY_LEN);
+        let closed = indicator_streams.closed_candles(now, usize::MAX);
+        assert_eq!(closed.last(), candles.last());
+        assert!(closed.len() >= HISTORY_LEN);
+    }
+
+    #[test]
+    fn indicator_streams_candle_type_test() {
+        let candles = candles(60);
+        let candle_types = [
+            CandleType::HeikinAshi,
+            CandleType::Renko(dec!(2)),
+            CandleType::RenkoAtr(14),
+            CandleType::RangeBar(dec!(3)),
+            CandleType::LineBreak(3),
+        ];
+        for candle_type in candle_types.iter() {
+            let expected = match *candle_type {
+                CandleType::HeikinAshi => heikin_ashi(&candles.iter().collect::<Vec<_>>()),
+                CandleType::Renko(size) => renko(&candles, size),
+                CandleType::RenkoAtr(period) => renko_atr(&candles, period),
+                CandleType::RangeBar(range) => range_bars(&candles, range),
+                CandleType::LineBreak(lines) => line_break(&candles, lines),
+                CandleType::Raw => unreachable!(),
+            };
+
+            // Candles loaded in chunks continue the candles made from the previous ones
+            let mut indicator_streams = IndicatorStreams::with_candle_type(*candle_type);
+            indicator_streams.push_candles(candles[..7].to_vec());
+            indicator_streams.push_candles(candles[5..31].to_vec());
+            indicator_streams.push_candles(candles[31..].to_vec());
+            let now = candles.last().unwrap().close_time;
+            assert_eq!(
+                indicator_streams.closed_candles(now, usize::MAX),
+                &expected[..],
+                "{:?}",
+                candle_type
+            );
+        }
+    }
+}
@@ -1,27 +1,59 @@
-#[derive(PartialEq, Eq, Hash)]
-pub enum IndicatorType {
-    Macd(usize, usize, usize),
-    MacdSignal(usize, usize, usize),
-    MacdDivergence(usize, usize, usize),
-    Ema(usize),
-    Sma(usize),
-    Rsi(usize),
-    Min(usize),
-    Max(usize),
-    //TopBottom(usize),
+use super::ind_registry::{definition, IndicatorDefinition, StreamFn, MAX_PARAMS};
+
+/// Output and params of a registered indicator, unused params are zero
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct IndicatorType {
+    pub name: &'static str,
+    pub output: &'static str,
+    params: [usize; MAX_PARAMS],
 }
 
 impl IndicatorType {
-    pub fn period(&self) -> i32 {
-        match self {
-            IndicatorType::Macd(period, _, _) => *period as i32,
-            IndicatorType::MacdSignal(period, _, _) => *period as i32,
-            IndicatorType::MacdDivergence(period, _, _) => *period as i32,
-            IndicatorType::Ema(period) => *period as i32,
-            IndicatorType::Sma(period) => *period as i32,
-            IndicatorType::Rsi(period) => *period as i32,
-            IndicatorType::Min(period) => *period as i32,
-            IndicatorType::Max(period) => *period as i32,
+    pub fn new(name: &'static str, output: &'static str, params: &[usize]) -> Self {
+        let mut values = [0; MAX_PARAMS];
+        values
+            .iter_mut()
+            .zip(params.iter())
+            .for_each(|(v, p)| *v = *p);
+        Self {
+            name,
+            output,
+            params: values,
         }
     }
+
+    /// Registered definition, output and params computing the indicator
+    pub fn registered(
+        &self,
+    ) -> Option<(
+        &'static IndicatorDefinition,
+        &'static str,
+        [usize; MAX_PARAMS],
+    )> {
+        Some((definition(self.name)?, self.output, self.params))
+    }
+
+    /// Params of the definition
+    pub fn params(&self) -> &[usize] {
+        let len = definition(self.name)
+            .map(|d| d.params.len())
+            .unwrap_or(MAX_PARAMS);
+        &self.params[..len]
+    }
+
+    /// Stream function of the output, none when computed from the candles window
+    pub fn stream_opt(&self) -> Option<StreamFn> {
+        definition(self.name)?
+            .outputs
+            .iter()
+            .find(|o| o.name == self.output)?
+            .stream_opt
+    }
+
+    /// Candles needed to compute the last value
+    pub fn period(&self) -> i32 {
+        self.registered()
+            .map(|(d, _, _)| (d.lookback)(self.params()) as i32)
+            .unwrap_or_default()
+    }
 }
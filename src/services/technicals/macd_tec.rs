@@ -1,3 +1,5 @@
+use super::ind_registry::{first_param, IndicatorDefinition, IndicatorOutput};
+use super::ind_stream::{close, invalid_periods, NextFn};
 use crate::services::technicals::serie::Serie;
 use crate::{
     config::definition::TacDefinition,
@@ -19,14 +21,60 @@ pub const IND_MACD_DIV: &str = "divergence";
 
 pub const TEC_MCAD: &str = "macd";
 
+pub const DEFINITION: IndicatorDefinition = IndicatorDefinition {
+    name: TEC_MCAD,
+    params: &[("fast", 34), ("slow", 72), ("signal", 17)],
+    outputs: &[
+        IndicatorOutput {
+            name: IND_MACD,
+            script_opt: Some(IND_MACD),
+            color: (0, 0, 255),
+            stream_opt: Some(macd_stream),
+        },
+        IndicatorOutput {
+            name: IND_MACD_SIG,
+            script_opt: Some("macd_signal"),
+            color: (255, 0, 0),
+            stream_opt: Some(signal_stream),
+        },
+        IndicatorOutput {
+            name: IND_MACD_DIV,
+            script_opt: Some("macd_divergence"),
+            color: (0, 0, 0),
+            stream_opt: Some(divergence_stream),
+        },
+    ],
+    plot_style: None,
+    lookback: first_param,
+    build,
+};
+
+fn build(candles: &[Candle], params: &[usize]) -> Box<dyn TecSerieIndicators + Send + Sync> {
+    Box::new(MacdTec::new(candles, params[0], params[1], params[2]))
+}
+
+fn macd_stream(params: &[usize]) -> eyre::Result<NextFn> {
+    let mut macd = Macd::new(params[0], params[1], params[2]).map_err(invalid_periods)?;
+    Ok(Box::new(move |c| macd.next(close(c)).macd))
+}
+
+fn signal_stream(params: &[usize]) -> eyre::Result<NextFn> {
+    let mut macd = Macd::new(params[0], params[1], params[2]).map_err(invalid_periods)?;
+    Ok(Box::new(move |c| macd.next(close(c)).signal))
+}
+
+fn divergence_stream(params: &[usize]) -> eyre::Result<NextFn> {
+    let mut macd = Macd::new(params[0], params[1], params[2]).map_err(invalid_periods)?;
+    Ok(Box::new(move |c| macd.next(close(c)).histogram))
+}
+
 pub struct MacdTec {
     indicators: HashMap<String, SerieIndicator>,
 }
 
 impl TechnicalDefinition for MacdTec {
-    fn definition() -> crate::config::definition::TacDefinition {
-        let indicators = vec![IND_MACD, IND_MACD_SIG, IND_MACD_DIV];
-        TacDefinition::new(IND_MACD, &indicators)
+    fn definition() -> TacDefinition {
+        DEFINITION.tac_definition()
     }
 }
 
@@ -0,0 +1,179 @@
+use super::ind_registry::{IndicatorDefinition, IndicatorOutput};
+use super::ind_stream::{close_volume, push_window, NextFn};
+use super::indicator::Indicator;
+use super::technical::TecSerieIndicators;
+use super::{
+    serie::Serie,
+    serie_indicator::SerieIndicator,
+    technical::{TechnicalDefinition, TechnicalIndicators},
+};
+use crate::config::definition::TacDefinition;
+use crate::model::candle::Candle;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+
+pub const IND_MFI: &str = "mfi";
+
+pub const TEC_MFI: &str = "mfi";
+
+pub const DEFINITION: IndicatorDefinition = IndicatorDefinition {
+    name: TEC_MFI,
+    params: &[("period", 14)],
+    outputs: &[IndicatorOutput {
+        name: IND_MFI,
+        script_opt: Some(IND_MFI),
+        color: (128, 0, 128),
+        stream_opt: Some(stream),
+    }],
+    plot_style: None,
+    lookback,
+    build,
+};
+
+// Money flow direction needs the previous typical price
+fn lookback(params: &[usize]) -> usize {
+    params[0] + 1
+}
+
+fn build(candles: &[Candle], params: &[usize]) -> Box<dyn TecSerieIndicators + Send + Sync> {
+    Box::new(MfiTec::new(candles, params[0]))
+}
+
+fn stream(params: &[usize]) -> eyre::Result<NextFn> {
+    let period = params[0];
+    let mut window = VecDeque::with_capacity(period + 1);
+    let mut previous_opt: Option<f64> = None;
+    Ok(Box::new(move |c| {
+        let typical_price = ((c.high + c.low + c.close) / dec!(3)).to_f64().unwrap();
+        let money_flow = typical_price * close_volume(c);
+        let signed = match previous_opt {
+            Some(previous) if typical_price > previous => money_flow,
+            Some(previous) if typical_price < previous => -money_flow,
+            _ => 0.,
+        };
+        previous_opt = Some(typical_price);
+        push_window(&mut window, period, signed);
+
+        let positive = window.iter().filter(|m| **m > 0.).sum::<f64>();
+        let negative = -window.iter().filter(|m| **m < 0.).sum::<f64>();
+        if negative > 0. {
+            100. - 100. / (1. + positive / negative)
+        } else if positive > 0. {
+            100.
+        } else {
+            50.
+        }
+    }))
+}
+
+/// Money flow index, from 0 to 100, 50 while no money flowed
+pub struct MfiTec {
+    pub indicators: HashMap<String, SerieIndicator>,
+}
+
+impl TechnicalDefinition for MfiTec {
+    fn definition() -> TacDefinition {
+        DEFINITION.tac_definition()
+    }
+}
+
+impl TechnicalIndicators for MfiTec {
+    fn get_indicator(&self, name: &str) -> Option<&dyn Indicator> {
+        self.indicators.get(name).map(|s| s as &dyn Indicator)
+    }
+
+    fn main_indicator(&self) -> &dyn Indicator {
+        self.indicators.get(IND_MFI).unwrap() as &dyn Indicator
+    }
+
+    fn name(&self) -> String {
+        TEC_MFI.to_string()
+    }
+}
+
+impl TecSerieIndicators for MfiTec {
+    fn serie_indicators(&self) -> &HashMap<String, SerieIndicator> {
+        &self.indicators
+    }
+
+    fn name(&self) -> String {
+        TEC_MFI.to_string()
+    }
+}
+
+impl MfiTec {
+    /// Money flow index, a volume weighted RSI over typical price of last `period` candles
+    pub fn new(candles: &[Candle], period: usize) -> Self {
+        let mut mfi_series = Vec::with_capacity(candles.len());
+
+        let typical_prices = candles
+            .iter()
+            .map(|c| ((c.high + c.low + c.close) / dec!(3)).to_f64().unwrap())
+            .collect::<Vec<_>>();
+
+        // Raw money flow signed by the typical price direction
+        let money_flows = candles
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let money_flow = typical_prices[i] * c.volume.to_f64().unwrap();
+                match i.checked_sub(1).map(|p| typical_prices[p]) {
+                    Some(previous) if typical_prices[i] > previous => money_flow,
+                    Some(previous) if typical_prices[i] < previous => -money_flow,
+                    _ => 0.,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        for (i, candle) in candles.iter().enumerate() {
+            let start = (i + 1).saturating_sub(period);
+            let window = &money_flows[start..=i];
+            let positive = window.iter().filter(|m| **m > 0.).sum::<f64>();
+            let negative = -window.iter().filter(|m| **m < 0.).sum::<f64>();
+            let mfi = if negative > 0. {
+                100. - 100. / (1. + positive / negative)
+            } else if positive > 0. {
+                100.
+            } else {
+                50.
+            };
+            mfi_series.push(Serie::new(candle.close_time, mfi));
+        }
+
+        let mut indicators = HashMap::new();
+        indicators.insert(
+            IND_MFI.to_string(),
+            SerieIndicator::from(IND_MFI, mfi_series),
+        );
+
+        Self { indicators }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::utils::candles_utils::tests::candle;
+
+    #[test]
+    fn mfi_test() {
+        // Typical prices 10, 11, 10, 11 with money flows 100, 110, 50, 220
+        let candles = [
+            candle(11, 9, 10, 10),
+            candle(12, 10, 11, 10),
+            candle(11, 9, 10, 5),
+            candle(12, 10, 11, 20),
+        ];
+        let mfi_tec = MfiTec::new(&candles, 3);
+        let values = mfi_tec.indicators[IND_MFI]
+            .series
+            .iter()
+            .map(|s| s.value)
+            .collect::<Vec<_>>();
+        assert_eq!(values[0], 50.);
+        assert_eq!(values[1], 100.);
+        assert_eq!(values[3], 100. - 100. / (1. + 330. / 50.));
+    }
+}
@@ -1,12 +1,15 @@
+use super::ind_registry::{first_param, IndicatorDefinition, IndicatorOutput};
+use super::ind_stream::{NextFn, RollingExtreme};
+use super::technical::TecSerieIndicators;
 use super::{
     indicator::Indicator,
+    serie::Serie,
+    serie_indicator::SerieIndicator,
     technical::{TechnicalDefinition, TechnicalIndicators},
 };
 use crate::config::definition::TacDefinition;
 use crate::model::candle::Candle;
-use crate::services::technicals::value_indicator::ValueIndicator;
 use rust_decimal::prelude::ToPrimitive;
-use rust_decimal_macros::dec;
 use std::collections::HashMap;
 
 pub const IND_MIN: &str = "min";
@@ -15,14 +18,49 @@ pub const IND_MAX: &str = "max";
 
 pub const TEC_MIN_MAX: &str = "min_max";
 
+pub const DEFINITION: IndicatorDefinition = IndicatorDefinition {
+    name: TEC_MIN_MAX,
+    params: &[("period", 14)],
+    outputs: &[
+        IndicatorOutput {
+            name: IND_MIN,
+            script_opt: Some(IND_MIN),
+            color: (200, 0, 0),
+            stream_opt: Some(min_stream),
+        },
+        IndicatorOutput {
+            name: IND_MAX,
+            script_opt: Some(IND_MAX),
+            color: (0, 160, 0),
+            stream_opt: Some(max_stream),
+        },
+    ],
+    plot_style: None,
+    lookback: first_param,
+    build,
+};
+
+fn build(candles: &[Candle], params: &[usize]) -> Box<dyn TecSerieIndicators + Send + Sync> {
+    Box::new(MinMaxTec::new(candles, params[0]))
+}
+
+fn min_stream(params: &[usize]) -> eyre::Result<NextFn> {
+    let mut lows = RollingExtreme::new(params[0], |l, n| l <= n);
+    Ok(Box::new(move |c| lows.next(c.low)))
+}
+
+fn max_stream(params: &[usize]) -> eyre::Result<NextFn> {
+    let mut highs = RollingExtreme::new(params[0], |h, n| h >= n);
+    Ok(Box::new(move |c| highs.next(c.high)))
+}
+
 pub struct MinMaxTec {
-    pub indicators: HashMap<String, ValueIndicator>,
+    pub indicators: HashMap<String, SerieIndicator>,
 }
 
 impl TechnicalDefinition for MinMaxTec {
-    fn definition() -> crate::config::definition::TacDefinition {
-        let indicators = vec![IND_MIN, IND_MAX];
-        TacDefinition::new(TEC_MIN_MAX, &indicators)
+    fn definition() -> TacDefinition {
+        DEFINITION.tac_definition()
     }
 }
 
@@ -40,21 +78,39 @@ impl TechnicalIndicators for MinMaxTec {
     }
 }
 
-impl<'a> MinMaxTec {
-    pub fn new(candles: &[Candle], period: usize) -> Self {
-        let start = (candles.len() - period).max(0);
-        let last_candles = candles[start..candles.len()].to_vec();
+impl TecSerieIndicators for MinMaxTec {
+    fn serie_indicators(&self) -> &HashMap<String, SerieIndicator> {
+        &self.indicators
+    }
 
-        let max = last_candles.iter().fold(dec!(0), |acc, x| acc.max(x.high));
-        let min = last_candles.iter().fold(max, |acc, x| acc.min(x.low));
+    fn name(&self) -> String {
+        TEC_MIN_MAX.to_string()
+    }
+}
 
-        let mut indicators = HashMap::new();
+impl MinMaxTec {
+    /// Lowest low and highest high of last `period` candles
+    pub fn new(candles: &[Candle], period: usize) -> Self {
+        let mut min_series = Vec::with_capacity(candles.len());
+        let mut max_series = Vec::with_capacity(candles.len());
 
-        let ind_max = ValueIndicator::new(max.to_f64().unwrap());
-        indicators.insert(IND_MAX.to_string(), ind_max);
+        for (i, candle) in candles.iter().enumerate() {
+            let window = &candles[(i + 1).saturating_sub(period)..=i];
+            let min = window.iter().map(|c| c.low).min().unwrap();
+            let max = window.iter().map(|c| c.high).max().unwrap();
+            min_series.push(Serie::new(candle.close_time, min.to_f64().unwrap()));
+            max_series.push(Serie::new(candle.close_time, max.to_f64().unwrap()));
+        }
 
-        let ind_min = ValueIndicator::new(min.to_f64().unwrap());
-        indicators.insert(IND_MIN.to_string(), ind_min);
+        let mut indicators = HashMap::new();
+        indicators.insert(
+            IND_MIN.to_string(),
+            SerieIndicator::from(IND_MIN, min_series),
+        );
+        indicators.insert(
+            IND_MAX.to_string(),
+            SerieIndicator::from(IND_MAX, max_series),
+        );
 
         Self { indicators }
     }
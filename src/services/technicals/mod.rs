@@ -1,16 +1,32 @@
+pub mod ad_tec;
+pub mod bollinger_tec;
+pub mod brick_candles;
+pub mod cmf_tec;
+pub mod divergence_tec;
 pub mod ema_tec;
 pub mod heikin_ashi;
+pub mod ichimoku_tec;
 pub mod ind_group;
 pub mod ind_provider;
+pub mod ind_registry;
+pub mod ind_stream;
 pub mod ind_type;
 pub mod indicator;
 pub mod macd_tec;
+pub mod mfi_tec;
 pub mod min_max_tec;
+pub mod obv_tec;
+pub mod pair_tec;
 pub mod rsi_tec;
 pub mod serie;
 pub mod serie_indicator;
 pub mod sma_tec;
+pub mod support_resistance_tec;
+pub mod swing_stream;
 pub mod technical;
 pub mod top_bottom;
 pub mod top_bottom_tec;
 pub mod value_indicator;
+pub mod volume_profile_tec;
+pub mod volume_tec;
+pub mod vwap_tec;
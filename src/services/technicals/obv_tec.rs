@@ -0,0 +1,141 @@
+use super::ind_registry::{first_param, IndicatorDefinition, IndicatorOutput};
+use super::ind_stream::NextFn;
+use super::indicator::Indicator;
+use super::technical::TecSerieIndicators;
+use super::{
+    serie::Serie,
+    serie_indicator::SerieIndicator,
+    technical::{TechnicalDefinition, TechnicalIndicators},
+};
+use crate::config::definition::TacDefinition;
+use crate::model::candle::Candle;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+pub const IND_OBV: &str = "obv";
+
+pub const TEC_OBV: &str = "obv";
+
+pub const DEFINITION: IndicatorDefinition = IndicatorDefinition {
+    name: TEC_OBV,
+    params: &[("period", 20)],
+    outputs: &[IndicatorOutput {
+        name: IND_OBV,
+        script_opt: Some(IND_OBV),
+        color: (30, 80, 200),
+        stream_opt: Some(stream),
+    }],
+    plot_style: None,
+    lookback: first_param,
+    build,
+};
+
+// Accumulated since the first candle, the period only sets the candles computed from
+fn build(candles: &[Candle], _params: &[usize]) -> Box<dyn TecSerieIndicators + Send + Sync> {
+    Box::new(ObvTec::new(candles))
+}
+
+fn stream(_params: &[usize]) -> eyre::Result<NextFn> {
+    let mut obv = 0.;
+    let mut previous_close_opt: Option<Decimal> = None;
+    Ok(Box::new(move |c| {
+        if let Some(previous_close) = previous_close_opt {
+            if c.close > previous_close {
+                obv += c.volume.to_f64().unwrap();
+            } else if c.close < previous_close {
+                obv -= c.volume.to_f64().unwrap();
+            }
+        }
+        previous_close_opt = Some(c.close);
+        obv
+    }))
+}
+
+pub struct ObvTec {
+    pub indicators: HashMap<String, SerieIndicator>,
+}
+
+impl TechnicalDefinition for ObvTec {
+    fn definition() -> TacDefinition {
+        DEFINITION.tac_definition()
+    }
+}
+
+impl TechnicalIndicators for ObvTec {
+    fn get_indicator(&self, name: &str) -> Option<&dyn Indicator> {
+        self.indicators.get(name).map(|s| s as &dyn Indicator)
+    }
+
+    fn main_indicator(&self) -> &dyn Indicator {
+        self.indicators.get(IND_OBV).unwrap() as &dyn Indicator
+    }
+
+    fn name(&self) -> String {
+        TEC_OBV.to_string()
+    }
+}
+
+impl TecSerieIndicators for ObvTec {
+    fn serie_indicators(&self) -> &HashMap<String, SerieIndicator> {
+        &self.indicators
+    }
+
+    fn name(&self) -> String {
+        TEC_OBV.to_string()
+    }
+}
+
+impl ObvTec {
+    /// On balance volume accumulated since the first candle
+    pub fn new(candles: &[Candle]) -> Self {
+        let mut obv_series = Vec::with_capacity(candles.len());
+
+        let mut obv = 0.;
+        let mut previous_close_opt = None;
+        for candle in candles.iter() {
+            let volume = candle.volume.to_f64().unwrap();
+            if let Some(previous_close) = previous_close_opt {
+                if candle.close > previous_close {
+                    obv += volume;
+                } else if candle.close < previous_close {
+                    obv -= volume;
+                }
+            }
+            previous_close_opt = Some(candle.close);
+
+            obv_series.push(Serie::new(candle.close_time, obv));
+        }
+
+        let mut indicators = HashMap::new();
+        indicators.insert(
+            IND_OBV.to_string(),
+            SerieIndicator::from(IND_OBV, obv_series),
+        );
+
+        Self { indicators }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::utils::candles_utils::tests::candle;
+
+    #[test]
+    fn obv_test() {
+        let candles = [
+            candle(11, 9, 10, 100),
+            candle(12, 10, 11, 20),
+            candle(12, 10, 11, 30),
+            candle(11, 9, 10, 5),
+        ];
+        let obv_tec = ObvTec::new(&candles);
+        let values = obv_tec.indicators[IND_OBV]
+            .series
+            .iter()
+            .map(|s| s.value)
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![0., 20., 20., 15.]);
+    }
+}
@@ -0,0 +1,279 @@
+use super::serie::Serie;
+use super::serie_indicator::SerieIndicator;
+use super::technical::{TecSerieIndicators, TechnicalDefinition};
+use crate::config::definition::TacDefinition;
+use crate::model::candle::Candle;
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+pub const IND_RATIO: &str = "ratio";
+pub const IND_SPREAD: &str = "spread";
+pub const IND_CORRELATION: &str = "correlation";
+pub const IND_BETA: &str = "beta";
+
+pub const TEC_PAIR_RATIO: &str = "pair_ratio";
+pub const TEC_PAIR_SPREAD: &str = "pair_spread";
+
+/// Returns used for correlation, beta and spread
+pub const PAIR_PERIOD: usize = 50;
+
+/// Relation of a symbol with another one over the last closes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PairStats {
+    /// Last close divided by the other last close
+    pub ratio: f64,
+    /// Correlation of the returns
+    pub correlation: f64,
+    /// Returns regression slope on the other returns
+    pub beta: f64,
+    /// Z-score of the last log price spread, hedged by the log prices regression
+    pub spread: f64,
+}
+
+/// Closes of both symbols at each close time of either one once both have closed, a symbol
+/// without a candle closing then keeps its previous close
+pub fn aligned_closes(candles: &[Candle], others: &[Candle]) -> Vec<(DateTime<Utc>, f64, f64)> {
+    let mut result = Vec::with_capacity(candles.len().max(others.len()));
+    let (mut i, mut j) = (0, 0);
+    let (mut close_opt, mut other_opt) = (None, None);
+    while i < candles.len() || j < others.len() {
+        let ordering = match (candles.get(i), others.get(j)) {
+            (Some(candle), Some(other)) => candle.close_time.cmp(&other.close_time),
+            (Some(_), None) => Ordering::Less,
+            _ => Ordering::Greater,
+        };
+        let close_time = match ordering {
+            Ordering::Less => {
+                close_opt = Some(candles[i].close);
+                i += 1;
+                candles[i - 1].close_time
+            }
+            Ordering::Greater => {
+                other_opt = Some(others[j].close);
+                j += 1;
+                others[j - 1].close_time
+            }
+            Ordering::Equal => {
+                close_opt = Some(candles[i].close);
+                other_opt = Some(others[j].close);
+                i += 1;
+                j += 1;
+                candles[i - 1].close_time
+            }
+        };
+        if let (Some(close), Some(other)) = (close_opt, other_opt) {
+            result.push((close_time, close.to_f64().unwrap(), other.to_f64().unwrap()));
+        }
+    }
+    result
+}
+
+/// Stats of the last `period` returns of the aligned closes, NaN while there are not enough
+pub fn pair_stats(closes: &[(DateTime<Utc>, f64, f64)], period: usize) -> PairStats {
+    let ratio = closes
+        .last()
+        .map(|(_, a, b)| divide(*a, *b))
+        .unwrap_or(f64::NAN);
+    let window = &closes[closes.len().saturating_sub(period + 1)..];
+    if period < 2 || window.len() < period + 1 {
+        return PairStats {
+            ratio,
+            correlation: f64::NAN,
+            beta: f64::NAN,
+            spread: f64::NAN,
+        };
+    }
+
+    let returns = |value: fn(&(DateTime<Utc>, f64, f64)) -> f64| {
+        window
+            .windows(2)
+            .map(|w| divide(value(&w[1]), value(&w[0])) - 1.)
+            .collect::<Vec<_>>()
+    };
+    let returns_a = returns(|c| c.1);
+    let returns_b = returns(|c| c.2);
+    let covariance_ab = covariance(&returns_a, &returns_b);
+    let variance_a = covariance(&returns_a, &returns_a);
+    let variance_b = covariance(&returns_b, &returns_b);
+
+    let logs_a = window[1..].iter().map(|c| c.1.ln()).collect::<Vec<_>>();
+    let logs_b = window[1..].iter().map(|c| c.2.ln()).collect::<Vec<_>>();
+    let hedge = divide(covariance(&logs_a, &logs_b), covariance(&logs_b, &logs_b));
+    let spreads = logs_a
+        .iter()
+        .zip(logs_b.iter())
+        .map(|(a, b)| a - hedge * b)
+        .collect::<Vec<_>>();
+    let spread_std = covariance(&spreads, &spreads).sqrt();
+    let spread = divide(spreads[spreads.len() - 1] - mean(&spreads), spread_std);
+
+    PairStats {
+        ratio,
+        correlation: divide(covariance_ab, (variance_a * variance_b).sqrt()),
+        beta: divide(covariance_ab, variance_b),
+        spread,
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn covariance(a: &[f64], b: &[f64]) -> f64 {
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| (a - mean_a) * (b - mean_b))
+        .sum::<f64>()
+        / a.len() as f64
+}
+
+/// NaN instead of infinite when the divisor is zero
+fn divide(dividend: f64, divisor: f64) -> f64 {
+    if divisor == 0. {
+        f64::NAN
+    } else {
+        dividend / divisor
+    }
+}
+
+/// Price ratio of the selection symbol to the pair symbol
+pub struct PairRatioTec {
+    pub indicators: HashMap<String, SerieIndicator>,
+}
+
+impl TechnicalDefinition for PairRatioTec {
+    fn definition() -> TacDefinition {
+        TacDefinition::new(TEC_PAIR_RATIO, &[IND_RATIO])
+    }
+}
+
+impl TecSerieIndicators for PairRatioTec {
+    fn serie_indicators(&self) -> &HashMap<String, SerieIndicator> {
+        &self.indicators
+    }
+
+    fn name(&self) -> String {
+        TEC_PAIR_RATIO.to_string()
+    }
+}
+
+impl PairRatioTec {
+    pub fn new(candles: &[Candle], others: &[Candle]) -> Self {
+        let series = aligned_closes(candles, others)
+            .iter()
+            .map(|(time, a, b)| Serie::new(*time, divide(*a, *b)))
+            .filter(|s| s.value.is_finite())
+            .collect();
+        let mut indicators = HashMap::new();
+        indicators.insert(
+            IND_RATIO.to_string(),
+            SerieIndicator::from(IND_RATIO, series),
+        );
+        Self { indicators }
+    }
+}
+
+/// Rolling spread z-score, correlation and beta of the selection symbol to the pair symbol
+pub struct PairSpreadTec {
+    pub indicators: HashMap<String, SerieIndicator>,
+}
+
+impl TechnicalDefinition for PairSpreadTec {
+    fn definition() -> TacDefinition {
+        TacDefinition::new(TEC_PAIR_SPREAD, &[IND_SPREAD, IND_CORRELATION, IND_BETA])
+    }
+}
+
+impl TecSerieIndicators for PairSpreadTec {
+    fn serie_indicators(&self) -> &HashMap<String, SerieIndicator> {
+        &self.indicators
+    }
+
+    fn name(&self) -> String {
+        TEC_PAIR_SPREAD.to_string()
+    }
+}
+
+impl PairSpreadTec {
+    pub fn new(candles: &[Candle], others: &[Candle], period: usize) -> Self {
+        let closes = aligned_closes(candles, others);
+        let stats = (0..closes.len())
+            .map(|i| (closes[i].0, pair_stats(&closes[..=i], period)))
+            .collect::<Vec<_>>();
+
+        let mut indicators = HashMap::new();
+        for (name, value) in [
+            (
+                IND_SPREAD,
+                (|s: &PairStats| s.spread) as fn(&PairStats) -> f64,
+            ),
+            (IND_CORRELATION, |s| s.correlation),
+            (IND_BETA, |s| s.beta),
+        ]
+        .iter()
+        {
+            let series = stats
+                .iter()
+                .map(|(time, s)| Serie::new(*time, value(s)))
+                .filter(|s| s.value.is_finite())
+                .collect();
+            indicators.insert(name.to_string(), SerieIndicator::from(name, series));
+        }
+        Self { indicators }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::utils::candles_utils::tests::candle_at;
+    use rust_decimal::prelude::FromPrimitive;
+    use rust_decimal::Decimal;
+
+    fn candles(closes: &[f64]) -> Vec<Candle> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, close)| {
+                let close = Decimal::from_f64(*close).unwrap();
+                candle_at(i as i64, close, close, close, close, Decimal::from(10))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pair_stats_test() {
+        let others = (0..30)
+            .map(|i| 100. + (i as f64 * 0.7).sin() * 5. + i as f64)
+            .collect::<Vec<_>>();
+        let mut closes = others.iter().map(|c| c * 2.).collect::<Vec<_>>();
+
+        let aligned = aligned_closes(&candles(&closes), &candles(&others));
+        let stats = pair_stats(&aligned, 20);
+        assert!((stats.ratio - 2.).abs() < 1e-9);
+        assert!((stats.correlation - 1.).abs() < 1e-9);
+        assert!((stats.beta - 1.).abs() < 1e-9);
+        assert!(pair_stats(&aligned[..20], 20).correlation.is_nan());
+
+        // Last close far above the hedged spread
+        *closes.last_mut().unwrap() *= 1.1;
+        let aligned = aligned_closes(&candles(&closes), &candles(&others));
+        assert!(pair_stats(&aligned, 20).spread > 2.);
+
+        // From the first time both closed, the last other close after its candles end
+        let aligned = aligned_closes(&candles(&closes)[1..], &candles(&others)[..10]);
+        assert_eq!(aligned.len(), 29);
+        assert!((aligned[0].2 - others[1]).abs() < 1e-9);
+        assert!((aligned[28].2 - others[9]).abs() < 1e-9);
+
+        // A missing candle of either symbol doesn't shorten the window
+        let mut gaps = candles(&others);
+        gaps.remove(25);
+        let aligned = aligned_closes(&candles(&closes)[9..], &gaps[8..]);
+        assert_eq!(aligned.len(), 21);
+        assert!(pair_stats(&aligned, 20).correlation.is_finite());
+    }
+}
@@ -1,3 +1,5 @@
+use super::ind_registry::{first_param, IndicatorDefinition, IndicatorOutput};
+use super::ind_stream::{close, invalid_periods, NextFn};
 use super::indicator::Indicator;
 use super::technical::TecSerieIndicators;
 use super::{
@@ -15,14 +17,36 @@ pub const IND_RSI: &str = "rsi";
 
 pub const TEC_RSI: &str = "rsi";
 
+pub const DEFINITION: IndicatorDefinition = IndicatorDefinition {
+    name: TEC_RSI,
+    params: &[("period", 14)],
+    outputs: &[IndicatorOutput {
+        name: IND_RSI,
+        script_opt: Some(IND_RSI),
+        color: (0, 0, 255),
+        stream_opt: Some(stream),
+    }],
+    plot_style: None,
+    lookback: first_param,
+    build,
+};
+
+fn build(candles: &[Candle], params: &[usize]) -> Box<dyn TecSerieIndicators + Send + Sync> {
+    Box::new(RsiTec::new(candles, params[0]))
+}
+
+fn stream(params: &[usize]) -> eyre::Result<NextFn> {
+    let mut rsi = Rsi::new(params[0]).map_err(invalid_periods)?;
+    Ok(Box::new(move |c| rsi.next(close(c))))
+}
+
 pub struct RsiTec {
     pub indicators: HashMap<String, SerieIndicator>,
 }
 
 impl TechnicalDefinition for RsiTec {
     fn definition() -> TacDefinition {
-        let indicators = vec![IND_RSI];
-        TacDefinition::new(IND_RSI, &indicators)
+        DEFINITION.tac_definition()
     }
 }
 
@@ -1,5 +1,6 @@
 use super::indicator::Indicator;
 use super::serie::Serie;
+use chrono::{DateTime, Utc};
 use eyre::eyre;
 
 // TODO transform Indicator into a trait
@@ -20,6 +21,13 @@ impl SerieIndicator {
             series,
         }
     }
+
+    /// First and last date time, displaced series could end after the last candle
+    pub fn time_range(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let first = self.series.iter().map(|s| s.date_time).min()?;
+        let last = self.series.iter().map(|s| s.date_time).max()?;
+        Some((first, last))
+    }
 }
 
 impl Indicator for SerieIndicator {
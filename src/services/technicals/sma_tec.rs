@@ -1,4 +1,7 @@
+use super::ind_registry::{first_param, IndicatorDefinition, IndicatorOutput};
+use super::ind_stream::{close, invalid_periods, NextFn};
 use super::indicator::Indicator;
+use super::technical::TecSerieIndicators;
 use super::{
     serie::Serie,
     serie_indicator::SerieIndicator,
@@ -14,14 +17,36 @@ pub const IND_SMA: &str = "sma";
 
 pub const TEC_SMA: &str = "sma";
 
+pub const DEFINITION: IndicatorDefinition = IndicatorDefinition {
+    name: TEC_SMA,
+    params: &[("period", 20)],
+    outputs: &[IndicatorOutput {
+        name: IND_SMA,
+        script_opt: Some(IND_SMA),
+        color: (0, 128, 128),
+        stream_opt: Some(stream),
+    }],
+    plot_style: None,
+    lookback: first_param,
+    build,
+};
+
+fn build(candles: &[Candle], params: &[usize]) -> Box<dyn TecSerieIndicators + Send + Sync> {
+    Box::new(SmaTec::new(candles, params[0]))
+}
+
+fn stream(params: &[usize]) -> eyre::Result<NextFn> {
+    let mut sma = Sma::new(params[0]).map_err(invalid_periods)?;
+    Ok(Box::new(move |c| sma.next(close(c))))
+}
+
 pub struct SmaTec {
     pub indicators: HashMap<String, SerieIndicator>,
 }
 
 impl TechnicalDefinition for SmaTec {
-    fn definition() -> crate::config::definition::TacDefinition {
-        let indicators = vec![IND_SMA];
-        TacDefinition::new(IND_SMA, &indicators)
+    fn definition() -> TacDefinition {
+        DEFINITION.tac_definition()
     }
 }
 
@@ -35,7 +60,17 @@ impl TechnicalIndicators for SmaTec {
     }
 
     fn name(&self) -> String {
-        todo!()
+        TEC_SMA.to_string()
+    }
+}
+
+impl TecSerieIndicators for SmaTec {
+    fn serie_indicators(&self) -> &HashMap<String, SerieIndicator> {
+        &self.indicators
+    }
+
+    fn name(&self) -> String {
+        TEC_SMA.to_string()
     }
 }
 
@@ -0,0 +1,216 @@
+use super::indicator::Indicator;
+use super::technical::{TechnicalDefinition, TechnicalIndicators};
+use super::top_bottom::TopBottom;
+use super::top_bottom_tec::TopBottomTec;
+use super::value_indicator::ValueIndicator;
+use super::volume_profile_tec::VolumeProfileTec;
+use crate::config::definition::TacDefinition;
+use crate::model::candle::Candle;
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use std::collections::HashMap;
+
+pub const IND_SUPPORT: &str = "support";
+pub const IND_RESISTANCE: &str = "resistance";
+
+pub const TEC_SUPPORT_RESISTANCE: &str = "support_resistance";
+
+/// Relative price distance under which swings belong to the same zone
+pub const ZONE_TOLERANCE: f64 = 0.005;
+/// Swings needed to make a zone
+pub const MIN_TOUCHES: usize = 2;
+/// Candles each side of the swings making the zones
+pub const ZONE_NEIGHBORS: usize = 7;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SupportResistanceZone {
+    pub price_low: f64,
+    pub price_high: f64,
+    pub touches: usize,
+    pub last_time: DateTime<Utc>,
+    /// Touches, weighted by the volume traded in the zone when a volume profile is given
+    pub strength: f64,
+}
+
+impl SupportResistanceZone {
+    pub fn price(&self) -> f64 {
+        (self.price_low + self.price_high) / 2.
+    }
+}
+
+pub struct SupportResistanceTec {
+    pub zones: Vec<SupportResistanceZone>,
+    pub indicators: HashMap<String, ValueIndicator>,
+}
+
+impl TechnicalDefinition for SupportResistanceTec {
+    fn definition() -> TacDefinition {
+        TacDefinition::new(TEC_SUPPORT_RESISTANCE, &[IND_SUPPORT, IND_RESISTANCE])
+    }
+}
+
+impl TechnicalIndicators for SupportResistanceTec {
+    fn get_indicator(&self, name: &str) -> Option<&dyn Indicator> {
+        self.indicators.get(name).map(|s| s as &dyn Indicator)
+    }
+
+    fn main_indicator(&self) -> &dyn Indicator {
+        self.indicators.get(IND_SUPPORT).unwrap() as &dyn Indicator
+    }
+
+    fn name(&self) -> String {
+        TEC_SUPPORT_RESISTANCE.to_string()
+    }
+}
+
+impl SupportResistanceTec {
+    /// Zones of the swing tops and bottoms with `neighbors` candles each side, support and
+    /// resistance indicators are the nearest zones to the last close
+    pub fn new(
+        candles: &[Candle],
+        neighbors: usize,
+        volume_profile_opt: Option<&VolumeProfileTec>,
+    ) -> Self {
+        let zones = if candles.len() > neighbors * 2 + 1 {
+            let top_bottoms = TopBottomTec::new(candles, candles.len(), neighbors)
+                .top_bottoms()
+                .unwrap_or_default();
+            zones(&top_bottoms, ZONE_TOLERANCE, volume_profile_opt)
+        } else {
+            Vec::new()
+        };
+
+        let mut indicators = HashMap::new();
+        if let Some(candle) = candles.last() {
+            let price = candle.close.to_f64().unwrap();
+            if let Some(support) = nearest_support(&zones, price) {
+                let indicator = ValueIndicator::new(support.price());
+                indicators.insert(IND_SUPPORT.to_string(), indicator);
+            }
+            if let Some(resistance) = nearest_resistance(&zones, price) {
+                let indicator = ValueIndicator::new(resistance.price());
+                indicators.insert(IND_RESISTANCE.to_string(), indicator);
+            }
+        }
+
+        Self { zones, indicators }
+    }
+
+    pub fn support(&self) -> Option<f64> {
+        self.get_indicator(IND_SUPPORT).and_then(|i| i.value().ok())
+    }
+
+    pub fn resistance(&self) -> Option<f64> {
+        self.get_indicator(IND_RESISTANCE)
+            .and_then(|i| i.value().ok())
+    }
+}
+
+/// Clusters swing prices into zones sorted by price, a swing joins the zone when it is within
+/// `tolerance` of the zone lowest price
+pub fn zones(
+    top_bottoms: &[TopBottom],
+    tolerance: f64,
+    volume_profile_opt: Option<&VolumeProfileTec>,
+) -> Vec<SupportResistanceZone> {
+    let mut swings = top_bottoms
+        .iter()
+        .map(|tb| (tb.price.to_f64().unwrap(), tb.close_time))
+        .collect::<Vec<_>>();
+    swings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    let mut zones: Vec<SupportResistanceZone> = Vec::new();
+    for (price, time) in swings {
+        match zones.last_mut() {
+            Some(zone) if price <= zone.price_low * (1. + tolerance) => {
+                zone.price_high = price;
+                zone.touches += 1;
+                zone.last_time = zone.last_time.max(time);
+            }
+            _ => zones.push(SupportResistanceZone {
+                price_low: price,
+                price_high: price,
+                touches: 1,
+                last_time: time,
+                strength: 0.,
+            }),
+        }
+    }
+    zones.retain(|z| z.touches >= MIN_TOUCHES);
+
+    let max_volume_opt = volume_profile_opt
+        .map(|vp| vp.levels.iter().fold(0f64, |acc, l| acc.max(l.volume)))
+        .filter(|max| *max > 0.);
+    for zone in zones.iter_mut() {
+        zone.strength = zone.touches as f64;
+        if let (Some(volume_profile), Some(max_volume)) = (volume_profile_opt, max_volume_opt) {
+            // Up to twice the touches in the most traded price level
+            let volume = volume_profile
+                .levels
+                .iter()
+                .filter(|l| l.price_low <= zone.price_high && l.price_high >= zone.price_low)
+                .fold(0f64, |acc, l| acc.max(l.volume));
+            zone.strength *= 1. + volume / max_volume;
+        }
+    }
+    zones
+}
+
+/// Highest zone under the price
+pub fn nearest_support(
+    zones: &[SupportResistanceZone],
+    price: f64,
+) -> Option<&SupportResistanceZone> {
+    zones.iter().rev().find(|z| z.price() < price)
+}
+
+/// Lowest zone over the price
+pub fn nearest_resistance(
+    zones: &[SupportResistanceZone],
+    price: f64,
+) -> Option<&SupportResistanceZone> {
+    zones.iter().find(|z| z.price() > price)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::services::technicals::top_bottom::TopBottomType;
+    use crate::utils::date_utils::str_to_datetime;
+    use chrono::Duration;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn support_resistance_test() {
+        let start = str_to_datetime("2020-01-12 12:00:00");
+        let top_bottoms = [100, 120, 100, 121, 110, 120, 101, 140]
+            .iter()
+            .enumerate()
+            .map(|(i, price)| {
+                let type_p = if i % 2 == 0 {
+                    TopBottomType::Bottom
+                } else {
+                    TopBottomType::Top
+                };
+                TopBottom::new(
+                    type_p,
+                    start + Duration::hours(i as i64),
+                    Decimal::from(*price),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let zones = zones(&top_bottoms, 0.01, None);
+        assert_eq!(zones.len(), 2);
+        assert_eq!((zones[0].price_low, zones[0].price_high), (100., 101.));
+        assert_eq!(zones[0].touches, 3);
+        assert_eq!(zones[0].last_time, start + Duration::hours(6));
+        assert_eq!(zones[1].price(), 120.5);
+        assert_eq!(zones[1].strength, 3.);
+
+        assert_eq!(nearest_support(&zones, 115.).unwrap().touches, 3);
+        assert_eq!(nearest_resistance(&zones, 115.).unwrap().price_low, 120.);
+        assert!(nearest_support(&zones, 100.).is_none());
+        assert!(nearest_resistance(&zones, 125.).is_none());
+    }
+}
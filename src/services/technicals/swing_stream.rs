@@ -0,0 +1,194 @@
+use super::ind_stream::{push_window, HISTORY_LEN};
+use super::top_bottom::{TopBottom, TopBottomType};
+use super::top_bottom_tec::window_top_bottoms;
+use crate::model::candle::Candle;
+use std::collections::VecDeque;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwingSerie {
+    Tops,
+    Bottoms,
+    /// Alternating tops and bottoms, keeping the most extreme of consecutive ones of same type
+    Zigzag,
+}
+
+/// Tops and bottoms updated with each new closed candle, a swing is only confirmed
+/// `neighbors` candles after it, so it never looks ahead of the last streamed candle.
+/// Tops and bottoms are normalized as `TopBottomTec` does.
+pub struct SwingStream {
+    neighbors: usize,
+    window: VecDeque<Candle>,
+    // Last swing found and whether it's kept by normalization
+    last_opt: Option<(TopBottom, bool)>,
+    tops: VecDeque<TopBottom>,
+    bottoms: VecDeque<TopBottom>,
+    zigzag: VecDeque<TopBottom>,
+}
+
+impl SwingStream {
+    pub fn new(neighbors: usize) -> Self {
+        Self {
+            neighbors,
+            window: VecDeque::new(),
+            last_opt: None,
+            tops: VecDeque::new(),
+            bottoms: VecDeque::new(),
+            zigzag: VecDeque::new(),
+        }
+    }
+
+    pub fn next(&mut self, candle: &Candle) {
+        let window_len = self.neighbors * 2 + 1;
+        push_window(&mut self.window, window_len, *candle);
+        if self.window.len() < window_len {
+            return;
+        }
+
+        for top_bottom in window_top_bottoms(self.window.make_contiguous(), self.neighbors) {
+            self.next_normalized(top_bottom.clone());
+            self.next_zigzag(top_bottom);
+        }
+    }
+
+    /// Of two consecutive swings of the same type, drops the highest top or the lowest bottom
+    fn next_normalized(&mut self, top_bottom: TopBottom) {
+        let swings = match top_bottom.type_p {
+            TopBottomType::Top => &mut self.tops,
+            TopBottomType::Bottom => &mut self.bottoms,
+        };
+        let kept = match self.last_opt.take() {
+            Some((last, last_kept)) if last.type_p == top_bottom.type_p => {
+                let drop_current = match top_bottom.type_p {
+                    TopBottomType::Top => top_bottom.price > last.price,
+                    TopBottomType::Bottom => top_bottom.price < last.price,
+                };
+                if !drop_current && last_kept {
+                    swings.pop_back();
+                }
+                !drop_current
+            }
+            _ => true,
+        };
+        if kept {
+            push_window(swings, HISTORY_LEN, top_bottom.clone());
+        }
+        self.last_opt = Some((top_bottom, kept));
+    }
+
+    fn next_zigzag(&mut self, top_bottom: TopBottom) {
+        if let Some(top_bottom) = merge_zigzag(self.zigzag.back_mut(), top_bottom) {
+            push_window(&mut self.zigzag, HISTORY_LEN, top_bottom);
+        }
+    }
+
+    /// Last `len` confirmed swings, oldest first
+    pub fn swings(&self, swing_serie: SwingSerie, len: usize) -> Vec<TopBottom> {
+        let swings = match swing_serie {
+            SwingSerie::Tops => &self.tops,
+            SwingSerie::Bottoms => &self.bottoms,
+            SwingSerie::Zigzag => &self.zigzag,
+        };
+        swings
+            .iter()
+            .skip(swings.len().saturating_sub(len))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Replaces last swing when of the same type and less extreme, returns the swing when it must
+/// be appended instead
+fn merge_zigzag(last_opt: Option<&mut TopBottom>, top_bottom: TopBottom) -> Option<TopBottom> {
+    match last_opt {
+        Some(last) if last.type_p == top_bottom.type_p => {
+            let more_extreme = match top_bottom.type_p {
+                TopBottomType::Top => top_bottom.price > last.price,
+                TopBottomType::Bottom => top_bottom.price < last.price,
+            };
+            if more_extreme {
+                *last = top_bottom;
+            }
+            None
+        }
+        _ => Some(top_bottom),
+    }
+}
+
+/// Alternating tops and bottoms of sorted swings
+pub fn zigzag(top_bottoms: &[TopBottom]) -> Vec<TopBottom> {
+    let mut result: Vec<TopBottom> = Vec::new();
+    for top_bottom in top_bottoms.iter() {
+        if let Some(top_bottom) = merge_zigzag(result.last_mut(), top_bottom.clone()) {
+            result.push(top_bottom);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::services::technicals::top_bottom_tec::TopBottomTec;
+    use crate::utils::candles_utils::tests::candle_at;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn swing_stream_test() {
+        let highs = [10, 12, 14, 12, 10, 9, 11, 13, 16, 15, 13, 15, 14, 12, 11];
+        let candles = highs
+            .iter()
+            .enumerate()
+            .map(|(i, high)| {
+                let close = Decimal::from(*high - 1);
+                let (high, low) = (Decimal::from(*high), Decimal::from(*high - 2));
+                candle_at(i as i64, close, high, low, close, Decimal::from(1))
+            })
+            .collect::<Vec<_>>();
+
+        let mut swing_stream = SwingStream::new(2);
+        // Top of candle 2 is confirmed only after candle 4 closes
+        candles[..4].iter().for_each(|c| swing_stream.next(c));
+        assert!(swing_stream.swings(SwingSerie::Tops, 10).is_empty());
+        swing_stream.next(&candles[4]);
+        let tops = swing_stream.swings(SwingSerie::Tops, 10);
+        assert_eq!(tops.len(), 1);
+        assert_eq!(tops[0].close_time, candles[2].close_time);
+
+        candles[5..].iter().for_each(|c| swing_stream.next(c));
+        let prices = |swings: Vec<TopBottom>| swings.iter().map(|s| s.price).collect::<Vec<_>>();
+        assert_eq!(
+            prices(swing_stream.swings(SwingSerie::Tops, 10)),
+            vec![Decimal::from(14), Decimal::from(16)]
+        );
+        assert_eq!(
+            prices(swing_stream.swings(SwingSerie::Bottoms, 10)),
+            vec![Decimal::from(7), Decimal::from(11)]
+        );
+        assert_eq!(
+            prices(swing_stream.swings(SwingSerie::Zigzag, 3)),
+            vec![Decimal::from(7), Decimal::from(16), Decimal::from(11)]
+        );
+    }
+
+    #[test]
+    fn swing_stream_equals_batch_test() {
+        let candles = (0..80)
+            .map(|i| {
+                let high = Decimal::from(100 + (i * 7) % 13 + (i * 3) % 5);
+                let low = high - Decimal::from(2 + i % 3);
+                candle_at(i, low, high, low, low, Decimal::from(1))
+            })
+            .collect::<Vec<_>>();
+
+        for neighbors in 1..4 {
+            let mut swing_stream = SwingStream::new(neighbors);
+            candles.iter().for_each(|c| swing_stream.next(c));
+            let mut streamed = swing_stream.swings(SwingSerie::Tops, HISTORY_LEN);
+            streamed.extend(swing_stream.swings(SwingSerie::Bottoms, HISTORY_LEN));
+            streamed.sort_by_key(|s| s.close_time);
+
+            let batch = TopBottomTec::new(&candles, candles.len(), neighbors);
+            assert_eq!(streamed, batch.top_bottoms().unwrap());
+        }
+    }
+}
@@ -16,4 +16,11 @@ pub trait TechnicalIndicators {
 pub trait TecSerieIndicators {
     fn serie_indicators(&self) -> &HashMap<String, SerieIndicator>;
     fn name(&self) -> String;
+
+    /// Indicator of a registered output, a serie unless the technical computes it otherwise
+    fn output(&self, name: &str) -> Option<&dyn Indicator> {
+        self.serie_indicators()
+            .get(name)
+            .map(|s| s as &dyn Indicator)
+    }
 }
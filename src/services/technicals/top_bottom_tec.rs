@@ -45,42 +45,8 @@ impl TopBottomTec {
 
         let mut top_bottoms = Vec::new();
 
-        for i in 0..candles.len() - (neighbors * 2 + 1) {
-            let candle = &candles[i + neighbors];
-            let l_min = candles[i..i + neighbors]
-                .iter()
-                .map(|c| c.low)
-                .min()
-                .unwrap_or(candle.low);
-            let l_max = candles[i..i + neighbors]
-                .iter()
-                .map(|c| c.high)
-                .max()
-                .unwrap_or(candle.high);
-            let r_min = candles[i + neighbors + 1..i + (neighbors * 2 + 1)]
-                .iter()
-                .map(|c| c.low)
-                .min()
-                .unwrap_or(candle.low);
-            let r_max = candles[i + neighbors + 1..i + (neighbors * 2 + 1)]
-                .iter()
-                .map(|c| c.high)
-                .max()
-                .unwrap_or(candle.high);
-            if candle.low < l_min && candle.low < r_min {
-                top_bottoms.push(TopBottom::new(
-                    TopBottomType::Bottom,
-                    candle.close_time,
-                    candle.low,
-                ));
-            }
-            if candle.high > l_max && candle.high > r_max {
-                top_bottoms.push(TopBottom::new(
-                    TopBottomType::Top,
-                    candle.close_time,
-                    candle.high,
-                ));
-            }
+        for window in candles.windows(neighbors * 2 + 1) {
+            top_bottoms.extend(window_top_bottoms(window, neighbors));
         }
         normalize_top_bottoms(&mut top_bottoms);
 
@@ -113,6 +79,48 @@ impl TopBottomTec {
     }
 }
 
+/// Top and bottom of the middle candle of a window of `neighbors * 2 + 1` candles, when its high
+/// or low exceeds all its neighbors
+pub fn window_top_bottoms(window: &[Candle], neighbors: usize) -> Vec<TopBottom> {
+    let mut top_bottoms = Vec::new();
+    let candle = &window[neighbors];
+    let l_min = window[..neighbors]
+        .iter()
+        .map(|c| c.low)
+        .min()
+        .unwrap_or(candle.low);
+    let l_max = window[..neighbors]
+        .iter()
+        .map(|c| c.high)
+        .max()
+        .unwrap_or(candle.high);
+    let r_min = window[neighbors + 1..]
+        .iter()
+        .map(|c| c.low)
+        .min()
+        .unwrap_or(candle.low);
+    let r_max = window[neighbors + 1..]
+        .iter()
+        .map(|c| c.high)
+        .max()
+        .unwrap_or(candle.high);
+    if candle.low < l_min && candle.low < r_min {
+        top_bottoms.push(TopBottom::new(
+            TopBottomType::Bottom,
+            candle.close_time,
+            candle.low,
+        ));
+    }
+    if candle.high > l_max && candle.high > r_max {
+        top_bottoms.push(TopBottom::new(
+            TopBottomType::Top,
+            candle.close_time,
+            candle.high,
+        ));
+    }
+    top_bottoms
+}
+
 fn normalize_top_bottoms(top_bottoms: &mut Vec<TopBottom>) {
     if top_bottoms.is_empty() {
         return;
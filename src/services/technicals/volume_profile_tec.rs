@@ -0,0 +1,237 @@
+use super::indicator::Indicator;
+use super::technical::{TechnicalDefinition, TechnicalIndicators};
+use super::value_indicator::ValueIndicator;
+use crate::config::candles_selection::CandlesSelection;
+use crate::config::definition::TacDefinition;
+use crate::model::candle::Candle;
+use crate::repository::trade_agg_repository::TradeAggRepository;
+use crate::utils::dec_utils::fdec;
+use rust_decimal::prelude::ToPrimitive;
+use std::collections::HashMap;
+
+pub const IND_POC: &str = "poc";
+pub const IND_VAH: &str = "vah";
+pub const IND_VAL: &str = "val";
+
+pub const TEC_VOLUME_PROFILE: &str = "volume_profile";
+
+/// Share of the total volume around the POC that makes the value area
+pub const VALUE_AREA: f64 = 0.7;
+
+/// Price levels of the profile when not given
+pub const PROFILE_BINS: usize = 50;
+
+#[derive(Clone, Copy, Debug)]
+pub struct VolumeLevel {
+    pub price_low: f64,
+    pub price_high: f64,
+    pub volume: f64,
+}
+
+pub struct VolumeProfileTec {
+    pub levels: Vec<VolumeLevel>,
+    pub indicators: HashMap<String, ValueIndicator>,
+}
+
+impl TechnicalDefinition for VolumeProfileTec {
+    fn definition() -> TacDefinition {
+        let indicators = vec![IND_POC, IND_VAH, IND_VAL];
+        TacDefinition::new(TEC_VOLUME_PROFILE, &indicators)
+    }
+}
+
+impl TechnicalIndicators for VolumeProfileTec {
+    fn get_indicator(&self, name: &str) -> Option<&dyn Indicator> {
+        self.indicators.get(name).map(|s| s as &dyn Indicator)
+    }
+
+    fn main_indicator(&self) -> &dyn Indicator {
+        self.indicators.get(IND_POC).unwrap() as &dyn Indicator
+    }
+
+    fn name(&self) -> String {
+        TEC_VOLUME_PROFILE.to_string()
+    }
+}
+
+impl VolumeProfileTec {
+    /// Volume profile from candles, each candle volume spread evenly over its low/high range
+    pub fn new(candles: &[Candle], bins: usize) -> Self {
+        let (price_min, bin_size) = match price_range(candles, bins) {
+            Some(range) => range,
+            None => return Self::from_bins(0., 0., Vec::new()),
+        };
+
+        let mut volumes = vec![0.; bins];
+        for candle in candles.iter() {
+            let low = candle.low.to_f64().unwrap();
+            let high = candle.high.to_f64().unwrap();
+            let volume = candle.volume.to_f64().unwrap();
+            let first = bin_index(low, price_min, bin_size, bins);
+            let last = bin_index(high, price_min, bin_size, bins);
+            if high <= low || first == last {
+                volumes[first] += volume;
+                continue;
+            }
+            for (i, bin_volume) in volumes.iter_mut().enumerate().take(last + 1).skip(first) {
+                let bin_low = price_min + bin_size * i as f64;
+                let overlap = (high.min(bin_low + bin_size) - low.max(bin_low)).max(0.);
+                *bin_volume += volume * overlap / (high - low);
+            }
+        }
+        Self::from_bins(price_min, bin_size, volumes)
+    }
+
+    /// Volume profile from traded quantities in `trade_agg`, falling back to candles when
+    /// there are no trades imported for the selection
+    pub fn from_selection(
+        trade_agg_repository: &TradeAggRepository,
+        candles_selection: &CandlesSelection,
+        candles: &[Candle],
+        bins: usize,
+    ) -> eyre::Result<Self> {
+        let (price_min, bin_size) = match price_range(candles, bins) {
+            Some(range) => range,
+            None => return Ok(Self::new(candles, bins)),
+        };
+
+        let volumes_at_price = trade_agg_repository.volume_at_price(
+            candles_selection.symbol_minutes.symbol,
+            candles_selection.start_time,
+            candles_selection.end_time,
+            fdec(price_min),
+            fdec(bin_size),
+            bins as i32,
+        )?;
+
+        if volumes_at_price.is_empty() {
+            return Ok(Self::new(candles, bins));
+        }
+
+        let mut volumes = vec![0.; bins];
+        for (bin, volume) in volumes_at_price.iter() {
+            volumes[*bin as usize] += volume.to_f64().unwrap();
+        }
+        Ok(Self::from_bins(price_min, bin_size, volumes))
+    }
+
+    fn from_bins(price_min: f64, bin_size: f64, volumes: Vec<f64>) -> Self {
+        let levels = volumes
+            .iter()
+            .enumerate()
+            .map(|(i, volume)| VolumeLevel {
+                price_low: price_min + bin_size * i as f64,
+                price_high: price_min + bin_size * (i + 1) as f64,
+                volume: *volume,
+            })
+            .collect::<Vec<_>>();
+
+        let mut indicators = HashMap::new();
+        if let Some((poc, vah, val)) = value_area(&levels) {
+            indicators.insert(IND_POC.to_string(), ValueIndicator::new(poc));
+            indicators.insert(IND_VAH.to_string(), ValueIndicator::new(vah));
+            indicators.insert(IND_VAL.to_string(), ValueIndicator::new(val));
+        }
+
+        Self { levels, indicators }
+    }
+
+    pub fn poc(&self) -> Option<f64> {
+        self.get_indicator(IND_POC).and_then(|i| i.value().ok())
+    }
+
+    pub fn vah(&self) -> Option<f64> {
+        self.get_indicator(IND_VAH).and_then(|i| i.value().ok())
+    }
+
+    pub fn val(&self) -> Option<f64> {
+        self.get_indicator(IND_VAL).and_then(|i| i.value().ok())
+    }
+}
+
+fn price_range(candles: &[Candle], bins: usize) -> Option<(f64, f64)> {
+    let min = candles.iter().map(|c| c.low).min()?.to_f64().unwrap();
+    let max = candles.iter().map(|c| c.high).max()?.to_f64().unwrap();
+    if bins == 0 || max <= min {
+        return None;
+    }
+    Some((min, (max - min) / bins as f64))
+}
+
+fn bin_index(price: f64, price_min: f64, bin_size: f64, bins: usize) -> usize {
+    (((price - price_min) / bin_size).floor().max(0.) as usize).min(bins - 1)
+}
+
+/// Returns (POC, VAH, VAL), expanding from the POC level to the greater neighbor until the
+/// value area holds `VALUE_AREA` of the volume
+fn value_area(levels: &[VolumeLevel]) -> Option<(f64, f64, f64)> {
+    let (poc_index, poc_level) = levels.iter().enumerate().fold(
+        None,
+        |acc: Option<(usize, &VolumeLevel)>, (i, l)| match acc {
+            Some((_, max)) if max.volume >= l.volume => acc,
+            _ => Some((i, l)),
+        },
+    )?;
+
+    let total = levels.iter().map(|l| l.volume).sum::<f64>();
+    let target = total * VALUE_AREA;
+
+    let (mut low, mut high) = (poc_index, poc_index);
+    let mut volume = poc_level.volume;
+    while volume < target && (low > 0 || high < levels.len() - 1) {
+        let below = if low > 0 { levels[low - 1].volume } else { -1. };
+        let above = if high < levels.len() - 1 {
+            levels[high + 1].volume
+        } else {
+            -1.
+        };
+        if above >= below {
+            high += 1;
+            volume += levels[high].volume;
+        } else {
+            low -= 1;
+            volume += levels[low].volume;
+        }
+    }
+
+    let poc = (poc_level.price_low + poc_level.price_high) / 2.;
+    Some((poc, levels[high].price_high, levels[low].price_low))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::utils::candles_utils::tests::candle_at;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    fn candle(low: Decimal, high: Decimal, volume: Decimal) -> Candle {
+        candle_at(0, low, high, low, high, volume)
+    }
+
+    #[test]
+    fn volume_profile_candles_test() {
+        let candles = [
+            candle(dec!(100), dec!(110), dec!(10)),
+            candle(dec!(104), dec!(106), dec!(50)),
+            candle(dec!(105), dec!(105), dec!(5)),
+        ];
+        let volume_profile = VolumeProfileTec::new(&candles, 10);
+
+        let total = volume_profile.levels.iter().map(|l| l.volume).sum::<f64>();
+        assert!((total - 65.).abs() < 1e-9);
+
+        // Bins [104, 105) and [105, 106) hold 1 + 25 each, plus 5 on [105, 106)
+        assert!((volume_profile.levels[5].volume - 31.).abs() < 1e-9);
+        assert_eq!(volume_profile.poc(), Some(105.5));
+        assert_eq!(volume_profile.vah(), Some(106.));
+        assert_eq!(volume_profile.val(), Some(104.));
+    }
+
+    #[test]
+    fn volume_profile_empty_test() {
+        let volume_profile = VolumeProfileTec::new(&[], 10);
+        assert!(volume_profile.levels.is_empty());
+        assert_eq!(volume_profile.poc(), None);
+    }
+}
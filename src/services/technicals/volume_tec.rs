@@ -0,0 +1,112 @@
+use super::ind_registry::{first_param, IndicatorDefinition, IndicatorOutput};
+use super::ind_stream::{close_volume, invalid_periods, NextFn};
+use super::indicator::Indicator;
+use super::technical::TecSerieIndicators;
+use super::{
+    serie::Serie,
+    serie_indicator::SerieIndicator,
+    technical::{TechnicalDefinition, TechnicalIndicators},
+};
+use crate::config::definition::TacDefinition;
+use crate::model::candle::Candle;
+use rust_decimal::prelude::ToPrimitive;
+use std::collections::HashMap;
+use ta::{indicators::SimpleMovingAverage as Sma, Next};
+
+pub const IND_VOLUME: &str = "volume";
+pub const IND_VOLUME_SMA: &str = "volume_sma";
+
+pub const TEC_VOLUME: &str = "volume";
+
+pub const DEFINITION: IndicatorDefinition = IndicatorDefinition {
+    name: TEC_VOLUME,
+    params: &[("period", 20)],
+    outputs: &[
+        IndicatorOutput {
+            name: IND_VOLUME,
+            script_opt: None,
+            color: (96, 96, 96),
+            stream_opt: None,
+        },
+        IndicatorOutput {
+            name: IND_VOLUME_SMA,
+            script_opt: Some(IND_VOLUME_SMA),
+            color: (255, 165, 0),
+            stream_opt: Some(sma_stream),
+        },
+    ],
+    plot_style: None,
+    lookback: first_param,
+    build,
+};
+
+fn build(candles: &[Candle], params: &[usize]) -> Box<dyn TecSerieIndicators + Send + Sync> {
+    Box::new(VolumeTec::new(candles, params[0]))
+}
+
+fn sma_stream(params: &[usize]) -> eyre::Result<NextFn> {
+    let mut sma = Sma::new(params[0]).map_err(invalid_periods)?;
+    Ok(Box::new(move |c| sma.next(close_volume(c))))
+}
+
+/// Candles volume and its simple moving average, to compare a volume with the usual one
+pub struct VolumeTec {
+    pub indicators: HashMap<String, SerieIndicator>,
+}
+
+impl TechnicalDefinition for VolumeTec {
+    fn definition() -> TacDefinition {
+        DEFINITION.tac_definition()
+    }
+}
+
+impl TechnicalIndicators for VolumeTec {
+    fn get_indicator(&self, name: &str) -> Option<&dyn Indicator> {
+        self.indicators.get(name).map(|s| s as &dyn Indicator)
+    }
+
+    fn main_indicator(&self) -> &dyn Indicator {
+        self.indicators.get(IND_VOLUME_SMA).unwrap() as &dyn Indicator
+    }
+
+    fn name(&self) -> String {
+        TEC_VOLUME.to_string()
+    }
+}
+
+impl TecSerieIndicators for VolumeTec {
+    fn serie_indicators(&self) -> &HashMap<String, SerieIndicator> {
+        &self.indicators
+    }
+
+    fn name(&self) -> String {
+        TEC_VOLUME.to_string()
+    }
+}
+
+impl VolumeTec {
+    /// Candles volume and its simple moving average
+    pub fn new(candles: &[Candle], period: usize) -> Self {
+        let mut volume_series = Vec::with_capacity(candles.len());
+        let mut volume_sma_series = Vec::with_capacity(candles.len());
+
+        let mut sma_ta = Sma::new(period).unwrap();
+        for candle in candles.iter() {
+            let volume = candle.volume.to_f64().unwrap();
+            volume_series.push(Serie::new(candle.close_time, volume));
+            volume_sma_series.push(Serie::new(candle.close_time, sma_ta.next(volume)));
+        }
+
+        let mut indicators = HashMap::new();
+        indicators.insert(
+            IND_VOLUME.to_string(),
+            SerieIndicator::from(IND_VOLUME, volume_series),
+        );
+        indicators.insert(
+            IND_VOLUME_SMA.to_string(),
+            SerieIndicator::from(IND_VOLUME_SMA, volume_sma_series),
+        );
+
+        Self { indicators }
+    }
+}
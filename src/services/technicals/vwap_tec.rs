@@ -0,0 +1,210 @@
+use super::indicator::Indicator;
+use super::technical::TecSerieIndicators;
+use super::top_bottom_tec::TopBottomTec;
+use super::{
+    serie::Serie,
+    serie_indicator::SerieIndicator,
+    technical::{TechnicalDefinition, TechnicalIndicators},
+};
+use crate::config::definition::TacDefinition;
+use crate::model::candle::Candle;
+use chrono::{DateTime, Utc};
+use eyre::eyre;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+
+pub const IND_VWAP: &str = "vwap";
+pub const IND_VWAP_UPPER: &str = "vwap_upper";
+pub const IND_VWAP_LOWER: &str = "vwap_lower";
+
+pub const TEC_VWAP: &str = "vwap";
+
+pub struct VwapTec {
+    pub indicators: HashMap<String, SerieIndicator>,
+}
+
+impl TechnicalDefinition for VwapTec {
+    fn definition() -> TacDefinition {
+        let indicators = vec![IND_VWAP, IND_VWAP_UPPER, IND_VWAP_LOWER];
+        TacDefinition::new(TEC_VWAP, &indicators)
+    }
+}
+
+impl TechnicalIndicators for VwapTec {
+    fn get_indicator(&self, name: &str) -> Option<&dyn Indicator> {
+        self.indicators.get(name).map(|s| s as &dyn Indicator)
+    }
+
+    fn main_indicator(&self) -> &dyn Indicator {
+        self.indicators.get(IND_VWAP).unwrap() as &dyn Indicator
+    }
+
+    fn name(&self) -> String {
+        TEC_VWAP.to_string()
+    }
+}
+
+impl TecSerieIndicators for VwapTec {
+    fn serie_indicators(&self) -> &HashMap<String, SerieIndicator> {
+        &self.indicators
+    }
+
+    fn name(&self) -> String {
+        TEC_VWAP.to_string()
+    }
+}
+
+impl VwapTec {
+    /// Session VWAP restarting at each UTC day, bands at `deviations` standard deviations
+    pub fn new(candles: &[Candle], deviations: f64) -> Self {
+        Self::accumulate(candles, deviations, |previous, current| {
+            previous.open_time.date() != current.open_time.date()
+        })
+    }
+
+    /// VWAP accumulated without reset from the first candle closed at or after `anchor`
+    pub fn anchored(candles: &[Candle], anchor: DateTime<Utc>, deviations: f64) -> Self {
+        let start = candles
+            .iter()
+            .position(|c| c.close_time >= anchor)
+            .unwrap_or(candles.len());
+        Self::accumulate(&candles[start..], deviations, |_, _| false)
+    }
+
+    /// VWAP anchored at the last top or bottom found by `top_bottom_tec`
+    pub fn anchored_last_top_bottom(
+        candles: &[Candle],
+        top_bottom_tec: &TopBottomTec,
+        deviations: f64,
+    ) -> eyre::Result<Self> {
+        let top_bottoms = top_bottom_tec.top_bottoms()?;
+        let anchor = top_bottoms
+            .last()
+            .ok_or_else(|| eyre!("No top/bottom to anchor VWAP!"))?
+            .close_time;
+        Ok(Self::anchored(candles, anchor, deviations))
+    }
+
+    pub fn main_serie_indicator(&self) -> &SerieIndicator {
+        self.indicators.get(IND_VWAP).unwrap()
+    }
+
+    fn accumulate<F>(candles: &[Candle], deviations: f64, is_reset: F) -> Self
+    where
+        F: Fn(&Candle, &Candle) -> bool,
+    {
+        let mut vwap_series = Vec::with_capacity(candles.len());
+        let mut upper_series = Vec::with_capacity(candles.len());
+        let mut lower_series = Vec::with_capacity(candles.len());
+
+        let mut volume_sum = 0.;
+        let mut price_volume_sum = 0.;
+        let mut price2_volume_sum = 0.;
+        let mut previous_opt: Option<&Candle> = None;
+
+        for candle in candles.iter() {
+            if previous_opt.map(|p| is_reset(p, candle)).unwrap_or(false) {
+                volume_sum = 0.;
+                price_volume_sum = 0.;
+                price2_volume_sum = 0.;
+            }
+            previous_opt = Some(candle);
+
+            let typical = ((candle.high + candle.low + candle.close) / dec!(3))
+                .to_f64()
+                .unwrap();
+            let volume = candle.volume.to_f64().unwrap();
+
+            volume_sum += volume;
+            price_volume_sum += typical * volume;
+            price2_volume_sum += typical * typical * volume;
+
+            let (vwap, deviation) = if volume_sum > 0. {
+                let vwap = price_volume_sum / volume_sum;
+                let variance = (price2_volume_sum / volume_sum - vwap * vwap).max(0.);
+                (vwap, variance.sqrt())
+            } else {
+                (typical, 0.)
+            };
+
+            vwap_series.push(Serie::new(candle.close_time, vwap));
+            upper_series.push(Serie::new(candle.close_time, vwap + deviations * deviation));
+            lower_series.push(Serie::new(candle.close_time, vwap - deviations * deviation));
+        }
+
+        let mut indicators = HashMap::new();
+        indicators.insert(
+            IND_VWAP.to_string(),
+            SerieIndicator::from(IND_VWAP, vwap_series),
+        );
+        indicators.insert(
+            IND_VWAP_UPPER.to_string(),
+            SerieIndicator::from(IND_VWAP_UPPER, upper_series),
+        );
+        indicators.insert(
+            IND_VWAP_LOWER.to_string(),
+            SerieIndicator::from(IND_VWAP_LOWER, lower_series),
+        );
+
+        Self { indicators }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::utils::candles_utils::tests::candle_at;
+    use crate::utils::date_utils::str_to_datetime;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    /// Candles opened at 23:30, 23:45 and 00:00 of the next day
+    fn candles(prices: [(Decimal, Decimal); 3]) -> Vec<Candle> {
+        prices
+            .iter()
+            .zip(46..)
+            .map(|((price, volume), index)| {
+                candle_at(index, *price, *price, *price, *price, *volume)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn vwap_session_reset_test() {
+        let candles = candles([
+            (dec!(100), dec!(1)),
+            (dec!(110), dec!(3)),
+            (dec!(200), dec!(2)),
+        ]);
+        let vwap_tec = VwapTec::new(&candles, 2.);
+
+        let vwap = &vwap_tec.indicators.get(IND_VWAP).unwrap().series;
+        assert!((vwap[0].value - 100.).abs() < 1e-9);
+        assert!((vwap[1].value - 107.5).abs() < 1e-9);
+        // New day starts a new session
+        assert!((vwap[2].value - 200.).abs() < 1e-9);
+
+        // Variance of 100 (x1) and 110 (x3) around 107.5 is 18.75
+        let upper = &vwap_tec.indicators.get(IND_VWAP_UPPER).unwrap().series;
+        let lower = &vwap_tec.indicators.get(IND_VWAP_LOWER).unwrap().series;
+        assert!((upper[1].value - (107.5 + 2. * 18.75f64.sqrt())).abs() < 1e-9);
+        assert!((lower[1].value - (107.5 - 2. * 18.75f64.sqrt())).abs() < 1e-9);
+        assert!((upper[2].value - 200.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn vwap_anchored_test() {
+        let candles = candles([
+            (dec!(100), dec!(1)),
+            (dec!(110), dec!(3)),
+            (dec!(200), dec!(5)),
+        ]);
+        let vwap_tec = VwapTec::anchored(&candles, str_to_datetime("2020-01-12 23:50:00"), 1.);
+
+        let vwap = &vwap_tec.indicators.get(IND_VWAP).unwrap().series;
+        assert_eq!(vwap.len(), 2);
+        // No reset across days when anchored
+        assert!((vwap[1].value - (110. * 3. + 200. * 5.) / 8.).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,485 @@
+use crate::services::technicals::swing_stream::zigzag;
+use crate::services::technicals::top_bottom::{TopBottom, TopBottomType};
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+
+/// Relative price difference under which swings are at the same level
+pub const PATTERN_TOLERANCE: f64 = 0.01;
+/// Swings needed before the last one to match any pattern, flags need the pole swing
+pub const PATTERN_SWINGS: usize = 6;
+
+/// End gap under this ratio of the start gap makes the trendlines converging
+const CONVERGING_RATIO: f64 = 0.75;
+/// Gap change under this ratio of the start gap makes the trendlines parallel
+const PARALLEL_RATIO: f64 = 0.25;
+/// Move before a channel at least this ratio of its height makes it a flag
+const POLE_RATIO: f64 = 2.;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ChartPatternType {
+    AscendingTriangle,
+    DescendingTriangle,
+    SymmetricTriangle,
+    DoubleTop,
+    DoubleBottom,
+    TripleTop,
+    TripleBottom,
+    HeadAndShoulders,
+    InverseHeadAndShoulders,
+    RisingWedge,
+    FallingWedge,
+    BullFlag,
+    BearFlag,
+    AscendingChannel,
+    DescendingChannel,
+    HorizontalChannel,
+}
+
+impl ChartPatternType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChartPatternType::AscendingTriangle => "ascending_triangle",
+            ChartPatternType::DescendingTriangle => "descending_triangle",
+            ChartPatternType::SymmetricTriangle => "symmetric_triangle",
+            ChartPatternType::DoubleTop => "double_top",
+            ChartPatternType::DoubleBottom => "double_bottom",
+            ChartPatternType::TripleTop => "triple_top",
+            ChartPatternType::TripleBottom => "triple_bottom",
+            ChartPatternType::HeadAndShoulders => "head_and_shoulders",
+            ChartPatternType::InverseHeadAndShoulders => "inverse_head_and_shoulders",
+            ChartPatternType::RisingWedge => "rising_wedge",
+            ChartPatternType::FallingWedge => "falling_wedge",
+            ChartPatternType::BullFlag => "bull_flag",
+            ChartPatternType::BearFlag => "bear_flag",
+            ChartPatternType::AscendingChannel => "ascending_channel",
+            ChartPatternType::DescendingChannel => "descending_channel",
+            ChartPatternType::HorizontalChannel => "horizontal_channel",
+        }
+    }
+}
+
+impl std::fmt::Display for ChartPatternType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrendLine {
+    pub start_time: DateTime<Utc>,
+    pub start_price: f64,
+    pub end_time: DateTime<Utc>,
+    pub end_price: f64,
+}
+
+impl TrendLine {
+    pub fn horizontal(start_time: DateTime<Utc>, end_time: DateTime<Utc>, price: f64) -> Self {
+        Self {
+            start_time,
+            start_price: price,
+            end_time,
+            end_price: price,
+        }
+    }
+
+    /// Least squares line through the swings and its mean absolute error
+    fn fit(swings: &[&TopBottom]) -> (Self, f64) {
+        let start_time = swings[0].close_time;
+        let end_time = swings[swings.len() - 1].close_time;
+        let points = swings
+            .iter()
+            .map(|s| ((s.close_time - start_time).num_seconds() as f64, price(s)))
+            .collect::<Vec<_>>();
+
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+        let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+        let var_x = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum::<f64>();
+        let cov = points
+            .iter()
+            .map(|p| (p.0 - mean_x) * (p.1 - mean_y))
+            .sum::<f64>();
+        let slope = if var_x == 0. { 0. } else { cov / var_x };
+        let intercept = mean_y - slope * mean_x;
+
+        let error = points
+            .iter()
+            .map(|p| (intercept + slope * p.0 - p.1).abs())
+            .sum::<f64>()
+            / n;
+        let end_x = (end_time - start_time).num_seconds() as f64;
+        let line = Self {
+            start_time,
+            start_price: intercept,
+            end_time,
+            end_price: intercept + slope * end_x,
+        };
+        (line, error)
+    }
+
+    pub fn price_at(&self, time: DateTime<Utc>) -> f64 {
+        let duration = (self.end_time - self.start_time).num_seconds() as f64;
+        if duration == 0. {
+            return self.end_price;
+        }
+        let elapsed = (time - self.start_time).num_seconds() as f64;
+        self.start_price + (self.end_price - self.start_price) * elapsed / duration
+    }
+
+    /// Same line between other times
+    pub fn extend(&self, start_time: DateTime<Utc>, end_time: DateTime<Utc>) -> Self {
+        Self {
+            start_time,
+            start_price: self.price_at(start_time),
+            end_time,
+            end_price: self.price_at(end_time),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChartPattern {
+    pub pattern_type: ChartPatternType,
+    pub start_time: DateTime<Utc>,
+    /// Close time of the last swing, the pattern is known once this swing is confirmed
+    pub end_time: DateTime<Utc>,
+    pub upper: TrendLine,
+    pub lower: TrendLine,
+    /// Price to cross in the expected direction
+    pub breakout: f64,
+    pub bullish: bool,
+    /// From 0 to 1, how well the swings fit the pattern
+    pub quality: f64,
+}
+
+/// All patterns of the swings, sorted by their last swing
+pub fn chart_patterns(top_bottoms: &[TopBottom], tolerance: f64) -> Vec<ChartPattern> {
+    let swings = zigzag(top_bottoms);
+    (1..=swings.len())
+        .flat_map(|end| last_chart_patterns(&swings[..end], tolerance))
+        .collect()
+}
+
+/// Patterns ending with the last of alternating swings
+pub fn last_chart_patterns(swings: &[TopBottom], tolerance: f64) -> Vec<ChartPattern> {
+    let len = swings.len();
+    let mut patterns = Vec::new();
+    if len >= 3 {
+        patterns.extend(double(&swings[len - 3..], tolerance));
+    }
+    if len >= 5 {
+        let window = &swings[len - 5..];
+        patterns.extend(triple(window, tolerance));
+        patterns.extend(head_and_shoulders(window, tolerance));
+        let pole_opt = len.checked_sub(6).map(|i| &swings[i]);
+        patterns.extend(trendlines_pattern(window, pole_opt, tolerance));
+    }
+    patterns
+}
+
+fn price(top_bottom: &TopBottom) -> f64 {
+    top_bottom.price.to_f64().unwrap()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Quality of prices at the same level, 0 when their spread is twice the tolerance
+fn level_quality(prices: &[f64], tolerance: f64) -> f64 {
+    let max = prices.iter().cloned().fold(f64::MIN, f64::max);
+    let min = prices.iter().cloned().fold(f64::MAX, f64::min);
+    (1. - (max - min) / (2. * tolerance * mean(prices))).clamp(0., 1.)
+}
+
+fn same_level(prices: &[f64], tolerance: f64) -> bool {
+    level_quality(prices, tolerance) >= 0.5
+}
+
+fn double(window: &[TopBottom], tolerance: f64) -> Option<ChartPattern> {
+    let [first, middle, last] = [&window[0], &window[1], &window[2]];
+    let extremes = [price(first), price(last)];
+    let neckline = price(middle);
+    let depth = (mean(&extremes) - neckline).abs() / mean(&extremes);
+    if !same_level(&extremes, tolerance) || depth <= tolerance {
+        return None;
+    }
+
+    let (extremes_line, _) = TrendLine::fit(&[first, last]);
+    let neckline_line = TrendLine::horizontal(first.close_time, last.close_time, neckline);
+    let top = first.type_p == TopBottomType::Top;
+    let (pattern_type, upper, lower) = if top {
+        (ChartPatternType::DoubleTop, extremes_line, neckline_line)
+    } else {
+        (ChartPatternType::DoubleBottom, neckline_line, extremes_line)
+    };
+    Some(ChartPattern {
+        pattern_type,
+        start_time: first.close_time,
+        end_time: last.close_time,
+        upper,
+        lower,
+        breakout: neckline,
+        bullish: !top,
+        quality: level_quality(&extremes, tolerance),
+    })
+}
+
+fn triple(window: &[TopBottom], tolerance: f64) -> Option<ChartPattern> {
+    let extremes = [price(&window[0]), price(&window[2]), price(&window[4])];
+    let necks = [price(&window[1]), price(&window[3])];
+    let depth = (mean(&extremes) - mean(&necks)).abs() / mean(&extremes);
+    if !same_level(&extremes, tolerance)
+        || !same_level(&necks, 2. * tolerance)
+        || depth <= tolerance
+    {
+        return None;
+    }
+
+    let (start_time, end_time) = (window[0].close_time, window[4].close_time);
+    let (extremes_line, _) = TrendLine::fit(&[&window[0], &window[2], &window[4]]);
+    let (neckline, _) = TrendLine::fit(&[&window[1], &window[3]]);
+    let neckline = neckline.extend(start_time, end_time);
+    let top = window[0].type_p == TopBottomType::Top;
+    let (pattern_type, upper, lower) = if top {
+        (ChartPatternType::TripleTop, extremes_line, neckline)
+    } else {
+        (ChartPatternType::TripleBottom, neckline, extremes_line)
+    };
+    Some(ChartPattern {
+        pattern_type,
+        start_time,
+        end_time,
+        upper,
+        lower,
+        breakout: neckline.end_price,
+        bullish: !top,
+        quality: level_quality(&extremes, tolerance),
+    })
+}
+
+fn head_and_shoulders(window: &[TopBottom], tolerance: f64) -> Option<ChartPattern> {
+    let top = window[0].type_p == TopBottomType::Top;
+    let sign = if top { 1. } else { -1. };
+    let shoulders = [price(&window[0]), price(&window[4])];
+    let head = price(&window[2]);
+    let necks = [price(&window[1]), price(&window[3])];
+    let head_height = if top {
+        head - shoulders[0].max(shoulders[1])
+    } else {
+        shoulders[0].min(shoulders[1]) - head
+    };
+    let shoulders_height = sign * (mean(&shoulders) - mean(&necks));
+    if !same_level(&shoulders, 2. * tolerance)
+        || head_height <= tolerance * head
+        || shoulders_height <= tolerance * mean(&shoulders)
+    {
+        return None;
+    }
+
+    let (start_time, end_time) = (window[0].close_time, window[4].close_time);
+    let (shoulders_line, _) = TrendLine::fit(&[&window[0], &window[4]]);
+    let (neckline, _) = TrendLine::fit(&[&window[1], &window[3]]);
+    let neckline = neckline.extend(start_time, end_time);
+    let (pattern_type, upper, lower) = if top {
+        (ChartPatternType::HeadAndShoulders, shoulders_line, neckline)
+    } else {
+        (
+            ChartPatternType::InverseHeadAndShoulders,
+            neckline,
+            shoulders_line,
+        )
+    };
+    Some(ChartPattern {
+        pattern_type,
+        start_time,
+        end_time,
+        upper,
+        lower,
+        breakout: neckline.end_price,
+        bullish: !top,
+        quality: level_quality(&shoulders, 2. * tolerance),
+    })
+}
+
+/// Triangles, wedges, channels and flags from the trendlines of tops and bottoms
+fn trendlines_pattern(
+    window: &[TopBottom],
+    pole_opt: Option<&TopBottom>,
+    tolerance: f64,
+) -> Option<ChartPattern> {
+    let (tops, bottoms): (Vec<_>, Vec<_>) =
+        window.iter().partition(|s| s.type_p == TopBottomType::Top);
+    let (start_time, end_time) = (window[0].close_time, window[4].close_time);
+    let (upper, upper_error) = TrendLine::fit(&tops);
+    let (lower, lower_error) = TrendLine::fit(&bottoms);
+    let upper = upper.extend(start_time, end_time);
+    let lower = lower.extend(start_time, end_time);
+
+    let start_gap = upper.start_price - lower.start_price;
+    let end_gap = upper.end_price - lower.end_price;
+    if start_gap <= 0. || end_gap <= 0. {
+        return None;
+    }
+
+    let mean_price = mean(&window.iter().map(price).collect::<Vec<_>>());
+    let upper_change = (upper.end_price - upper.start_price) / mean_price;
+    let lower_change = (lower.end_price - lower.start_price) / mean_price;
+    let rising = |change: f64| change > tolerance;
+    let falling = |change: f64| change < -tolerance;
+    let flat = |change: f64| change.abs() <= tolerance;
+    // Continuation of the move into the first swing
+    let trend_up = window[0].type_p == TopBottomType::Top;
+
+    let (pattern_type, bullish) = if end_gap < start_gap * CONVERGING_RATIO {
+        if flat(upper_change) && rising(lower_change) {
+            (ChartPatternType::AscendingTriangle, true)
+        } else if falling(upper_change) && flat(lower_change) {
+            (ChartPatternType::DescendingTriangle, false)
+        } else if falling(upper_change) && rising(lower_change) {
+            (ChartPatternType::SymmetricTriangle, trend_up)
+        } else if rising(upper_change) && rising(lower_change) {
+            (ChartPatternType::RisingWedge, false)
+        } else if falling(upper_change) && falling(lower_change) {
+            (ChartPatternType::FallingWedge, true)
+        } else {
+            return None;
+        }
+    } else if (end_gap - start_gap).abs() <= start_gap * PARALLEL_RATIO {
+        let pole = pole_opt
+            .map(|p| price(&window[0]) - price(p))
+            .filter(|pole| pole.abs() >= start_gap * POLE_RATIO);
+        match pole {
+            Some(pole) if pole > 0. && !rising(upper_change) && !rising(lower_change) => {
+                (ChartPatternType::BullFlag, true)
+            }
+            Some(pole) if pole < 0. && !falling(upper_change) && !falling(lower_change) => {
+                (ChartPatternType::BearFlag, false)
+            }
+            _ if rising(upper_change) && rising(lower_change) => {
+                (ChartPatternType::AscendingChannel, true)
+            }
+            _ if falling(upper_change) && falling(lower_change) => {
+                (ChartPatternType::DescendingChannel, false)
+            }
+            _ if flat(upper_change) && flat(lower_change) => {
+                (ChartPatternType::HorizontalChannel, trend_up)
+            }
+            _ => return None,
+        }
+    } else {
+        return None;
+    };
+
+    let start_time = pole_opt
+        .filter(|_| {
+            matches!(
+                pattern_type,
+                ChartPatternType::BullFlag | ChartPatternType::BearFlag
+            )
+        })
+        .map(|p| p.close_time)
+        .unwrap_or(start_time);
+    Some(ChartPattern {
+        pattern_type,
+        start_time,
+        end_time,
+        upper,
+        lower,
+        breakout: if bullish {
+            upper.end_price
+        } else {
+            lower.end_price
+        },
+        bullish,
+        quality: (1. - (upper_error + lower_error) / start_gap).clamp(0., 1.),
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::utils::date_utils::str_to_datetime;
+    use chrono::Duration;
+    use rust_decimal::Decimal;
+
+    fn swings(prices: &[i64], first_top: bool) -> Vec<TopBottom> {
+        let start = str_to_datetime("2020-01-12 12:00:00");
+        prices
+            .iter()
+            .enumerate()
+            .map(|(i, price)| {
+                let type_p = if (i % 2 == 0) == first_top {
+                    TopBottomType::Top
+                } else {
+                    TopBottomType::Bottom
+                };
+                TopBottom::new(
+                    type_p,
+                    start + Duration::hours(i as i64),
+                    Decimal::from(*price),
+                )
+            })
+            .collect()
+    }
+
+    fn last_types(prices: &[i64], first_top: bool) -> Vec<ChartPatternType> {
+        last_chart_patterns(&swings(prices, first_top), PATTERN_TOLERANCE)
+            .iter()
+            .map(|p| p.pattern_type)
+            .collect()
+    }
+
+    #[test]
+    fn chart_pattern_test() {
+        assert_eq!(
+            last_types(&[100, 90, 100], true),
+            vec![ChartPatternType::DoubleTop]
+        );
+        assert_eq!(
+            last_types(&[90, 100, 90], false),
+            vec![ChartPatternType::DoubleBottom]
+        );
+        assert!(last_types(&[100, 90, 110], true).is_empty());
+
+        let head_and_shoulders = last_types(&[100, 90, 110, 90, 100], true);
+        assert!(head_and_shoulders.contains(&ChartPatternType::HeadAndShoulders));
+        let inverse = last_types(&[90, 100, 80, 100, 90], false);
+        assert!(inverse.contains(&ChartPatternType::InverseHeadAndShoulders));
+        let triple = last_types(&[100, 90, 100, 90, 100], true);
+        assert!(triple.contains(&ChartPatternType::TripleTop));
+        assert!(triple.contains(&ChartPatternType::HorizontalChannel));
+
+        assert_eq!(
+            last_types(&[100, 80, 100, 88, 100], true),
+            vec![
+                ChartPatternType::DoubleTop,
+                ChartPatternType::AscendingTriangle
+            ]
+        );
+        assert!(last_types(&[110, 80, 100, 88, 96], true)
+            .contains(&ChartPatternType::SymmetricTriangle));
+        assert!(last_types(&[100, 90, 110, 100, 120], true)
+            .contains(&ChartPatternType::AscendingChannel));
+        let types = last_types(&[100, 80, 106, 92, 108], true);
+        assert!(types.contains(&ChartPatternType::RisingWedge));
+        // Descending channel after a strong move up
+        let types = last_types(&[50, 100, 90, 97, 87, 94], false);
+        assert!(types.contains(&ChartPatternType::BullFlag));
+
+        let pattern = last_chart_patterns(&swings(&[100, 80, 100, 88, 100], true), 0.01)
+            .into_iter()
+            .find(|p| p.pattern_type == ChartPatternType::AscendingTriangle)
+            .unwrap();
+        assert!(pattern.bullish);
+        assert!((pattern.breakout - 100.).abs() < 1e-9);
+        assert!((pattern.quality - 1.).abs() < 1e-9);
+
+        // Patterns of all swings, consecutive swings of same type are merged
+        let mut top_bottoms = swings(&[100, 90, 100], true);
+        top_bottoms.insert(1, top_bottoms[0].clone());
+        top_bottoms[1].close_time = top_bottoms[1].close_time + Duration::minutes(30);
+        let patterns = chart_patterns(&top_bottoms, PATTERN_TOLERANCE);
+        assert_eq!(patterns.len(), 1);
+    }
+}
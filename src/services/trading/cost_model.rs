@@ -0,0 +1,187 @@
+use crate::model::candle::Candle;
+use crate::model::operation::Operation;
+use crate::model::price::Price;
+use eyre::bail;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::str::FromStr;
+
+/// Fee rate discount when fees are paid with BNB
+pub const BNB_DISCOUNT: Decimal = dec!(0.25);
+
+/// Price moved against the order, as a fraction of the price
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Slippage {
+    None,
+    /// Fixed fraction of the price
+    Fixed(Decimal),
+    /// Factor of the candle high low range relative to its close
+    Volatility(Decimal),
+    /// Factor of the order quantity relative to the candle volume
+    Volume(Decimal),
+}
+
+/// Parses `none`, `fixed:<rate>`, `volatility:<factor>` or `volume:<factor>`
+impl FromStr for Slippage {
+    type Err = eyre::Error;
+
+    fn from_str(value: &str) -> eyre::Result<Self> {
+        let slippage = match value.split_once(':') {
+            None if value == "none" => Slippage::None,
+            Some(("fixed", rate)) => Slippage::Fixed(Decimal::from_str(rate)?),
+            Some(("volatility", factor)) => Slippage::Volatility(Decimal::from_str(factor)?),
+            Some(("volume", factor)) => Slippage::Volume(Decimal::from_str(factor)?),
+            _ => bail!("Unknown slippage {}", value),
+        };
+        match slippage {
+            Slippage::Fixed(rate) | Slippage::Volatility(rate) | Slippage::Volume(rate)
+                if rate < dec!(0) =>
+            {
+                bail!("Slippage {} can't be negative", value)
+            }
+            _ => Ok(slippage),
+        }
+    }
+}
+
+/// Costs of filling an operation on the exchange
+#[derive(Clone, Copy, Debug)]
+pub struct CostModel {
+    pub maker_fee: Decimal,
+    pub taker_fee: Decimal,
+    pub bnb_discount: bool,
+    pub slippage: Slippage,
+    /// Orders with a lower total are rejected
+    pub min_notional: Decimal,
+}
+
+/// Operation filled at a price with its fee in fiat
+#[derive(Clone, Copy, Debug)]
+pub struct Fill {
+    pub price: Price,
+    pub fee_rate: Decimal,
+}
+
+impl CostModel {
+    pub fn new(
+        maker_fee: Decimal,
+        taker_fee: Decimal,
+        bnb_discount: bool,
+        slippage: Slippage,
+        min_notional: Decimal,
+    ) -> Self {
+        Self {
+            maker_fee,
+            taker_fee,
+            bnb_discount,
+            slippage,
+            min_notional,
+        }
+    }
+
+    pub fn fee_rate(&self, is_maker: bool) -> Decimal {
+        let rate = if is_maker {
+            self.maker_fee
+        } else {
+            self.taker_fee
+        };
+        if self.bnb_discount {
+            rate * (dec!(1) - BNB_DISCOUNT)
+        } else {
+            rate
+        }
+    }
+
+    /// Slippage fraction of the operation in the candle
+    pub fn slippage_rate(&self, operation: &Operation, candle: &Candle) -> Decimal {
+        match self.slippage {
+            Slippage::None => dec!(0),
+            Slippage::Fixed(rate) => rate,
+            Slippage::Volatility(factor) if candle.close > dec!(0) => {
+                factor * (candle.high - candle.low) / candle.close
+            }
+            Slippage::Volume(factor) if candle.volume > dec!(0) => {
+                factor * operation.quantity().0 / candle.volume
+            }
+            Slippage::Volatility(_) | Slippage::Volume(_) => dec!(0),
+        }
+    }
+
+    /// Taker fill of the operation at the price moved against it
+    pub fn fill(&self, operation: &Operation, price: Price, candle: &Candle) -> Fill {
+        let slippage_rate = self.slippage_rate(operation, candle);
+        let price = if operation.is_buy() {
+            price.0 * (dec!(1) + slippage_rate)
+        } else {
+            price.0 * (dec!(1) - slippage_rate)
+        };
+        Fill {
+            price: Price(price),
+            fee_rate: self.fee_rate(false),
+        }
+    }
+
+    /// Fill of a pending order at its price, makers don't slip
+    pub fn order_fill(
+        &self,
+        operation: &Operation,
+        price: Price,
+        is_maker: bool,
+        candle: &Candle,
+    ) -> Fill {
+        if is_maker {
+            Fill {
+                price,
+                fee_rate: self.fee_rate(true),
+            }
+        } else {
+            self.fill(operation, price, candle)
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::model::quantity::Quantity;
+    use crate::utils::candles_utils::tests::candle_at;
+
+    #[test]
+    fn cost_model_test() {
+        let candle = candle_at(0, dec!(100), dec!(104), dec!(96), dec!(100), dec!(50));
+        let buy = Operation::Buy(Quantity(dec!(1)));
+        let sell = Operation::Sell(Quantity(dec!(1)));
+
+        let cost_model = CostModel::new(dec!(0.001), dec!(0.002), true, Slippage::None, dec!(10));
+        assert_eq!(cost_model.fee_rate(true), dec!(0.00075));
+        let fill = cost_model.fill(&buy, Price(dec!(100)), &candle);
+        assert_eq!((fill.price.0, fill.fee_rate), (dec!(100), dec!(0.0015)));
+
+        let volatility = CostModel {
+            slippage: Slippage::Volatility(dec!(0.1)),
+            ..cost_model
+        };
+        assert_eq!(
+            volatility.fill(&buy, Price(dec!(100)), &candle).price.0,
+            dec!(100.8)
+        );
+        assert_eq!(
+            volatility.fill(&sell, Price(dec!(100)), &candle).price.0,
+            dec!(99.2)
+        );
+
+        let volume = CostModel {
+            slippage: Slippage::Volume(dec!(0.5)),
+            ..cost_model
+        };
+        assert_eq!(volume.slippage_rate(&sell, &candle), dec!(0.01));
+        let maker = volume.order_fill(&sell, Price(dec!(100)), true, &candle);
+        assert_eq!((maker.price.0, maker.fee_rate), (dec!(100), dec!(0.00075)));
+
+        assert_eq!(
+            Slippage::from_str("fixed:0.001").unwrap(),
+            Slippage::Fixed(dec!(0.001))
+        );
+        assert!(Slippage::from_str("fixed:-1").is_err());
+    }
+}
@@ -0,0 +1,87 @@
+use crate::model::candle::Candle;
+use crate::model::price::Price;
+use chrono::{DateTime, Duration, Utc};
+use eyre::bail;
+use std::str::FromStr;
+
+/// When the operations decided at a candle close are filled
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Execution {
+    /// At the same candle close
+    Close,
+    /// At the next candle open after the latency
+    NextOpen(Duration),
+}
+
+/// Parses `close`, `next_open` or `next_open:<latency seconds>`
+impl FromStr for Execution {
+    type Err = eyre::Error;
+
+    fn from_str(value: &str) -> eyre::Result<Self> {
+        match value.split_once(':') {
+            None if value == "close" => Ok(Execution::Close),
+            None if value == "next_open" => Ok(Execution::NextOpen(Duration::zero())),
+            Some(("next_open", seconds)) => match seconds.parse::<i64>()? {
+                seconds if seconds >= 0 => Ok(Execution::NextOpen(Duration::seconds(seconds))),
+                _ => bail!("Execution {} can't have a negative latency", value),
+            },
+            _ => bail!("Unknown execution {}", value),
+        }
+    }
+}
+
+impl Execution {
+    pub fn latency(&self) -> Duration {
+        match self {
+            Execution::Close => Duration::zero(),
+            Execution::NextOpen(latency) => *latency,
+        }
+    }
+}
+
+/// Time and price after the latency from the candle open, the open of the path candle reached
+pub fn open_after(candle: &Candle, path: &[Candle], latency: Duration) -> (DateTime<Utc>, Price) {
+    let time = candle.open_time + latency;
+    let step = path
+        .iter()
+        .find(|c| c.close_time >= time)
+        .or_else(|| path.last())
+        .unwrap_or(candle);
+    (time.max(step.open_time), Price(step.open))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::utils::candles_utils::tests::timeframe_candle;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn execution_test() {
+        assert_eq!(Execution::from_str("close").unwrap(), Execution::Close);
+        assert_eq!(
+            Execution::from_str("next_open:90").unwrap().latency(),
+            Duration::seconds(90)
+        );
+        assert!(Execution::from_str("next_open:-1").is_err());
+
+        let path = (0..15)
+            .map(|i| {
+                let open = Decimal::from(100 + i);
+                timeframe_candle(1, i, open, dec!(120), dec!(90), dec!(100), dec!(10))
+            })
+            .collect::<Vec<_>>();
+        let bar = timeframe_candle(15, 0, dec!(100), dec!(120), dec!(90), dec!(100), dec!(150));
+        let open_time = bar.open_time;
+
+        assert_eq!(
+            open_after(&bar, &[bar], Duration::zero()),
+            (open_time, Price(dec!(100)))
+        );
+        assert_eq!(
+            open_after(&bar, &path, Duration::seconds(90)),
+            (open_time + Duration::seconds(90), Price(dec!(101)))
+        );
+    }
+}
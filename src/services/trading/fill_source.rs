@@ -0,0 +1,112 @@
+use crate::model::candle::Candle;
+use crate::model::trade_agg::TradeAgg;
+use eyre::bail;
+use std::str::FromStr;
+
+/// Prices resolving the pending order fills inside each candle of the selection
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FillSource {
+    /// High and low of the candle
+    Bar,
+    /// Candles of a lower timeframe in minutes
+    Candles(i32),
+    /// Aggregated trades
+    Trades,
+}
+
+/// Parses `bar`, `<minutes>m` or `trades`
+impl FromStr for FillSource {
+    type Err = eyre::Error;
+
+    fn from_str(value: &str) -> eyre::Result<Self> {
+        match value {
+            "bar" => Ok(FillSource::Bar),
+            "trades" => Ok(FillSource::Trades),
+            _ => match value.strip_suffix('m').map(str::parse::<i32>) {
+                Some(Ok(minutes)) if minutes > 0 => Ok(FillSource::Candles(minutes)),
+                _ => bail!("Unknown fill source {}", value),
+            },
+        }
+    }
+}
+
+/// Lower timeframe candles inside the bar, the bar itself when there are none
+pub fn bar_path<'a>(bar: &'a Candle, lower: &'a [Candle]) -> &'a [Candle] {
+    let start = lower.partition_point(|c| c.open_time < bar.open_time);
+    let end = lower.partition_point(|c| c.close_time <= bar.close_time);
+    if start >= end {
+        std::slice::from_ref(bar)
+    } else {
+        &lower[start..end]
+    }
+}
+
+/// Trades inside the bar, from trades sorted by time
+pub fn bar_trades<'a>(bar: &Candle, trades: &'a [TradeAgg]) -> &'a [TradeAgg] {
+    let start = trades.partition_point(|t| t.time < bar.open_time);
+    let end = trades.partition_point(|t| t.time <= bar.close_time);
+    &trades[start..end.max(start)]
+}
+
+/// Trades as candles of a single price at the trade time
+pub fn trade_candles(bar: &Candle, trades: &[TradeAgg]) -> Vec<Candle> {
+    trades
+        .iter()
+        .map(|t| Candle {
+            open_time: t.time,
+            close_time: t.time,
+            open: t.price,
+            high: t.price,
+            low: t.price,
+            close: t.price,
+            volume: t.quantity,
+            ..*bar
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::utils::candles_utils::tests::timeframe_candle;
+    use chrono::Duration;
+    use rust_decimal_macros::dec;
+
+    fn candles(minutes: i64, count: i64) -> Vec<Candle> {
+        let price = dec!(100);
+        (0..count)
+            .map(|i| timeframe_candle(minutes, i, price, price, price, price, dec!(10)))
+            .collect()
+    }
+
+    #[test]
+    fn bar_path_test() {
+        let bars = candles(15, 3);
+        let lower = candles(1, 30);
+        let path = bar_path(&bars[1], &lower);
+        assert_eq!(path.len(), 15);
+        assert_eq!(path[0].open_time, bars[1].open_time);
+        assert_eq!(path[14].close_time, bars[1].close_time);
+        assert_eq!(bar_path(&bars[2], &lower), &bars[2..]);
+
+        assert_eq!(FillSource::from_str("1m").unwrap(), FillSource::Candles(1));
+        assert_eq!(FillSource::from_str("trades").unwrap(), FillSource::Trades);
+        assert!(FillSource::from_str("0m").is_err());
+    }
+
+    #[test]
+    fn bar_trades_test() {
+        let bars = candles(15, 3);
+        let trades = (0..45)
+            .map(|i| {
+                let time = bars[0].open_time + Duration::minutes(i);
+                TradeAgg::new(i, 1, dec!(1), dec!(100), time)
+            })
+            .collect::<Vec<_>>();
+        let inside = bar_trades(&bars[1], &trades);
+        assert_eq!(inside.len(), 15);
+        assert_eq!(inside[0].time, bars[1].open_time);
+        assert_eq!(trade_candles(&bars[1], inside).len(), 15);
+        assert!(bar_trades(&bars[1], &trades[..10]).is_empty());
+    }
+}
@@ -1,31 +1,72 @@
 use crate::model::flow::Flow;
-use crate::model::operation::Operation;
+use crate::model::funding_rate::FundingRate;
 use crate::model::position::Position;
+use crate::model::price::Price;
+use crate::model::quantity::Quantity;
 use crate::repository::flow_repository::FlowRepository;
+use crate::services::trading::lots::LotClose;
 use crate::services::trading::trade_operation::TradeOperation;
 use crate::utils::dec_utils::percent;
 use colored::Colorize;
 use ifmt::iformat;
-use log::info;
+use log::{log, Level};
 use pad::{Alignment, PadStr};
 use rust_decimal::Decimal;
 use rust_decimal::RoundingStrategy;
 use rust_decimal_macros::dec;
 
+/// Log of the funding payment flows
+pub const FUNDING_LOG: &str = "Funding";
+
 #[derive(Clone)]
 pub struct FlowRegister {
-    flow_repository: FlowRepository,
+    /// Flows kept in memory without it
+    flow_repository_opt: Option<FlowRepository>,
+    flows: Vec<Flow>,
     old_real_balance_usd: Decimal,
 }
 
 impl FlowRegister {
     pub fn new(flow_repository: FlowRepository) -> Self {
         Self {
-            flow_repository,
+            flow_repository_opt: Some(flow_repository),
+            flows: Vec::new(),
+            old_real_balance_usd: dec!(0),
+        }
+    }
+
+    /// Register of a back test not saved, logging the flows at debug level
+    pub fn in_memory() -> Self {
+        Self {
+            flow_repository_opt: None,
+            flows: Vec::new(),
             old_real_balance_usd: dec!(0),
         }
     }
 
+    /// Flows registered in memory
+    pub fn flows(&self) -> &[Flow] {
+        &self.flows
+    }
+
+    fn insert_flow(&mut self, mut flow: Flow) -> eyre::Result<()> {
+        match self.flow_repository_opt.as_ref() {
+            Some(flow_repository) => {
+                flow_repository.insert_flow(&mut flow)?;
+            }
+            None => self.flows.push(flow),
+        }
+        Ok(())
+    }
+
+    fn log_level(&self) -> Level {
+        if self.flow_repository_opt.is_some() {
+            Level::Info
+        } else {
+            Level::Debug
+        }
+    }
+
     pub fn set_position_old(&mut self, position: &Position) {
         self.old_real_balance_usd = position.real_balance_fiat;
     }
@@ -34,27 +75,33 @@ impl FlowRegister {
         &mut self,
         position: &Position,
         trade_operation: &TradeOperation,
+        quantity: Quantity,
+        price: Price,
+        fee: Decimal,
+        lot_close_opt: Option<LotClose>,
     ) -> eyre::Result<()> {
         let gain_perc = percent(&position.real_balance_fiat, &self.old_real_balance_usd);
 
-        let (is_buyer_maker, quantity) = match trade_operation.operation {
-            Operation::Buy(quantity) => (true, quantity),
-            Operation::Sell(quantity) => (false, quantity),
-        };
-        let mut flow = Flow {
+        let is_buyer_maker = trade_operation.operation.is_buy();
+        let flow = Flow {
             id: 0,
             position: position.id,
             is_buyer_maker,
             time: trade_operation.now,
-            price: trade_operation.price.0,
+            price: price.0,
             quantity: quantity.0,
-            total: quantity.0 * trade_operation.price.0,
+            total: quantity.0 * price.0,
+            fee,
             real_balance_fiat_old: self.old_real_balance_usd,
             real_balance_fiat_new: position.real_balance_fiat,
             gain_perc,
             log: trade_operation.description_opt.clone(),
+            order_id: trade_operation.order_id_opt,
+            entry_price: lot_close_opt.map(|c| c.entry_price),
+            realized_pnl: lot_close_opt.map(|c| c.realized_pnl),
+            holding_seconds: lot_close_opt.map(|c| c.holding.num_seconds()),
         };
-        self.flow_repository.insert_flow(&mut flow)?;
+        self.insert_flow(flow)?;
 
         {
             let gain_usd = (position.real_balance_fiat - self.old_real_balance_usd)
@@ -83,13 +130,53 @@ impl FlowRegister {
 
             let message = iformat!(
                 "{trade_operation.now} {state_str} \
-            price {trade_operation.price} Balance USD {position.balance_asset_r()} \
+            price {price} fee {fee.round_dp(8)} Balance USD {position.balance_asset_r()} \
             Position USD {real_balance_fiat_str} \
             Gain USD {gain_usd_str} {gain_perc_str}"
             );
-            info!("{}", message);
+            log!(self.log_level(), "{}", message);
         }
 
         Ok(())
     }
+
+    /// Records the funding payment of the position, the total is the fiat received
+    pub fn set_funding(
+        &mut self,
+        position: &Position,
+        funding_rate: &FundingRate,
+        mark_price: Decimal,
+        payment: Decimal,
+    ) -> eyre::Result<()> {
+        let gain_perc = percent(&position.real_balance_fiat, &self.old_real_balance_usd);
+        let flow = Flow {
+            id: 0,
+            position: position.id,
+            is_buyer_maker: false,
+            time: funding_rate.time,
+            price: mark_price,
+            quantity: position.balance_asset,
+            total: -payment,
+            fee: dec!(0),
+            real_balance_fiat_old: self.old_real_balance_usd,
+            real_balance_fiat_new: position.real_balance_fiat,
+            gain_perc,
+            log: Some(FUNDING_LOG.to_string()),
+            order_id: None,
+            entry_price: None,
+            realized_pnl: None,
+            holding_seconds: None,
+        };
+        self.insert_flow(flow)?;
+
+        log!(
+            self.log_level(),
+            "{}",
+            iformat!(
+                "{funding_rate.time} Funding rate {funding_rate.rate} \
+            mark {mark_price.round_dp(2)} paid USD {payment.round_dp(8)}"
+            )
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,206 @@
+use chrono::{DateTime, Duration, Utc};
+use eyre::bail;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LotMode {
+    /// Closing fills close the oldest lots first
+    Fifo,
+    /// Opening fills merge into a single lot at the average cost
+    Average,
+}
+
+/// Parses `fifo` or `average`
+impl FromStr for LotMode {
+    type Err = eyre::Error;
+
+    fn from_str(value: &str) -> eyre::Result<Self> {
+        match value {
+            "fifo" => Ok(LotMode::Fifo),
+            "average" => Ok(LotMode::Average),
+            _ => bail!("Unknown lot mode {}", value),
+        }
+    }
+}
+
+/// Quantity opened at a price, negative for shorts
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lot {
+    pub quantity: Decimal,
+    pub price: Decimal,
+    pub time: DateTime<Utc>,
+    /// Back test candle of the opening fill
+    pub bar: i64,
+}
+
+/// Lots closed by a fill
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LotClose {
+    /// Average entry price of the closed quantity
+    pub entry_price: Decimal,
+    /// P&L of the closed quantity before fees
+    pub realized_pnl: Decimal,
+    /// Holding time of the closed lots weighted by their quantity
+    pub holding: Duration,
+}
+
+/// Open lots of a position
+#[derive(Clone, Debug)]
+pub struct Lots {
+    mode: LotMode,
+    lots: VecDeque<Lot>,
+}
+
+impl Lots {
+    pub fn new(mode: LotMode) -> Self {
+        Self {
+            mode,
+            lots: VecDeque::new(),
+        }
+    }
+
+    /// Average price of the open lots, 0 when flat
+    pub fn entry_price(&self) -> Decimal {
+        let quantity: Decimal = self.lots.iter().map(|l| l.quantity).sum();
+        if quantity == dec!(0) {
+            return dec!(0);
+        }
+        self.lots
+            .iter()
+            .map(|l| l.quantity * l.price)
+            .sum::<Decimal>()
+            / quantity
+    }
+
+    pub fn unrealized_pnl(&self, price: Decimal) -> Decimal {
+        self.lots
+            .iter()
+            .map(|l| l.quantity * (price - l.price))
+            .sum()
+    }
+
+    /// Candles since the oldest open lot, 0 when flat
+    pub fn bars_in_trade(&self, bar: i64) -> i64 {
+        self.lots.front().map(|l| bar - l.bar).unwrap_or(0)
+    }
+
+    /// Adds the asset change at the price, negative when selling, closing the lots of the other
+    /// side first and opening a lot with the rest, returns None when nothing was closed
+    pub fn fill(
+        &mut self,
+        quantity: Decimal,
+        price: Decimal,
+        time: DateTime<Utc>,
+        bar: i64,
+    ) -> Option<LotClose> {
+        let mut remaining = quantity;
+        let mut closed = dec!(0);
+        let mut cost = dec!(0);
+        let mut realized_pnl = dec!(0);
+        let mut holding_seconds = dec!(0);
+
+        while remaining != dec!(0) {
+            let lot = match self.lots.front_mut() {
+                Some(lot) if lot.quantity.is_sign_negative() != remaining.is_sign_negative() => lot,
+                _ => break,
+            };
+            let close = remaining.abs().min(lot.quantity.abs());
+            let signed_close = if lot.quantity > dec!(0) {
+                close
+            } else {
+                -close
+            };
+            closed += close;
+            cost += close * lot.price;
+            realized_pnl += signed_close * (price - lot.price);
+            holding_seconds += close * Decimal::from((time - lot.time).num_seconds());
+            lot.quantity -= signed_close;
+            remaining += signed_close;
+            if lot.quantity == dec!(0) {
+                self.lots.pop_front();
+            }
+        }
+
+        if remaining != dec!(0) {
+            match (self.mode, self.lots.back_mut()) {
+                (LotMode::Average, Some(lot)) => {
+                    let quantity = lot.quantity + remaining;
+                    lot.price = (lot.quantity * lot.price + remaining * price) / quantity;
+                    lot.quantity = quantity;
+                }
+                _ => self.lots.push_back(Lot {
+                    quantity: remaining,
+                    price,
+                    time,
+                    bar,
+                }),
+            }
+        }
+
+        if closed == dec!(0) {
+            return None;
+        }
+        Some(LotClose {
+            entry_price: cost / closed,
+            realized_pnl,
+            holding: Duration::seconds((holding_seconds / closed).to_i64().unwrap_or(0)),
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::utils::date_utils::str_to_datetime;
+
+    #[test]
+    fn lots_test() {
+        let time = str_to_datetime("2020-01-12 12:00:00");
+        let hours = |h: i64| time + Duration::hours(h);
+
+        let mut fifo = Lots::new(LotMode::Fifo);
+        let mut average = Lots::new(LotMode::Average);
+        for lots in [&mut fifo, &mut average].iter_mut() {
+            assert_eq!(lots.fill(dec!(1), dec!(100), hours(0), 0), None);
+            assert_eq!(lots.fill(dec!(1), dec!(110), hours(1), 1), None);
+            assert_eq!(lots.entry_price(), dec!(105));
+            assert_eq!(lots.unrealized_pnl(dec!(120)), dec!(30));
+        }
+
+        // FIFO closes the first lot at 100
+        let close = fifo.fill(dec!(-1), dec!(120), hours(3), 3).unwrap();
+        assert_eq!(
+            (close.entry_price, close.realized_pnl),
+            (dec!(100), dec!(20))
+        );
+        assert_eq!(close.holding, Duration::hours(3));
+        assert_eq!(fifo.entry_price(), dec!(110));
+        assert_eq!(fifo.bars_in_trade(5), 4);
+
+        // Average closes at the average cost and keeps it
+        let close = average.fill(dec!(-1), dec!(120), hours(3), 3).unwrap();
+        assert_eq!(
+            (close.entry_price, close.realized_pnl),
+            (dec!(105), dec!(15))
+        );
+        assert_eq!(average.entry_price(), dec!(105));
+        assert_eq!(average.bars_in_trade(5), 5);
+
+        // Reversal closes the long and opens a short with the rest
+        let close = average.fill(dec!(-3), dec!(90), hours(4), 4).unwrap();
+        assert_eq!(
+            (close.entry_price, close.realized_pnl),
+            (dec!(105), dec!(-15))
+        );
+        assert_eq!(average.entry_price(), dec!(90));
+        assert_eq!(average.unrealized_pnl(dec!(80)), dec!(20));
+        assert_eq!(average.bars_in_trade(5), 1);
+
+        assert_eq!(LotMode::from_str("fifo").unwrap(), LotMode::Fifo);
+        assert!(LotMode::from_str("lifo").is_err());
+    }
+}
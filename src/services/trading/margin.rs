@@ -0,0 +1,114 @@
+use eyre::bail;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MarginMode {
+    /// The position only risks its initial margin
+    Isolated,
+    /// The position risks the whole balance
+    Cross,
+}
+
+/// Parses `isolated` or `cross`
+impl FromStr for MarginMode {
+    type Err = eyre::Error;
+
+    fn from_str(value: &str) -> eyre::Result<Self> {
+        match value {
+            "isolated" => Ok(MarginMode::Isolated),
+            "cross" => Ok(MarginMode::Cross),
+            _ => bail!("Unknown margin mode {}", value),
+        }
+    }
+}
+
+/// Futures margin allowing short and leveraged positions
+#[derive(Clone, Copy, Debug)]
+pub struct Margin {
+    pub leverage: Decimal,
+    pub mode: MarginMode,
+    /// Fraction of the position value the equity must keep to avoid liquidation
+    pub maintenance_rate: Decimal,
+}
+
+impl Margin {
+    pub fn new(leverage: Decimal, mode: MarginMode, maintenance_rate: Decimal) -> Self {
+        Self {
+            leverage,
+            mode,
+            maintenance_rate,
+        }
+    }
+
+    /// Largest absolute position, in asset, the equity allows at the price
+    pub fn max_position(&self, equity: Decimal, price: Decimal) -> Decimal {
+        if price <= dec!(0) {
+            return dec!(0);
+        }
+        (equity * self.leverage / price).max(dec!(0))
+    }
+
+    /// Price where the position equity falls to the maintenance margin, `asset` is negative for
+    /// shorts, `balance_fiat` is the fiat balance including short sales and borrowed fiat
+    pub fn liquidation_price(
+        &self,
+        asset: Decimal,
+        entry_price: Decimal,
+        balance_fiat: Decimal,
+    ) -> Option<Decimal> {
+        if asset == dec!(0) || self.leverage <= dec!(0) {
+            return None;
+        }
+        let collateral = match self.mode {
+            MarginMode::Isolated => asset.abs() * entry_price / self.leverage,
+            MarginMode::Cross => balance_fiat + asset * entry_price,
+        };
+        // collateral + asset * (price - entry_price) = maintenance_rate * |asset| * price
+        let divisor = asset - self.maintenance_rate * asset.abs();
+        if divisor == dec!(0) {
+            return None;
+        }
+        let price = (asset * entry_price - collateral) / divisor;
+        if price > dec!(0) {
+            Some(price)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn liquidation_price_test() {
+        let cross = Margin::new(dec!(10), MarginMode::Cross, dec!(0.004));
+        // 10 of balance, long 1 at 100 borrowing 90
+        let long = cross
+            .liquidation_price(dec!(1), dec!(100), dec!(-90))
+            .unwrap();
+        assert_eq!(long.round_dp(2), dec!(90.36));
+        // 10 of balance, short 1 at 100
+        let short = cross
+            .liquidation_price(dec!(-1), dec!(100), dec!(110))
+            .unwrap();
+        assert_eq!(short.round_dp(2), dec!(109.56));
+        // Without borrowing a long is never liquidated
+        assert!(cross
+            .liquidation_price(dec!(1), dec!(100), dec!(0))
+            .is_none());
+
+        let isolated = Margin {
+            mode: MarginMode::Isolated,
+            ..cross
+        };
+        let long = isolated
+            .liquidation_price(dec!(1), dec!(100), dec!(500))
+            .unwrap();
+        assert_eq!(long.round_dp(2), dec!(90.36));
+        assert_eq!(isolated.max_position(dec!(10), dec!(100)), dec!(1));
+    }
+}
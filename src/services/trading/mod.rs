@@ -1,6 +1,13 @@
+pub mod chart_pattern;
+pub mod cost_model;
+pub mod execution;
+pub mod fill_source;
 pub mod flow_register;
+pub mod lots;
+pub mod margin;
+pub mod order_book;
+pub mod performance;
 pub mod running_script_state;
-pub mod top_bottom_triangle;
 pub mod trade_context;
 pub mod trade_context_provider;
 pub mod trade_context_provider_factory;
@@ -0,0 +1,326 @@
+use crate::model::candle::Candle;
+use crate::model::order::{Order, OrderAction, OrderKind, TimeInForce};
+use crate::model::price::Price;
+use log::warn;
+
+/// Pending order crossed by a candle
+#[derive(Clone, Debug)]
+pub struct OrderFill {
+    pub order: Order,
+    pub price: Price,
+    /// Resting orders fill as maker, triggered stops as taker
+    pub is_maker: bool,
+}
+
+/// Simulated pending orders of a position
+#[derive(Clone, Debug)]
+pub struct OrderBook {
+    orders: Vec<Order>,
+    next_id: i32,
+}
+
+impl OrderBook {
+    pub fn new() -> Self {
+        Self {
+            orders: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Id of the next placed order
+    pub fn next_id(&self) -> i32 {
+        self.next_id
+    }
+
+    pub fn orders(&self) -> &[Order] {
+        &self.orders
+    }
+
+    pub fn apply(&mut self, action: OrderAction) {
+        match action {
+            OrderAction::Place(order) => {
+                self.next_id = self.next_id.max(order.id + 1);
+                self.orders.push(order);
+            }
+            OrderAction::Cancel(id) => self.orders.retain(|o| o.id != id),
+            OrderAction::CancelAll => self.orders.clear(),
+            OrderAction::Replace {
+                id,
+                new_id,
+                quantity,
+                price,
+            } => {
+                let order = match self.orders.iter().position(|o| o.id == id) {
+                    Some(index) => self.orders.remove(index),
+                    None => {
+                        warn!("Order {} to replace not found!", id);
+                        return;
+                    }
+                };
+                let kind = match order.kind {
+                    OrderKind::Limit(_) => OrderKind::Limit(price),
+                    OrderKind::Stop(_) => OrderKind::Stop(price),
+                    OrderKind::StopLimit { stop, .. } => {
+                        OrderKind::StopLimit { stop, limit: price }
+                    }
+                };
+                let operation = order.operation.with_quantity(quantity);
+                self.next_id = self.next_id.max(new_id + 1);
+                self.orders.push(Order {
+                    id: new_id,
+                    operation,
+                    kind,
+                    ..order
+                });
+            }
+        }
+    }
+
+    /// Removes and returns the orders crossed by the candle high and low, in placement order,
+    /// expired orders are dropped, IOC orders are kept until the whole bar is checked. Orders
+    /// placed after the candle opened (e.g. after a latency) wait for a later candle, as its
+    /// high and low may precede them.
+    pub fn fill(&mut self, candle: &Candle) -> Vec<OrderFill> {
+        let mut fills = Vec::new();
+        let mut pending = Vec::with_capacity(self.orders.len());
+
+        for mut order in self.orders.drain(..) {
+            if order.time > candle.open_time {
+                pending.push(order);
+                continue;
+            }
+            if let TimeInForce::Gtt(expire) = order.time_in_force {
+                if candle.open_time >= expire {
+                    continue;
+                }
+            }
+
+            if let Some((price, is_maker)) = cross(&mut order, candle) {
+                fills.push(OrderFill {
+                    order,
+                    price,
+                    is_maker,
+                });
+            } else {
+                pending.push(order);
+            }
+        }
+
+        self.orders = pending;
+        fills
+    }
+
+    /// Drops the IOC orders the path inside the bar could fill but didn't, the last path candle
+    /// opened at or after their placement
+    pub fn expire_ioc(&mut self, last_step: &Candle) {
+        self.orders
+            .retain(|o| o.time_in_force != TimeInForce::Ioc || o.time > last_step.open_time);
+    }
+}
+
+impl Default for OrderBook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Fill price of the order in the candle, a triggered stop limit becomes a limit order
+fn cross(order: &mut Order, candle: &Candle) -> Option<(Price, bool)> {
+    let is_buy = order.operation.is_buy();
+    // Price moving up to reach a buy stop or a sell limit
+    let reaches_above = |price: Price| candle.high >= price.0;
+    let reaches_below = |price: Price| candle.low <= price.0;
+    // Stops fill at market, at the open when it gapped past them
+    let stop_price = |stop: Price| {
+        if is_buy {
+            Price(candle.open.max(stop.0))
+        } else {
+            Price(candle.open.min(stop.0))
+        }
+    };
+
+    match order.kind {
+        OrderKind::Limit(limit) => {
+            let crossed = if is_buy {
+                reaches_below(limit)
+            } else {
+                reaches_above(limit)
+            };
+            crossed.then_some((limit, true))
+        }
+        OrderKind::Stop(stop) => {
+            let crossed = if is_buy {
+                reaches_above(stop)
+            } else {
+                reaches_below(stop)
+            };
+            crossed.then(|| (stop_price(stop), false))
+        }
+        OrderKind::StopLimit { stop, limit } => {
+            let triggered = if is_buy {
+                reaches_above(stop)
+            } else {
+                reaches_below(stop)
+            };
+            if !triggered {
+                return None;
+            }
+            order.kind = OrderKind::Limit(limit);
+
+            let trigger = stop_price(stop);
+            let (marketable, crossed) = if is_buy {
+                (trigger.0 <= limit.0, reaches_below(limit))
+            } else {
+                (trigger.0 >= limit.0, reaches_above(limit))
+            };
+            if marketable {
+                Some((trigger, false))
+            } else {
+                crossed.then_some((limit, true))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::model::operation::Operation;
+    use crate::model::quantity::Quantity;
+    use crate::utils::candles_utils::tests::{candle_at, timeframe_candle};
+    use chrono::Duration;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    fn candle(i: i64, open: i64, high: i64, low: i64, close: i64) -> Candle {
+        candle_at(
+            i,
+            Decimal::from(open),
+            Decimal::from(high),
+            Decimal::from(low),
+            Decimal::from(close),
+            dec!(10),
+        )
+    }
+
+    fn place(book: &mut OrderBook, operation: Operation, kind: OrderKind, tif: TimeInForce) -> i32 {
+        let id = book.next_id();
+        let time = candle(0, 0, 0, 0, 0).close_time;
+        book.apply(OrderAction::Place(Order::new(
+            id, operation, kind, tif, time, None,
+        )));
+        id
+    }
+
+    #[test]
+    fn order_book_test() {
+        let buy = Operation::Buy(Quantity(dec!(1)));
+        let sell = Operation::Sell(Quantity(dec!(1)));
+        let mut book = OrderBook::new();
+        let limit = place(
+            &mut book,
+            buy,
+            OrderKind::Limit(Price(dec!(95))),
+            TimeInForce::Gtc,
+        );
+        let stop = place(
+            &mut book,
+            sell,
+            OrderKind::Stop(Price(dec!(90))),
+            TimeInForce::Gtc,
+        );
+        place(
+            &mut book,
+            buy,
+            OrderKind::Limit(Price(dec!(80))),
+            TimeInForce::Ioc,
+        );
+        let expire = candle(2, 0, 0, 0, 0).open_time;
+        place(
+            &mut book,
+            buy,
+            OrderKind::Limit(Price(dec!(80))),
+            TimeInForce::Gtt(expire),
+        );
+        assert_eq!(book.next_id(), 5);
+
+        // Placement candle doesn't fill
+        assert!(book.fill(&candle(0, 100, 100, 50, 100)).is_empty());
+
+        // Limit filled at its price as maker, IOC not filled dropped after the bar
+        let bar = candle(1, 100, 101, 94, 96);
+        let fills = book.fill(&bar);
+        assert_eq!(book.orders().len(), 3);
+        book.expire_ioc(&bar);
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].order.id, limit);
+        assert_eq!((fills[0].price.0, fills[0].is_maker), (dec!(95), true));
+        assert_eq!(book.orders().len(), 2);
+
+        // Stop gapped below filled at the open as taker, GTT expired
+        let fills = book.fill(&candle(2, 85, 88, 70, 75));
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].order.id, stop);
+        assert_eq!((fills[0].price.0, fills[0].is_maker), (dec!(85), false));
+        assert!(book.orders().is_empty());
+
+        // Replaced stop limit, triggered and resting at its limit
+        let id = place(
+            &mut book,
+            buy,
+            OrderKind::StopLimit {
+                stop: Price(dec!(110)),
+                limit: Price(dec!(112)),
+            },
+            TimeInForce::Gtc,
+        );
+        book.apply(OrderAction::Replace {
+            id,
+            new_id: id + 1,
+            quantity: Quantity(dec!(2)),
+            price: Price(dec!(105)),
+        });
+        assert!(book.fill(&candle(1, 100, 111, 107, 108)).is_empty());
+        assert_eq!(book.orders()[0].kind, OrderKind::Limit(Price(dec!(105))));
+        let fills = book.fill(&candle(2, 108, 109, 104, 106));
+        assert_eq!((fills[0].order.id, fills[0].price.0), (id + 1, dec!(105)));
+
+        place(
+            &mut book,
+            buy,
+            OrderKind::Limit(Price(dec!(1))),
+            TimeInForce::Gtc,
+        );
+        book.apply(OrderAction::CancelAll);
+        assert!(book.orders().is_empty());
+    }
+
+    #[test]
+    fn order_latency_test() {
+        let latency_order = |time_in_force| {
+            let time = candle(0, 0, 0, 0, 0).close_time + Duration::seconds(30);
+            let kind = OrderKind::Limit(Price(dec!(95)));
+            let operation = Operation::Buy(Quantity(dec!(1)));
+            OrderAction::Place(Order::new(1, operation, kind, time_in_force, time, None))
+        };
+        let minute = |i, low| timeframe_candle(1, i, dec!(100), dec!(100), low, dec!(100), dec!(1));
+
+        // The first minute of the bar opened before the order reached the book
+        let mut book = OrderBook::new();
+        book.apply(latency_order(TimeInForce::Gtc));
+        assert!(book.fill(&minute(15, dec!(90))).is_empty());
+        assert!(book.fill(&minute(16, dec!(96))).is_empty());
+        let fills = book.fill(&minute(17, dec!(94)));
+        assert_eq!(fills.len(), 1);
+
+        // The bar opened before the order, an IOC order waits for the next one
+        let mut book = OrderBook::new();
+        book.apply(latency_order(TimeInForce::Ioc));
+        let bar = candle(1, 100, 100, 90, 100);
+        assert!(book.fill(&bar).is_empty());
+        book.expire_ioc(&bar);
+        assert_eq!(book.orders().len(), 1);
+        let bar = candle(2, 100, 100, 90, 100);
+        assert_eq!(book.fill(&bar).len(), 1);
+    }
+}
@@ -0,0 +1,313 @@
+use crate::model::candle::Candle;
+use crate::model::equity_point::EquityPoint;
+use crate::model::flow::Flow;
+use crate::services::trading::flow_register::FUNDING_LOG;
+use chrono::Duration;
+use pad::{Alignment, PadStr};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+use std::fmt::{self, Display};
+use std::path::Path;
+
+const MINUTES_PER_YEAR: f64 = 365. * 24. * 60.;
+
+/// Return and risk metrics of a back test, returns and rates as fractions, ratios are 0 when
+/// undefined
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct PerformanceReport {
+    pub total_return: f64,
+    pub annualized_return: f64,
+    pub max_drawdown: f64,
+    /// Longest time under a previous equity peak
+    pub max_drawdown_hours: f64,
+    pub sharpe: f64,
+    pub sortino: f64,
+    pub calmar: f64,
+    pub win_rate: f64,
+    pub profit_factor: f64,
+    /// Average P&L of the trades
+    pub expectancy: f64,
+    pub average_win: f64,
+    pub average_loss: f64,
+    pub trades: usize,
+    /// Fraction of the candles closing with an open position
+    pub exposure: f64,
+    /// Return of holding the asset from the first candle open to the last candle close
+    pub buy_and_hold_return: f64,
+}
+
+impl PerformanceReport {
+    /// Report of the equity at each candle close and the P&L of the closed trades
+    pub fn new(equity: &[EquityPoint], trade_pnls: &[Decimal], candles: &[Candle]) -> Self {
+        let values = equity
+            .iter()
+            .map(|p| p.equity.to_f64().unwrap())
+            .collect::<Vec<_>>();
+        let (first, last) = match (values.first(), values.last(), candles.first()) {
+            (Some(first), Some(last), Some(_)) if *first > 0. => (*first, *last),
+            _ => return Self::default(),
+        };
+        let periods_per_year = MINUTES_PER_YEAR / candles[0].minutes as f64;
+
+        let total_return = last / first - 1.;
+        let years = values.len() as f64 / periods_per_year;
+        let annualized_return = if last > 0. {
+            (last / first).powf(1. / years) - 1.
+        } else {
+            -1.
+        };
+
+        let mut peak = (first, equity[0].time);
+        let mut max_drawdown = 0_f64;
+        let mut max_drawdown_duration = Duration::zero();
+        for (point, value) in equity.iter().zip(values.iter()) {
+            if *value >= peak.0 {
+                peak = (*value, point.time);
+            } else {
+                max_drawdown = max_drawdown.max(1. - value / peak.0);
+                max_drawdown_duration = max_drawdown_duration.max(point.time - peak.1);
+            }
+        }
+
+        let returns = values
+            .windows(2)
+            .filter(|w| w[0] > 0.)
+            .map(|w| w[1] / w[0] - 1.)
+            .collect::<Vec<_>>();
+        let mean_return = mean(&returns);
+        let deviation = mean(
+            &returns
+                .iter()
+                .map(|r| (r - mean_return).powi(2))
+                .collect::<Vec<_>>(),
+        )
+        .sqrt();
+        let downside_deviation = mean(
+            &returns
+                .iter()
+                .map(|r| r.min(0.).powi(2))
+                .collect::<Vec<_>>(),
+        )
+        .sqrt();
+
+        let pnls = trade_pnls
+            .iter()
+            .map(|pnl| pnl.to_f64().unwrap())
+            .collect::<Vec<_>>();
+        let wins = pnls.iter().copied().filter(|p| *p > 0.).collect::<Vec<_>>();
+        let losses = pnls.iter().copied().filter(|p| *p < 0.).collect::<Vec<_>>();
+        let gross_profit: f64 = wins.iter().sum();
+        let gross_loss: f64 = -losses.iter().sum::<f64>();
+
+        let exposed = equity.iter().filter(|p| p.exposed).count();
+        let first_open = candles[0].open.to_f64().unwrap();
+        let last_close = candles[candles.len() - 1].close.to_f64().unwrap();
+
+        Self {
+            total_return,
+            annualized_return,
+            max_drawdown,
+            max_drawdown_hours: max_drawdown_duration.num_minutes() as f64 / 60.,
+            sharpe: ratio(mean_return, deviation) * periods_per_year.sqrt(),
+            sortino: ratio(mean_return, downside_deviation) * periods_per_year.sqrt(),
+            calmar: ratio(annualized_return, max_drawdown),
+            win_rate: ratio(wins.len() as f64, pnls.len() as f64),
+            profit_factor: ratio(gross_profit, gross_loss),
+            expectancy: mean(&pnls),
+            average_win: mean(&wins),
+            average_loss: mean(&losses),
+            trades: pnls.len(),
+            exposure: ratio(exposed as f64, equity.len() as f64),
+            buy_and_hold_return: ratio(last_close, first_open) - 1.,
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> eyre::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Table of the metrics
+impl Display for PerformanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = |value: f64| format!("{:.2}%", value * 100.);
+        let rows = [
+            ("Total return", percent(self.total_return)),
+            ("Annualized return", percent(self.annualized_return)),
+            ("Max drawdown", percent(self.max_drawdown)),
+            (
+                "Max drawdown duration",
+                format!("{:.1}h", self.max_drawdown_hours),
+            ),
+            ("Sharpe", format!("{:.2}", self.sharpe)),
+            ("Sortino", format!("{:.2}", self.sortino)),
+            ("Calmar", format!("{:.2}", self.calmar)),
+            ("Win rate", percent(self.win_rate)),
+            ("Profit factor", format!("{:.2}", self.profit_factor)),
+            ("Expectancy USD", format!("{:.2}", self.expectancy)),
+            ("Average win USD", format!("{:.2}", self.average_win)),
+            ("Average loss USD", format!("{:.2}", self.average_loss)),
+            ("Trades", self.trades.to_string()),
+            ("Exposure", percent(self.exposure)),
+            ("Buy and hold return", percent(self.buy_and_hold_return)),
+        ];
+        for (name, value) in rows.iter() {
+            writeln!(
+                f,
+                "{} {}",
+                name.pad_to_width(22),
+                value.pad_to_width_with_alignment(12, Alignment::Right)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// P&L of the trades, one per round trip once the position goes flat or flips, net of the fees
+/// opening and closing them and of the funding paid while open. Partial closes are summed into
+/// the trade, a flip charges the closing part of its fee to the closed trade.
+pub fn trade_pnls(flows: &[Flow]) -> Vec<Decimal> {
+    let mut position = dec!(0);
+    // Entry fees and funding of the open position not charged to a trade yet
+    let mut costs = dec!(0);
+    // P&L of the partial closes of the open position
+    let mut trade_pnl = dec!(0);
+    let mut pnls = Vec::new();
+    for flow in flows.iter() {
+        if flow.log.as_deref() == Some(FUNDING_LOG) {
+            costs -= flow.total;
+            continue;
+        }
+        let mut entry_fee = flow.fee;
+        if let Some(realized_pnl) = flow.realized_pnl {
+            let closed = flow.quantity.min(position.abs());
+            let (closing_fee, closed_costs) = if closed > dec!(0) {
+                (
+                    flow.fee * closed / flow.quantity,
+                    costs * closed / position.abs(),
+                )
+            } else {
+                (dec!(0), dec!(0))
+            };
+            entry_fee -= closing_fee;
+            costs -= closed_costs;
+            trade_pnl += realized_pnl - closing_fee - closed_costs;
+            if closed == position.abs() {
+                pnls.push(trade_pnl);
+                trade_pnl = dec!(0);
+            }
+        }
+        position += if flow.is_buyer_maker {
+            flow.quantity
+        } else {
+            -flow.quantity
+        };
+        if position == dec!(0) {
+            costs = dec!(0);
+        }
+        costs += entry_fee;
+    }
+    pnls
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        0.
+    } else {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+}
+
+fn ratio(numerator: f64, denominator: f64) -> f64 {
+    if denominator == 0. {
+        0.
+    } else {
+        numerator / denominator
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::utils::candles_utils::tests::timeframe_candle;
+    use crate::utils::date_utils::str_to_datetime;
+
+    #[test]
+    fn performance_report_test() {
+        let candles = (0..5)
+            .map(|i| {
+                let open = Decimal::from(100 + i * 10);
+                let close = Decimal::from(110 + i * 10);
+                timeframe_candle(24 * 60, i, open, dec!(200), dec!(50), close, dec!(10))
+            })
+            .collect::<Vec<_>>();
+        let equity = [1000, 1100, 990, 1045, 1200]
+            .iter()
+            .zip(candles.iter())
+            .map(|(e, c)| EquityPoint::new(c.close_time, Decimal::from(*e), *e != 1000))
+            .collect::<Vec<_>>();
+        let pnls = [dec!(100), dec!(-110), dec!(210)];
+
+        let report = PerformanceReport::new(&equity, &pnls, &candles);
+        assert!((report.total_return - 0.2).abs() < 1e-9);
+        assert!((report.max_drawdown - 0.1).abs() < 1e-9);
+        assert_eq!(report.max_drawdown_hours, 48.);
+        assert_eq!(report.win_rate, 2. / 3.);
+        assert_eq!(report.profit_factor, 310. / 110.);
+        assert_eq!(report.expectancy, 200. / 3.);
+        assert_eq!(report.exposure, 0.8);
+        assert_eq!(report.buy_and_hold_return, 0.5);
+        assert_eq!(report.trades, 3);
+        assert!(report.sharpe > 0. && report.sortino > report.sharpe);
+    }
+
+    fn flow(
+        is_buy: bool,
+        quantity: Decimal,
+        fee: Decimal,
+        realized_pnl_opt: Option<Decimal>,
+    ) -> Flow {
+        Flow {
+            id: 0,
+            position: 1,
+            is_buyer_maker: is_buy,
+            time: str_to_datetime("2020-01-12 00:00:00"),
+            price: dec!(100),
+            quantity,
+            total: quantity * dec!(100),
+            fee,
+            real_balance_fiat_old: dec!(1000),
+            real_balance_fiat_new: dec!(1000),
+            gain_perc: dec!(0),
+            log: None,
+            order_id: None,
+            entry_price: realized_pnl_opt.map(|_| dec!(100)),
+            realized_pnl: realized_pnl_opt,
+            holding_seconds: realized_pnl_opt.map(|_| 0),
+        }
+    }
+
+    #[test]
+    fn trade_pnls_test() {
+        let funding = Flow {
+            total: dec!(-0.5),
+            log: Some(FUNDING_LOG.to_string()),
+            ..flow(false, dec!(1), dec!(0), None)
+        };
+        let flows = [
+            // Long closed in two halves, a single trade paying the entry fee and funding
+            flow(true, dec!(1), dec!(1), None),
+            funding,
+            flow(false, dec!(0.5), dec!(0.5), Some(dec!(5))),
+            flow(false, dec!(0.5), dec!(0.5), Some(dec!(5))),
+            // Short flipped to a long, the opening part of the fee goes to the long
+            flow(false, dec!(1), dec!(1), None),
+            flow(true, dec!(2), dec!(2), Some(dec!(10))),
+            flow(false, dec!(1), dec!(1), Some(dec!(-2))),
+        ];
+        assert_eq!(trade_pnls(&flows), vec![dec!(7.5), dec!(8), dec!(-4)]);
+    }
+}
@@ -1,7 +1,10 @@
 use super::trade_operation::TradeOperation;
 use super::trend::trend_direction::TrendDirection;
+use crate::model::order::OrderAction;
 
 pub struct TrendState {
     pub trend_direction: TrendDirection,
     pub trade_operation_opt: Option<TradeOperation>,
+    /// Changes of the pending orders, applied after the operation
+    pub order_actions: Vec<OrderAction>,
 }
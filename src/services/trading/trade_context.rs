@@ -1,17 +1,34 @@
 use super::trend::trend_direction::TrendDirection;
+use crate::config::candle_type::CandleType;
 use crate::services::provider::candles_provider::CandlesProvider;
 use crate::services::provider::candles_provider_buffer::CandlesProviderBuffer;
+use crate::services::technicals::divergence_tec::{divergences, Divergence};
 use crate::services::technicals::ind_provider::IndicatorProvider;
+use crate::services::technicals::ind_stream::{IndicatorStreams, StreamIndicator, HISTORY_LEN};
 use crate::services::technicals::indicator::Indicator;
+use crate::services::technicals::pair_tec::{aligned_closes, pair_stats, PairStats};
+use crate::services::technicals::serie::Serie;
+use crate::services::technicals::serie_indicator::SerieIndicator;
+use crate::services::technicals::swing_stream::SwingSerie;
+use crate::services::technicals::top_bottom::TopBottom;
 use crate::{config::candles_selection::CandlesSelection, model::candle::Candle};
 use crate::{model::price::Price, services::technicals::ind_type::IndicatorType};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
+use eyre::{bail, eyre};
+use std::collections::HashMap;
+
+/// Candles with the now, minutes, period and candle type they were loaded for
+type CandlesCache = (Vec<Candle>, DateTime<Utc>, i32, i32, CandleType);
 
 pub struct TradeContext {
     symbol: i32,
     indicator_provider: IndicatorProvider,
     candles_provider: CandlesProviderBuffer,
-    candles_opt: Option<(Vec<Candle>, DateTime<Utc>, i32, i32)>,
+    candles_opt: Option<CandlesCache>,
+    stream_selection_opt: Option<CandlesSelection>,
+    candle_types: HashMap<i32, CandleType>,
+    indicator_streams: HashMap<(i32, i32, CandleType), IndicatorStreams>,
+    symbols: HashMap<String, i32>,
     now: Option<DateTime<Utc>>,
     price: Option<Price>,
     current_trend_direction_opt: Option<TrendDirection>,
@@ -25,11 +42,17 @@ impl TradeContext {
         indicator_provider: IndicatorProvider,
         candles_provider: CandlesProviderBuffer,
     ) -> Self {
+        // Streamed indicators start with the trader candles, as the plotted ones
+        let stream_selection_opt = candles_provider.candles_selection();
         Self {
             symbol,
             indicator_provider,
             candles_provider,
             candles_opt: None,
+            stream_selection_opt,
+            candle_types: HashMap::new(),
+            indicator_streams: HashMap::new(),
+            symbols: HashMap::new(),
             now: None,
             price: None,
             current_trend_direction_opt: None,
@@ -93,24 +116,54 @@ impl TradeContext {
         self.changed_trend.take()
     }
 
+    /// Candles of the timeframe used when none are asked explicitly
+    pub fn set_candle_type(&mut self, minutes: i32, candle_type: CandleType) {
+        self.candle_types.insert(minutes, candle_type);
+    }
+
+    /// Candles set for the timeframe, else the ones of the trader selection
+    pub fn candle_type(&self, minutes: i32) -> CandleType {
+        self.candle_types
+            .get(&minutes)
+            .copied()
+            .or_else(|| self.stream_selection_opt.map(|s| s.candle_type))
+            .unwrap_or_default()
+    }
+
     pub fn indicator(
         &mut self,
         minutes: i32,
         indicator_type: &IndicatorType,
     ) -> eyre::Result<&dyn Indicator> {
+        let candle_type = self.candle_type(minutes);
+        self.candles_indicator(minutes, candle_type, indicator_type)
+    }
+
+    /// Indicator of the timeframe computed from the given candles
+    pub fn candles_indicator(
+        &mut self,
+        minutes: i32,
+        candle_type: CandleType,
+        indicator_type: &IndicatorType,
+    ) -> eyre::Result<&dyn Indicator> {
+        if StreamIndicator::is_streamed(indicator_type) {
+            return self.stream_indicator(minutes, candle_type, indicator_type);
+        }
+
         let now = self.now();
         let period = indicator_type.period();
         // This caching is working ok
         self.candles_opt = self
             .candles_opt
             .take()
-            .filter(|e| e.1 == now && e.2 == minutes && e.3 == period);
+            .filter(|e| e.1 == now && e.2 == minutes && e.3 == period && e.4 == candle_type);
 
         let candles_provider = &mut self.candles_provider;
         let symbol = self.symbol;
 
-        let (candles, _, _, _) = self.candles_opt.get_or_insert_with(|| {
-            let candles_selection = CandlesSelection::last_n(symbol, minutes, period, now);
+        let (candles, _, _, _, _) = self.candles_opt.get_or_insert_with(|| {
+            let mut candles_selection = CandlesSelection::last_n(symbol, minutes, period, now);
+            candles_selection.candle_type = candle_type;
             // TODO here should considere use range
             // let mut candles_provider_selection =
             //     CandlesProviderSelection::new(candles_provider.clone(), candles_selection);
@@ -119,9 +172,228 @@ impl TradeContext {
             candles_provider.set_candles_selection(candles_selection);
             let candles = candles_provider.candles().unwrap();
 
-            (candles, now, minutes, period)
+            (candles, now, minutes, period, candle_type)
         });
         self.indicator_provider
-            .indicator(now, candles, indicator_type)
+            .indicator(now, minutes, candle_type, candles, indicator_type)
+    }
+
+    /// Indicator updated from candles closed since the last call
+    fn stream_indicator(
+        &mut self,
+        minutes: i32,
+        candle_type: CandleType,
+        indicator_type: &IndicatorType,
+    ) -> eyre::Result<&dyn Indicator> {
+        let now = self.now();
+        self.timeframe_streams(minutes, candle_type)?
+            .indicator(now, indicator_type)
+    }
+
+    /// Candles closed until now to compute a script indicator from, so it can't look ahead, none
+    /// when the cached serie is up to date
+    pub fn script_candles(
+        &mut self,
+        minutes: i32,
+        name: &str,
+    ) -> eyre::Result<Option<Vec<Candle>>> {
+        let now = self.now();
+        let cached_close_time_opt = self
+            .indicator_provider
+            .script_serie_close_time(minutes, name);
+        let candle_type = self.candle_type(minutes);
+        let candles = self
+            .timeframe_streams(minutes, candle_type)?
+            .closed_candles(now, usize::MAX);
+        if cached_close_time_opt == Some(candles.last().map(|c| c.close_time)) {
+            return Ok(None);
+        }
+        Ok(Some(candles.to_vec()))
+    }
+
+    pub fn set_script_serie(
+        &mut self,
+        minutes: i32,
+        close_time_opt: Option<DateTime<Utc>>,
+        serie: SerieIndicator,
+    ) {
+        self.indicator_provider
+            .set_script_serie(minutes, close_time_opt, serie);
+    }
+
+    pub fn script_history(
+        &mut self,
+        minutes: i32,
+        name: &str,
+        len: usize,
+    ) -> eyre::Result<Vec<f64>> {
+        let now = self.now();
+        self.indicator_provider
+            .script_history(minutes, name, now, len)
+    }
+
+    /// Values of last `len` candles of the timeframe, oldest first, only for streamed indicators
+    pub fn indicator_history(
+        &mut self,
+        minutes: i32,
+        indicator_type: &IndicatorType,
+        len: usize,
+    ) -> eyre::Result<Vec<f64>> {
+        let candle_type = self.candle_type(minutes);
+        self.candles_indicator_history(minutes, candle_type, indicator_type, len)
+    }
+
+    /// Values of last `len` given candles of the timeframe, oldest first
+    pub fn candles_indicator_history(
+        &mut self,
+        minutes: i32,
+        candle_type: CandleType,
+        indicator_type: &IndicatorType,
+        len: usize,
+    ) -> eyre::Result<Vec<f64>> {
+        if !StreamIndicator::is_streamed(indicator_type) {
+            bail!("History not available for {:?}!", indicator_type);
+        }
+        let now = self.now();
+        self.timeframe_streams(minutes, candle_type)?
+            .history(now, indicator_type, len)
+    }
+
+    /// Last `len` candles of the timeframe closed until now, oldest first
+    pub fn candles(&mut self, minutes: i32, len: usize) -> eyre::Result<Vec<Candle>> {
+        let now = self.now();
+        let candle_type = self.candle_type(minutes);
+        Ok(self
+            .timeframe_streams(minutes, candle_type)?
+            .closed_candles(now, len)
+            .to_vec())
+    }
+
+    /// Last `len` swings of the timeframe confirmed until now, oldest first
+    pub fn swings(
+        &mut self,
+        minutes: i32,
+        neighbors: usize,
+        swing_serie: SwingSerie,
+        len: usize,
+    ) -> eyre::Result<Vec<TopBottom>> {
+        let now = self.now();
+        let candle_type = self.candle_type(minutes);
+        Ok(self
+            .timeframe_streams(minutes, candle_type)?
+            .swings(now, neighbors, swing_serie, len))
+    }
+
+    /// Divergences of the last two swings of each type with the oscillator streamed values
+    pub fn divergences(
+        &mut self,
+        minutes: i32,
+        neighbors: usize,
+        indicator_type: &IndicatorType,
+    ) -> eyre::Result<Vec<Divergence>> {
+        let mut top_bottoms = self.swings(minutes, neighbors, SwingSerie::Tops, 2)?;
+        top_bottoms.extend(self.swings(minutes, neighbors, SwingSerie::Bottoms, 2)?);
+        let values = self.indicator_history(minutes, indicator_type, HISTORY_LEN)?;
+        let series = self
+            .candles(minutes, values.len())?
+            .iter()
+            .zip(values)
+            .map(|(candle, value)| Serie::new(candle.close_time, value))
+            .collect();
+        let oscillator = SerieIndicator::from(&format!("{:?}", indicator_type), series);
+        Ok(divergences(&top_bottoms, &oscillator))
+    }
+
+    /// Last `len` candles of the timeframe of another symbol (e.g. BTCUSDT) closed until now,
+    /// oldest first
+    pub fn symbol_candles(
+        &mut self,
+        pair: &str,
+        minutes: i32,
+        len: usize,
+    ) -> eyre::Result<Vec<Candle>> {
+        let now = self.now();
+        let symbol = self.symbol_id(pair)?;
+        let candle_type = self.candle_type(minutes);
+        Ok(self
+            .symbol_streams(symbol, minutes, candle_type)?
+            .closed_candles(now, len)
+            .to_vec())
+    }
+
+    /// Ratio, correlation, beta and spread with another symbol over last `period` candles
+    pub fn pair_stats(
+        &mut self,
+        pair: &str,
+        minutes: i32,
+        period: usize,
+    ) -> eyre::Result<PairStats> {
+        let candles = self.candles(minutes, period + 1)?;
+        let others = self.symbol_candles(pair, minutes, period + 1)?;
+        Ok(pair_stats(&aligned_closes(&candles, &others), period))
+    }
+
+    fn symbol_id(&mut self, pair: &str) -> eyre::Result<i32> {
+        if let Some(symbol) = self.symbols.get(pair) {
+            return Ok(*symbol);
+        }
+        let symbol = self
+            .candles_provider
+            .symbol_id(pair)
+            .ok_or_else(|| eyre!("Symbol {} not found!", pair))?;
+        self.symbols.insert(pair.to_string(), symbol);
+        Ok(symbol)
+    }
+
+    fn timeframe_streams(
+        &mut self,
+        minutes: i32,
+        candle_type: CandleType,
+    ) -> eyre::Result<&mut IndicatorStreams> {
+        self.symbol_streams(self.symbol, minutes, candle_type)
+    }
+
+    /// Exchange candles of each symbol and timeframe are loaded once for whole trader selection,
+    /// and made into the candle type by the streams as they are pushed
+    fn symbol_streams(
+        &mut self,
+        symbol: i32,
+        minutes: i32,
+        candle_type: CandleType,
+    ) -> eyre::Result<&mut IndicatorStreams> {
+        let now = self.now();
+        let candles_provider = &mut self.candles_provider;
+        let stream_selection_opt = self.stream_selection_opt;
+
+        let indicator_streams = self
+            .indicator_streams
+            .entry((symbol, minutes, candle_type))
+            .or_insert_with(|| IndicatorStreams::with_candle_type(candle_type));
+
+        let duration = Duration::minutes(minutes as i64);
+        let start_time_opt = match indicator_streams.last_source_candle() {
+            None => Some(
+                stream_selection_opt
+                    .map(|s| s.start_time)
+                    .filter(|s| s <= &now)
+                    .unwrap_or(now - duration),
+            ),
+            // Only when a new candle could have closed after the loaded ones
+            Some(last) if last.close_time + duration <= now => Some(last.open_time + duration),
+            Some(_) => None,
+        };
+
+        if let Some(start_time) = start_time_opt {
+            let end_time = stream_selection_opt
+                .map(|s| s.end_time.max(now))
+                .unwrap_or(now);
+            let mut candles_selection =
+                CandlesSelection::from(symbol, minutes, start_time, end_time);
+            candles_selection.candle_type = CandleType::Raw;
+            candles_provider.set_candles_selection(candles_selection);
+            indicator_streams.push_candles(candles_provider.candles()?);
+        }
+
+        Ok(indicator_streams)
     }
 }
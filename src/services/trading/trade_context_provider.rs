@@ -1,6 +1,13 @@
 use super::{trade_context::TradeContext, trend::trend_direction::TrendDirection};
+use crate::config::candle_type::CandleType;
+use crate::model::candle::Candle;
 use crate::services::provider::candles_provider_buffer::CandlesProviderBuffer;
+use crate::services::technicals::divergence_tec::Divergence;
 use crate::services::technicals::ind_type::IndicatorType;
+use crate::services::technicals::pair_tec::PairStats;
+use crate::services::technicals::serie_indicator::SerieIndicator;
+use crate::services::technicals::swing_stream::SwingSerie;
+use crate::services::technicals::top_bottom::TopBottom;
 use crate::{model::price::Price, services::technicals::ind_provider::IndicatorProvider};
 use chrono::{DateTime, Utc};
 use std::rc::Rc;
@@ -74,4 +81,149 @@ impl TradeContextProvider {
             .unwrap()
             .value()
     }
+
+    pub fn set_candle_type(&self, minutes: i32, candle_type: CandleType) {
+        self.trade_context
+            .lock()
+            .unwrap()
+            .get_mut()
+            .set_candle_type(minutes, candle_type);
+    }
+
+    /// Value computed from the given candles of the timeframe
+    pub fn candles_value(
+        &self,
+        minutes: i32,
+        candle_type: CandleType,
+        i_type: &IndicatorType,
+    ) -> eyre::Result<f64> {
+        self.trade_context
+            .lock()
+            .unwrap()
+            .get_mut()
+            .candles_indicator(minutes, candle_type, i_type)?
+            .value()
+    }
+
+    /// Values of last `len` given candles of the timeframe, oldest first
+    pub fn candles_values(
+        &self,
+        minutes: i32,
+        candle_type: CandleType,
+        i_type: &IndicatorType,
+        len: usize,
+    ) -> eyre::Result<Vec<f64>> {
+        self.trade_context
+            .lock()
+            .unwrap()
+            .get_mut()
+            .candles_indicator_history(minutes, candle_type, i_type, len)
+    }
+
+    /// Values of last `len` candles of the timeframe, oldest first
+    pub fn values(
+        &self,
+        minutes: i32,
+        i_type: &IndicatorType,
+        len: usize,
+    ) -> eyre::Result<Vec<f64>> {
+        self.trade_context
+            .lock()
+            .unwrap()
+            .get_mut()
+            .indicator_history(minutes, i_type, len)
+    }
+
+    /// Last `len` closed candles of the timeframe, oldest first
+    pub fn candles(&self, minutes: i32, len: usize) -> eyre::Result<Vec<Candle>> {
+        self.trade_context
+            .lock()
+            .unwrap()
+            .get_mut()
+            .candles(minutes, len)
+    }
+
+    /// Last `len` closed candles of the timeframe of another symbol, oldest first
+    pub fn symbol_candles(
+        &self,
+        pair: &str,
+        minutes: i32,
+        len: usize,
+    ) -> eyre::Result<Vec<Candle>> {
+        self.trade_context
+            .lock()
+            .unwrap()
+            .get_mut()
+            .symbol_candles(pair, minutes, len)
+    }
+
+    pub fn pair_stats(&self, pair: &str, minutes: i32, period: usize) -> eyre::Result<PairStats> {
+        self.trade_context
+            .lock()
+            .unwrap()
+            .get_mut()
+            .pair_stats(pair, minutes, period)
+    }
+
+    pub fn swings(
+        &self,
+        minutes: i32,
+        neighbors: usize,
+        swing_serie: SwingSerie,
+        len: usize,
+    ) -> eyre::Result<Vec<TopBottom>> {
+        self.trade_context
+            .lock()
+            .unwrap()
+            .get_mut()
+            .swings(minutes, neighbors, swing_serie, len)
+    }
+
+    pub fn divergences(
+        &self,
+        minutes: i32,
+        neighbors: usize,
+        i_type: &IndicatorType,
+    ) -> eyre::Result<Vec<Divergence>> {
+        self.trade_context
+            .lock()
+            .unwrap()
+            .get_mut()
+            .divergences(minutes, neighbors, i_type)
+    }
+
+    /// Values of last `len` candles of a script indicator, `eval` computes its serie when not
+    /// cached yet and runs without the context locked, so it can call other script functions
+    pub fn script_values<F>(
+        &self,
+        minutes: i32,
+        name: &str,
+        len: usize,
+        eval: F,
+    ) -> eyre::Result<Vec<f64>>
+    where
+        F: FnOnce(&[Candle]) -> eyre::Result<SerieIndicator>,
+    {
+        let candles_opt = self
+            .trade_context
+            .lock()
+            .unwrap()
+            .get_mut()
+            .script_candles(minutes, name)?;
+
+        if let Some(candles) = candles_opt {
+            let serie = eval(&candles)?;
+            self.trade_context
+                .lock()
+                .unwrap()
+                .get_mut()
+                .set_script_serie(minutes, candles.last().map(|c| c.close_time), serie);
+        }
+
+        self.trade_context
+            .lock()
+            .unwrap()
+            .get_mut()
+            .script_history(minutes, name, len)
+    }
 }
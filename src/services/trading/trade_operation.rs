@@ -1,6 +1,10 @@
+use crate::model::flow::Flow;
 use crate::model::operation::Operation;
 use crate::model::price::Price;
+use crate::model::quantity::Quantity;
+use crate::services::trading::order_book::OrderFill;
 use chrono::{DateTime, Utc};
+use rust_decimal_macros::dec;
 
 /// TradeOperation is a Operation with current context (date_time and price)
 #[derive(Clone, Debug)]
@@ -9,6 +13,8 @@ pub struct TradeOperation {
     pub now: DateTime<Utc>,
     pub price: Price,
     pub description_opt: Option<String>,
+    /// Pending order filled by the operation
+    pub order_id_opt: Option<i32>,
 }
 
 impl TradeOperation {
@@ -23,6 +29,51 @@ impl TradeOperation {
             now,
             price,
             description_opt,
+            order_id_opt: None,
         }
     }
+
+    pub fn from_fill(order_fill: &OrderFill, now: DateTime<Utc>) -> Self {
+        Self {
+            operation: order_fill.order.operation,
+            now,
+            price: order_fill.price,
+            description_opt: order_fill.order.description_opt.clone(),
+            order_id_opt: Some(order_fill.order.id),
+        }
+    }
+
+    /// Operations registered by the trade flows in time order, a sell leaving the position short
+    /// reads as a short and a buy not leaving it long as a cover
+    pub fn from_flows(flows: &[Flow]) -> Vec<Self> {
+        let mut position = dec!(0);
+        flows
+            .iter()
+            .map(|flow| {
+                let quantity = Quantity(flow.quantity);
+                let operation = if flow.is_buyer_maker {
+                    position += flow.quantity;
+                    if position > dec!(0) {
+                        Operation::Buy(quantity)
+                    } else {
+                        Operation::Cover(quantity)
+                    }
+                } else {
+                    position -= flow.quantity;
+                    if position < dec!(0) {
+                        Operation::Short(quantity)
+                    } else {
+                        Operation::Sell(quantity)
+                    }
+                };
+                Self {
+                    operation,
+                    now: flow.time,
+                    price: Price(flow.price),
+                    description_opt: flow.log.clone(),
+                    order_id_opt: flow.order_id,
+                }
+            })
+            .collect()
+    }
 }
@@ -1,17 +1,27 @@
 use super::{
-    trade_operation::TradeOperation, trader_register::TraderRegister,
+    execution::{open_after, Execution},
+    trade_operation::TradeOperation,
+    trader_register::TraderRegister,
     trend::trend_provider::TrendProvider,
 };
+use crate::model::candle::Candle;
+use crate::model::equity_point::EquityPoint;
+use crate::model::order::OrderAction;
 use crate::services::provider::candles_provider_buffer::CandlesProviderBuffer;
+use crate::services::script::position_register::PositionRegister;
 use crate::services::trading::trade_context_provider::TradeContextProvider;
 use crate::{model::price::Price, services::technicals::ind_provider::IndicatorProvider};
-use chrono::{DateTime, Utc};
+use rust_decimal_macros::dec;
 
 pub struct Trader<T: TrendProvider + Send + Sync> {
     trend_provider: T,
     trade_operations: Vec<TradeOperation>,
     trade_context_provider: TradeContextProvider,
     trader_register: TraderRegister,
+    execution: Execution,
+    /// Operation decided at the last candle close waiting for the next open
+    deferred_opt: Option<TradeOperation>,
+    equity: Vec<EquityPoint>,
 }
 
 impl<'a, T: TrendProvider + Send + Sync> Trader<T> {
@@ -21,6 +31,7 @@ impl<'a, T: TrendProvider + Send + Sync> Trader<T> {
         indicator_provider: IndicatorProvider,
         candles_provider: CandlesProviderBuffer,
         trader_register: TraderRegister,
+        execution: Execution,
     ) -> Self {
         let trade_context_provider =
             TradeContextProvider::new(symbol, indicator_provider, candles_provider);
@@ -29,12 +40,34 @@ impl<'a, T: TrendProvider + Send + Sync> Trader<T> {
             trade_operations: Vec::new(),
             trade_context_provider,
             trader_register,
+            execution,
+            deferred_opt: None,
+            equity: Vec::new(),
         }
     }
 
-    pub fn check(&'a mut self, now: DateTime<Utc>, price: Price) -> eyre::Result<()> {
-        self.trade_context_provider.set_now(now);
-        self.trade_context_provider.set_price(price);
+    /// Applies the funding reached, fills the deferred operation at the candle open and the
+    /// pending orders crossed by the path candles inside the candle, the candle itself or lower
+    /// timeframe candles, and runs the trend at its close
+    pub fn check(&'a mut self, candle: &Candle, path: &[Candle]) -> eyre::Result<()> {
+        self.trader_register.next_candle(candle)?;
+
+        if let Some(trade_operation) = self.deferred_opt.take() {
+            let (now, price) = open_after(candle, path, self.execution.latency());
+            let trade_operation = TradeOperation {
+                now,
+                price,
+                ..trade_operation
+            };
+            let filled_opt = self.trader_register.register(&trade_operation, candle)?;
+            self.trade_operations.extend(filled_opt);
+        }
+
+        let filled = self.trader_register.fill_orders(candle, path)?;
+        self.trade_operations.extend(filled);
+
+        self.trade_context_provider.set_now(candle.close_time);
+        self.trade_context_provider.set_price(Price(candle.close));
 
         let position = self.trader_register.position_register();
 
@@ -45,18 +78,47 @@ impl<'a, T: TrendProvider + Send + Sync> Trader<T> {
         self.trade_context_provider
             .set_trend_direction(running_script_state.trend_direction);
 
-        // Get the operation, if none then exit routine
-        let trade_operation = match running_script_state.trade_operation_opt {
-            Some(trade_operation) => trade_operation,
-            None => return Ok(()),
-        };
+        if let Some(trade_operation) = running_script_state.trade_operation_opt {
+            match self.execution {
+                Execution::Close => {
+                    let filled_opt = self.trader_register.register(&trade_operation, candle)?;
+                    self.trade_operations.extend(filled_opt);
+                }
+                Execution::NextOpen(_) => self.deferred_opt = Some(trade_operation),
+            }
+        }
 
-        self.trader_register.register(trade_operation.clone())?;
+        // Orders reach the book after the latency
+        let latency = self.execution.latency();
+        for order_action in running_script_state.order_actions {
+            let order_action = match order_action {
+                OrderAction::Place(mut order) => {
+                    order.time = order.time + latency;
+                    OrderAction::Place(order)
+                }
+                order_action => order_action,
+            };
+            self.trader_register.apply_order(order_action);
+        }
 
-        self.trade_operations.push(trade_operation);
+        let position = &self.trader_register.position_register().position;
+        self.equity.push(EquityPoint::new(
+            candle.close_time,
+            position.balance_fiat + position.balance_asset * candle.close,
+            position.balance_asset != dec!(0),
+        ));
         Ok(())
     }
 
+    pub fn position_register(&self) -> &PositionRegister {
+        self.trader_register.position_register()
+    }
+
+    /// Position value at each checked candle close
+    pub fn equity(&self) -> &[EquityPoint] {
+        &self.equity
+    }
+
     pub fn trades(&self) -> Vec<TradeOperation> {
         self.trade_operations.clone()
     }
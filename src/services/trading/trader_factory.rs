@@ -1,5 +1,6 @@
 use super::{
-    trader::Trader, trader_register::TraderRegister, trend::trend_provider::TrendProvider,
+    execution::Execution, trader::Trader, trader_register::TraderRegister,
+    trend::trend_provider::TrendProvider,
 };
 use crate::config::candles_selection::CandlesSelection;
 use crate::services::provider::candles_provider_buffer::CandlesProviderBuffer;
@@ -26,6 +27,7 @@ impl TraderFactory {
         &self,
         trend_provider: T,
         trader_register: TraderRegister,
+        execution: Execution,
     ) -> Trader<T> {
         let mut candles_provider = self.candles_provider.clone();
         candles_provider.set_candles_selection(self.candles_selection);
@@ -37,6 +39,7 @@ impl TraderFactory {
             indicator_provider,
             candles_provider,
             trader_register,
+            execution,
         )
     }
 }
@@ -1,10 +1,16 @@
 use super::trade_operation::TradeOperation;
+use crate::model::candle::Candle;
+use crate::model::funding_rate::FundingRate;
+use crate::model::order::OrderAction;
 use crate::services::script::position_register::PositionRegister;
+use std::collections::VecDeque;
 
 #[derive(Clone)]
 pub struct TraderRegister {
     position_register: PositionRegister,
     trades: Vec<TradeOperation>,
+    /// Funding rates not reached yet, in time order
+    funding_rates: VecDeque<FundingRate>,
 }
 
 impl TraderRegister {
@@ -12,16 +18,55 @@ impl TraderRegister {
         Self {
             position_register,
             trades: Vec::new(),
+            funding_rates: VecDeque::new(),
         }
     }
 
-    /// Update profit from new operation
-    pub fn register(&mut self, trade_operation: TradeOperation) -> eyre::Result<()> {
-        self.position_register.register(&trade_operation)?;
-        self.trades.push(trade_operation);
+    pub fn set_funding_rates(&mut self, funding_rates: Vec<FundingRate>) {
+        self.funding_rates = funding_rates.into();
+    }
+
+    /// Counts the candle and applies the funding rates reached at its open
+    pub fn next_candle(&mut self, candle: &Candle) -> eyre::Result<()> {
+        self.position_register.bar += 1;
+        while let Some(funding_rate) = self.funding_rates.front() {
+            if funding_rate.time > candle.open_time {
+                break;
+            }
+            self.position_register
+                .apply_funding(funding_rate, candle.open)?;
+            self.funding_rates.pop_front();
+        }
         Ok(())
     }
 
+    /// Update profit from new operation filled in the candle, returns the filled operation or
+    /// None when rejected
+    pub fn register(
+        &mut self,
+        trade_operation: &TradeOperation,
+        candle: &Candle,
+    ) -> eyre::Result<Option<TradeOperation>> {
+        let filled_opt = self.position_register.register(trade_operation, candle)?;
+        self.trades.extend(filled_opt.iter().cloned());
+        Ok(filled_opt)
+    }
+
+    /// Fills the pending orders crossed by the path inside the candle
+    pub fn fill_orders(
+        &mut self,
+        candle: &Candle,
+        path: &[Candle],
+    ) -> eyre::Result<Vec<TradeOperation>> {
+        let trade_operations = self.position_register.fill_orders(candle, path)?;
+        self.trades.extend(trade_operations.iter().cloned());
+        Ok(trade_operations)
+    }
+
+    pub fn apply_order(&mut self, order_action: OrderAction) {
+        self.position_register.order_book.apply(order_action);
+    }
+
     pub fn position_register(&self) -> &PositionRegister {
         &self.position_register
     }
@@ -8,3 +8,61 @@ pub fn min_max_candles(candles: &[&Candle]) -> (f64, f64) {
     let min = candles.iter().fold(max, |acc, t| acc.min(t.low));
     (min.to_f64().unwrap(), max.to_f64().unwrap())
 }
+
+#[cfg(test)]
+pub mod tests {
+    use crate::model::candle::Candle;
+    use crate::utils::date_utils::str_to_datetime;
+    use chrono::Duration;
+    use rust_decimal::Decimal;
+
+    /// Candle of `minutes` opened `index` candles after 2020-01-12 12:00:00
+    pub fn timeframe_candle(
+        minutes: i64,
+        index: i64,
+        open: Decimal,
+        high: Decimal,
+        low: Decimal,
+        close: Decimal,
+        volume: Decimal,
+    ) -> Candle {
+        let open_time = str_to_datetime("2020-01-12 12:00:00") + Duration::minutes(minutes * index);
+        Candle {
+            id: index as i32,
+            open_time,
+            close_time: open_time + Duration::minutes(minutes) - Duration::seconds(1),
+            symbol: 1,
+            minutes: minutes as i32,
+            open,
+            high,
+            low,
+            close,
+            volume,
+        }
+    }
+
+    /// 15 minutes candle opened `index` candles after 2020-01-12 12:00:00
+    pub fn candle_at(
+        index: i64,
+        open: Decimal,
+        high: Decimal,
+        low: Decimal,
+        close: Decimal,
+        volume: Decimal,
+    ) -> Candle {
+        timeframe_candle(15, index, open, high, low, close, volume)
+    }
+
+    /// First 15 minutes candle, opened at its close
+    pub fn candle(high: i64, low: i64, close: i64, volume: i64) -> Candle {
+        let close = Decimal::from(close);
+        candle_at(
+            0,
+            close,
+            Decimal::from(high),
+            Decimal::from(low),
+            close,
+            Decimal::from(volume),
+        )
+    }
+}